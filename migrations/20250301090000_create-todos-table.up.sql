@@ -0,0 +1,13 @@
+-- Add up migration script here
+CREATE TABLE todos (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  title VARCHAR(255) NOT NULL,
+  notes TEXT,
+  due_at TIMESTAMP WITH TIME ZONE,
+  completed_at TIMESTAMP WITH TIME ZONE,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now())
+);
+
+CREATE INDEX todos_user_id_idx ON todos (user_id);
@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE recurrences (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  rrule TEXT NOT NULL,
+  timezone VARCHAR(64) NOT NULL,
+  dtstart TIMESTAMP WITH TIME ZONE NOT NULL,
+  stopped_at TIMESTAMP WITH TIME ZONE,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now())
+);
+
+ALTER TABLE todos
+ADD COLUMN series_id UUID REFERENCES recurrences (id) ON DELETE SET NULL;
+
+CREATE INDEX todos_series_id_idx ON todos (series_id);
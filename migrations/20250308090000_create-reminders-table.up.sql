@@ -0,0 +1,22 @@
+-- Add up migration script here
+CREATE TABLE reminders (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  todo_id UUID NOT NULL REFERENCES todos (id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  channel VARCHAR(16) NOT NULL DEFAULT 'log',
+  remind_at TIMESTAMP WITH TIME ZONE,
+  minutes_before INTEGER,
+  fire_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  attempts INTEGER NOT NULL DEFAULT 0,
+  last_error TEXT,
+  sent_at TIMESTAMP WITH TIME ZONE,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  -- A reminder is either absolute or relative to the due date of its todo
+  CONSTRAINT reminders_kind_check CHECK ((remind_at IS NULL) <> (minutes_before IS NULL))
+);
+
+CREATE INDEX reminders_todo_id_idx ON reminders (todo_id);
+
+CREATE INDEX reminders_pending_idx ON reminders (fire_at)
+WHERE
+  sent_at IS NULL;
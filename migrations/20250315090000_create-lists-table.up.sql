@@ -0,0 +1,19 @@
+-- Add up migration script here
+CREATE TABLE lists (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  name VARCHAR(100) NOT NULL,
+  -- Fractional index, compared byte by byte
+  position VARCHAR(64) COLLATE "C" NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now())
+);
+
+CREATE INDEX lists_user_id_position_idx ON lists (user_id, position);
+
+-- Todos without a list are in the user's Inbox
+ALTER TABLE todos
+ADD COLUMN list_id UUID REFERENCES lists (id) ON DELETE CASCADE,
+ADD COLUMN position VARCHAR(64) COLLATE "C" NOT NULL DEFAULT 'V';
+
+CREATE INDEX todos_user_id_list_id_position_idx ON todos (user_id, list_id, position);
@@ -0,0 +1,10 @@
+-- Add up migration script here
+CREATE TABLE todo_dependencies (
+  todo_id UUID NOT NULL REFERENCES todos (id) ON DELETE CASCADE,
+  depends_on_id UUID NOT NULL REFERENCES todos (id) ON DELETE CASCADE,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  PRIMARY KEY (todo_id, depends_on_id),
+  CONSTRAINT todo_dependencies_self_check CHECK (todo_id <> depends_on_id)
+);
+
+CREATE INDEX todo_dependencies_depends_on_id_idx ON todo_dependencies (depends_on_id);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE todos
+DROP COLUMN IF EXISTS status_id;
+
+DROP TABLE IF EXISTS "board_columns";
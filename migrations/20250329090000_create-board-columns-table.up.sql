@@ -0,0 +1,24 @@
+-- Add up migration script here
+CREATE TABLE board_columns (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  list_id UUID NOT NULL REFERENCES lists (id) ON DELETE CASCADE,
+  name VARCHAR(100) NOT NULL,
+  -- Fractional index, compared byte by byte
+  position VARCHAR(64) COLLATE "C" NOT NULL,
+  wip_limit INTEGER CHECK (wip_limit > 0),
+  is_done BOOLEAN NOT NULL DEFAULT false,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now())
+);
+
+CREATE INDEX board_columns_list_id_position_idx ON board_columns (list_id, position);
+
+-- A list has at most one done column
+CREATE UNIQUE INDEX board_columns_done_idx ON board_columns (list_id)
+WHERE
+  is_done;
+
+ALTER TABLE todos
+ADD COLUMN status_id UUID REFERENCES board_columns (id) ON DELETE SET NULL;
+
+CREATE INDEX todos_status_id_idx ON todos (status_id);
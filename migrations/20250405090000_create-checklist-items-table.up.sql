@@ -0,0 +1,13 @@
+-- Add up migration script here
+CREATE TABLE checklist_items (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  todo_id UUID NOT NULL REFERENCES todos (id) ON DELETE CASCADE,
+  text VARCHAR(500) NOT NULL,
+  checked BOOLEAN NOT NULL DEFAULT false,
+  -- Fractional index, compared byte by byte
+  position VARCHAR(64) COLLATE "C" NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now())
+);
+
+CREATE INDEX checklist_items_todo_id_position_idx ON checklist_items (todo_id, position);
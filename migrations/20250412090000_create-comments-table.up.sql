@@ -0,0 +1,25 @@
+-- Add up migration script here
+CREATE TABLE comments (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  todo_id UUID NOT NULL REFERENCES todos (id) ON DELETE CASCADE,
+  author_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  body TEXT NOT NULL,
+  edited_at TIMESTAMP WITH TIME ZONE,
+  deleted_at TIMESTAMP WITH TIME ZONE,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now())
+);
+
+CREATE INDEX comments_todo_id_created_at_idx ON comments (todo_id, created_at);
+
+CREATE TABLE comment_mentions (
+  comment_id UUID NOT NULL REFERENCES comments (id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  notified_at TIMESTAMP WITH TIME ZONE,
+  attempts INTEGER NOT NULL DEFAULT 0,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  PRIMARY KEY (comment_id, user_id)
+);
+
+CREATE INDEX comment_mentions_pending_idx ON comment_mentions (created_at)
+WHERE
+  notified_at IS NULL;
@@ -0,0 +1,16 @@
+-- Add up migration script here
+CREATE TABLE attachments (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  todo_id UUID NOT NULL REFERENCES todos (id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  filename VARCHAR(255) NOT NULL,
+  content_type VARCHAR(255) NOT NULL,
+  size BIGINT NOT NULL,
+  -- Hex SHA-256 of the contents. Attachments with the same checksum share one stored blob.
+  checksum CHAR(64) NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now())
+);
+
+CREATE INDEX attachments_todo_id_idx ON attachments (todo_id);
+
+CREATE INDEX attachments_checksum_idx ON attachments (checksum);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE lists
+DROP COLUMN IF EXISTS deleted_at;
+
+ALTER TABLE todos
+DROP COLUMN IF EXISTS deleted_at;
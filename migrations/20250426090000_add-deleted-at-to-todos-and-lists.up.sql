@@ -0,0 +1,15 @@
+-- Add up migration script here
+ALTER TABLE todos
+ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE lists
+ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
+
+-- The trash, and the rows the purge looks at
+CREATE INDEX todos_deleted_at_idx ON todos (user_id, deleted_at)
+WHERE
+  deleted_at IS NOT NULL;
+
+CREATE INDEX lists_deleted_at_idx ON lists (user_id, deleted_at)
+WHERE
+  deleted_at IS NOT NULL;
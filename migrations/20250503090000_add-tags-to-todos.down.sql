@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS todos_tags_idx;
+
+ALTER TABLE todos
+DROP COLUMN IF EXISTS tags;
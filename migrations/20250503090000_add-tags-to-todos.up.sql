@@ -0,0 +1,5 @@
+-- Add up migration script here
+ALTER TABLE todos
+ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX todos_tags_idx ON todos USING GIN (tags);
@@ -0,0 +1,16 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS todos_search_language ON todos;
+
+DROP FUNCTION IF EXISTS todos_search_language;
+
+DROP INDEX IF EXISTS todos_search_idx;
+
+ALTER TABLE todos
+DROP COLUMN IF EXISTS search;
+
+ALTER TABLE todos
+DROP COLUMN IF EXISTS search_language;
+
+DROP FUNCTION IF EXISTS todo_tags_text;
+
+DROP TABLE IF EXISTS user_settings;
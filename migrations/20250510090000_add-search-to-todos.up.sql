@@ -0,0 +1,38 @@
+-- Add up migration script here
+CREATE TABLE user_settings (
+  user_id UUID PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+  -- Text search configuration the user's todos are indexed and searched with
+  search_language REGCONFIG NOT NULL DEFAULT 'english',
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+-- array_to_string is only stable, which a generated column does not accept
+CREATE FUNCTION todo_tags_text (tags TEXT[]) RETURNS TEXT LANGUAGE sql IMMUTABLE AS $$
+  SELECT array_to_string(tags, ' ')
+$$;
+
+ALTER TABLE todos
+ADD COLUMN search_language REGCONFIG NOT NULL DEFAULT 'english';
+
+ALTER TABLE todos
+ADD COLUMN search TSVECTOR GENERATED ALWAYS AS (
+  setweight(to_tsvector(search_language, title), 'A') ||
+  setweight(to_tsvector(search_language, todo_tags_text (tags)), 'A') ||
+  setweight(to_tsvector(search_language, coalesce(notes, '')), 'B')
+) STORED;
+
+CREATE INDEX todos_search_idx ON todos USING GIN (search);
+
+-- New todos are indexed in the language of their owner
+CREATE FUNCTION todos_search_language () RETURNS TRIGGER LANGUAGE plpgsql AS $$
+BEGIN
+  NEW.search_language := COALESCE(
+    (SELECT search_language FROM user_settings WHERE user_id = NEW.user_id),
+    'english'
+  );
+  RETURN NEW;
+END
+$$;
+
+CREATE TRIGGER todos_search_language BEFORE INSERT ON todos FOR EACH ROW
+EXECUTE FUNCTION todos_search_language ();
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS saved_view_shares;
+
+DROP TABLE IF EXISTS saved_views;
@@ -0,0 +1,26 @@
+-- Add up migration script here
+CREATE TABLE saved_views (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  name VARCHAR(255) NOT NULL,
+  -- In the filter query language, empty for all todos
+  filter TEXT NOT NULL DEFAULT '',
+  sort VARCHAR(16) NOT NULL DEFAULT 'position',
+  descending BOOLEAN NOT NULL DEFAULT false,
+  group_by VARCHAR(16),
+  -- Options for clients, such as whether to show completed todos
+  display JSONB NOT NULL DEFAULT '{}',
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now())
+);
+
+CREATE INDEX saved_views_user_id_idx ON saved_views (user_id);
+
+CREATE TABLE saved_view_shares (
+  view_id UUID NOT NULL REFERENCES saved_views (id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  PRIMARY KEY (view_id, user_id)
+);
+
+CREATE INDEX saved_view_shares_user_id_idx ON saved_view_shares (user_id);
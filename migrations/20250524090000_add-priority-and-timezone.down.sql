@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE user_settings
+DROP COLUMN IF EXISTS timezone;
+
+ALTER TABLE todos
+DROP COLUMN IF EXISTS priority;
@@ -0,0 +1,7 @@
+-- Add up migration script here
+ALTER TABLE todos
+ADD COLUMN priority VARCHAR(8);
+
+-- IANA timezone dates written by the user are read in
+ALTER TABLE user_settings
+ADD COLUMN timezone VARCHAR(64) NOT NULL DEFAULT 'UTC';
@@ -0,0 +1,12 @@
+-- Add up migration script here
+CREATE TABLE templates (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  name VARCHAR(255) NOT NULL,
+  -- The list and todos to create, with placeholders such as {{name}}
+  body JSONB NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now())
+);
+
+CREATE INDEX templates_user_id_idx ON templates (user_id);
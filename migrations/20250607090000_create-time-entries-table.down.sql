@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE todos
+DROP COLUMN IF EXISTS estimate_minutes;
+
+DROP TABLE IF EXISTS time_entries;
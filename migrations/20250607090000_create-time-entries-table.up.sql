@@ -0,0 +1,25 @@
+-- Add up migration script here
+CREATE TABLE time_entries (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  todo_id UUID NOT NULL REFERENCES todos (id) ON DELETE CASCADE,
+  started_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  -- NULL while the timer is running
+  ended_at TIMESTAMP WITH TIME ZONE,
+  note TEXT,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  CONSTRAINT time_entries_range_check CHECK (ended_at IS NULL OR ended_at >= started_at)
+);
+
+CREATE INDEX time_entries_todo_id_idx ON time_entries (todo_id);
+
+CREATE INDEX time_entries_user_id_started_at_idx ON time_entries (user_id, started_at);
+
+-- A user has at most one running timer
+CREATE UNIQUE INDEX time_entries_running_idx ON time_entries (user_id)
+WHERE
+  ended_at IS NULL;
+
+ALTER TABLE todos
+ADD COLUMN estimate_minutes INTEGER;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE todos
+ADD COLUMN notes_html TEXT;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS todos_revision ON todos;
+
+DROP FUNCTION IF EXISTS todos_revision;
+
+DROP TABLE IF EXISTS todo_revisions;
@@ -0,0 +1,63 @@
+-- Add up migration script here
+CREATE TABLE todo_revisions (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  todo_id UUID NOT NULL REFERENCES todos (id) ON DELETE CASCADE,
+  actor_id UUID REFERENCES users (id) ON DELETE SET NULL,
+  -- Numbers the revisions of a todo from 1
+  revision INTEGER NOT NULL,
+  -- The changed fields, as {"field": {"old": ..., "new": ...}}
+  changes JSONB NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  UNIQUE (todo_id, revision)
+);
+
+CREATE INDEX todo_revisions_created_at_idx ON todo_revisions (created_at);
+
+-- Record the fields of a new todo and the changed fields of every update, in the transaction
+-- making them. Todos are only changed by their owner. The lock an update holds on the row keeps
+-- revision numbers unique.
+CREATE FUNCTION todos_revision () RETURNS TRIGGER LANGUAGE plpgsql AS $$
+DECLARE
+  fields TEXT[] := ARRAY[
+    ['title', 'title'],
+    ['notes', 'notes'],
+    ['due_at', 'dueAt'],
+    ['completed_at', 'completedAt'],
+    ['list_id', 'listId'],
+    ['status_id', 'statusId'],
+    ['tags', 'tags'],
+    ['priority', 'priority'],
+    ['estimate_minutes', 'estimateMinutes'],
+    ['deleted_at', 'deletedAt']
+  ];
+  field TEXT[];
+  old_row JSONB := COALESCE(to_jsonb(OLD), '{}');
+  new_row JSONB := to_jsonb(NEW);
+  changes JSONB := '{}';
+BEGIN
+  FOREACH field SLICE 1 IN ARRAY fields LOOP
+    -- A new todo has no old values, which a `null` it is created with does not change
+    IF COALESCE(old_row -> field[1], 'null') IS DISTINCT FROM new_row -> field[1] THEN
+      changes := changes || jsonb_build_object(
+        field[2],
+        jsonb_build_object('old', COALESCE(old_row -> field[1], 'null'), 'new', new_row -> field[1])
+      );
+    END IF;
+  END LOOP;
+
+  IF changes <> '{}' THEN
+    INSERT INTO todo_revisions (todo_id, actor_id, revision, changes)
+    VALUES (
+      NEW.id,
+      NEW.user_id,
+      COALESCE((SELECT max(revision) FROM todo_revisions WHERE todo_id = NEW.id), 0) + 1,
+      changes
+    );
+  END IF;
+
+  RETURN NULL;
+END
+$$;
+
+CREATE TRIGGER todos_revision AFTER INSERT OR UPDATE ON todos FOR EACH ROW
+EXECUTE FUNCTION todos_revision ();
@@ -0,0 +1,10 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS lists_undo ON lists;
+
+DROP TRIGGER IF EXISTS todos_undo ON todos;
+
+DROP FUNCTION IF EXISTS record_undo;
+
+DROP TABLE IF EXISTS undo_changes;
+
+DROP TABLE IF EXISTS undo_operations;
@@ -0,0 +1,60 @@
+-- Add up migration script here
+-- A transaction that changed todos or lists of a user, which the user can undo
+CREATE TABLE undo_operations (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  txid BIGINT NOT NULL,
+  undone_at TIMESTAMP WITH TIME ZONE,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  UNIQUE (user_id, txid)
+);
+
+CREATE INDEX undo_operations_created_at_idx ON undo_operations (created_at);
+
+-- The rows an operation changed, as they were before and after it
+CREATE TABLE undo_changes (
+  operation_id UUID NOT NULL REFERENCES undo_operations (id) ON DELETE CASCADE,
+  table_name VARCHAR(16) NOT NULL,
+  row_id UUID NOT NULL,
+  -- NULL for a row the operation created
+  before JSONB,
+  after JSONB NOT NULL,
+  PRIMARY KEY (operation_id, table_name, row_id)
+);
+
+-- Record every change to a todo or a list in the operation of its transaction. A new operation
+-- clears what the user could redo. Undoing and redoing set `todos.undoing` to change rows without
+-- recording them.
+CREATE FUNCTION record_undo () RETURNS TRIGGER LANGUAGE plpgsql AS $$
+DECLARE
+  operation UUID;
+BEGIN
+  IF current_setting('todos.undoing', true) = 'on'
+    OR to_jsonb(OLD) - 'updated_at' = to_jsonb(NEW) - 'updated_at' THEN
+    RETURN NULL;
+  END IF;
+
+  INSERT INTO undo_operations (user_id, txid) VALUES (NEW.user_id, txid_current())
+  ON CONFLICT (user_id, txid) DO NOTHING
+  RETURNING id INTO operation;
+
+  IF operation IS NULL THEN
+    SELECT id INTO operation FROM undo_operations
+    WHERE user_id = NEW.user_id AND txid = txid_current();
+  ELSE
+    DELETE FROM undo_operations WHERE user_id = NEW.user_id AND undone_at IS NOT NULL;
+  END IF;
+
+  INSERT INTO undo_changes (operation_id, table_name, row_id, before, after)
+  VALUES (operation, TG_TABLE_NAME, NEW.id, to_jsonb(OLD), to_jsonb(NEW))
+  ON CONFLICT (operation_id, table_name, row_id) DO UPDATE SET after = EXCLUDED.after;
+
+  RETURN NULL;
+END
+$$;
+
+CREATE TRIGGER todos_undo AFTER INSERT OR UPDATE ON todos FOR EACH ROW
+EXECUTE FUNCTION record_undo ();
+
+CREATE TRIGGER lists_undo AFTER INSERT OR UPDATE ON lists FOR EACH ROW
+EXECUTE FUNCTION record_undo ();
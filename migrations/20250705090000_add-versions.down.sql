@@ -0,0 +1,65 @@
+-- Add down migration script here
+CREATE OR REPLACE FUNCTION record_undo () RETURNS TRIGGER LANGUAGE plpgsql AS $$
+DECLARE
+  operation UUID;
+BEGIN
+  IF current_setting('todos.undoing', true) = 'on'
+    OR to_jsonb(OLD) - 'updated_at' = to_jsonb(NEW) - 'updated_at' THEN
+    RETURN NULL;
+  END IF;
+
+  INSERT INTO undo_operations (user_id, txid) VALUES (NEW.user_id, txid_current())
+  ON CONFLICT (user_id, txid) DO NOTHING
+  RETURNING id INTO operation;
+
+  IF operation IS NULL THEN
+    SELECT id INTO operation FROM undo_operations
+    WHERE user_id = NEW.user_id AND txid = txid_current();
+  ELSE
+    DELETE FROM undo_operations WHERE user_id = NEW.user_id AND undone_at IS NOT NULL;
+  END IF;
+
+  INSERT INTO undo_changes (operation_id, table_name, row_id, before, after)
+  VALUES (operation, TG_TABLE_NAME, NEW.id, to_jsonb(OLD), to_jsonb(NEW))
+  ON CONFLICT (operation_id, table_name, row_id) DO UPDATE SET after = EXCLUDED.after;
+
+  RETURN NULL;
+END
+$$;
+
+DROP TRIGGER IF EXISTS time_entries_version ON time_entries;
+
+DROP TRIGGER IF EXISTS saved_views_version ON saved_views;
+
+DROP TRIGGER IF EXISTS templates_version ON templates;
+
+DROP TRIGGER IF EXISTS comments_version ON comments;
+
+DROP TRIGGER IF EXISTS checklist_items_version ON checklist_items;
+
+DROP TRIGGER IF EXISTS lists_version ON lists;
+
+DROP TRIGGER IF EXISTS todos_version ON todos;
+
+DROP FUNCTION IF EXISTS bump_version;
+
+ALTER TABLE time_entries
+DROP COLUMN IF EXISTS version;
+
+ALTER TABLE saved_views
+DROP COLUMN IF EXISTS version;
+
+ALTER TABLE templates
+DROP COLUMN IF EXISTS version;
+
+ALTER TABLE comments
+DROP COLUMN IF EXISTS version;
+
+ALTER TABLE checklist_items
+DROP COLUMN IF EXISTS version;
+
+ALTER TABLE lists
+DROP COLUMN IF EXISTS version;
+
+ALTER TABLE todos
+DROP COLUMN IF EXISTS version;
@@ -0,0 +1,84 @@
+-- Add up migration script here
+-- Counts the changes to a row, for clients to tell whether theirs is still current
+ALTER TABLE todos
+ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+ALTER TABLE lists
+ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+ALTER TABLE checklist_items
+ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+ALTER TABLE comments
+ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+ALTER TABLE templates
+ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+ALTER TABLE saved_views
+ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+ALTER TABLE time_entries
+ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+-- Only a change clients can see is a new version: neither touching updated_at nor reindexing in
+-- another search language is one
+CREATE FUNCTION bump_version () RETURNS TRIGGER LANGUAGE plpgsql AS $$
+BEGIN
+  IF to_jsonb(NEW) - 'updated_at' - 'search_language' - 'search'
+    IS DISTINCT FROM to_jsonb(OLD) - 'updated_at' - 'search_language' - 'search' THEN
+    NEW.version := OLD.version + 1;
+  END IF;
+  RETURN NEW;
+END
+$$;
+
+CREATE TRIGGER todos_version BEFORE UPDATE ON todos FOR EACH ROW
+EXECUTE FUNCTION bump_version ();
+
+CREATE TRIGGER lists_version BEFORE UPDATE ON lists FOR EACH ROW
+EXECUTE FUNCTION bump_version ();
+
+CREATE TRIGGER checklist_items_version BEFORE UPDATE ON checklist_items FOR EACH ROW
+EXECUTE FUNCTION bump_version ();
+
+CREATE TRIGGER comments_version BEFORE UPDATE ON comments FOR EACH ROW
+EXECUTE FUNCTION bump_version ();
+
+CREATE TRIGGER templates_version BEFORE UPDATE ON templates FOR EACH ROW
+EXECUTE FUNCTION bump_version ();
+
+CREATE TRIGGER saved_views_version BEFORE UPDATE ON saved_views FOR EACH ROW
+EXECUTE FUNCTION bump_version ();
+
+CREATE TRIGGER time_entries_version BEFORE UPDATE ON time_entries FOR EACH ROW
+EXECUTE FUNCTION bump_version ();
+
+-- A version bump alone is not a change to undo
+CREATE OR REPLACE FUNCTION record_undo () RETURNS TRIGGER LANGUAGE plpgsql AS $$
+DECLARE
+  operation UUID;
+BEGIN
+  IF current_setting('todos.undoing', true) = 'on'
+    OR to_jsonb(OLD) - 'updated_at' - 'version' = to_jsonb(NEW) - 'updated_at' - 'version' THEN
+    RETURN NULL;
+  END IF;
+
+  INSERT INTO undo_operations (user_id, txid) VALUES (NEW.user_id, txid_current())
+  ON CONFLICT (user_id, txid) DO NOTHING
+  RETURNING id INTO operation;
+
+  IF operation IS NULL THEN
+    SELECT id INTO operation FROM undo_operations
+    WHERE user_id = NEW.user_id AND txid = txid_current();
+  ELSE
+    DELETE FROM undo_operations WHERE user_id = NEW.user_id AND undone_at IS NOT NULL;
+  END IF;
+
+  INSERT INTO undo_changes (operation_id, table_name, row_id, before, after)
+  VALUES (operation, TG_TABLE_NAME, NEW.id, to_jsonb(OLD), to_jsonb(NEW))
+  ON CONFLICT (operation_id, table_name, row_id) DO UPDATE SET after = EXCLUDED.after;
+
+  RETURN NULL;
+END
+$$;
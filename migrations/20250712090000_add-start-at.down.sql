@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS todos_start_at_idx;
+
+ALTER TABLE todos
+DROP COLUMN IF EXISTS notify_on_start,
+DROP COLUMN IF EXISTS start_at;
@@ -0,0 +1,9 @@
+-- Add up migration script here
+-- Snoozed todos stay out of active views until they start
+ALTER TABLE todos
+ADD COLUMN start_at TIMESTAMPTZ,
+ADD COLUMN notify_on_start BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX todos_start_at_idx ON todos (start_at)
+WHERE
+  start_at IS NOT NULL;
@@ -0,0 +1,10 @@
+-- Add down migration script here
+ALTER TABLE saved_views
+DROP COLUMN IF EXISTS sort_field;
+
+DROP INDEX IF EXISTS todos_fields_idx;
+
+ALTER TABLE todos
+DROP COLUMN IF EXISTS fields;
+
+DROP TABLE IF EXISTS custom_fields;
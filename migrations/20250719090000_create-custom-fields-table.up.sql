@@ -0,0 +1,28 @@
+-- Add up migration script here
+CREATE TABLE custom_fields (
+  id UUID PRIMARY KEY NOT NULL DEFAULT (gen_random_uuid ()),
+  list_id UUID NOT NULL REFERENCES lists (id) ON DELETE CASCADE,
+  name VARCHAR(100) NOT NULL,
+  -- The name in lowercase words joined by underscores, the key of values in todos.fields
+  key VARCHAR(100) NOT NULL,
+  kind VARCHAR(16) NOT NULL CHECK (kind IN ('text', 'number', 'date', 'select', 'checkbox')),
+  -- The choices of a select field
+  options TEXT[] NOT NULL DEFAULT '{}',
+  version INTEGER NOT NULL DEFAULT 1,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  UNIQUE (list_id, key)
+);
+
+CREATE TRIGGER custom_fields_version BEFORE UPDATE ON custom_fields FOR EACH ROW
+EXECUTE FUNCTION bump_version ();
+
+-- Values of the custom fields of the list of a todo, by key
+ALTER TABLE todos
+ADD COLUMN fields JSONB NOT NULL DEFAULT '{}';
+
+-- Serves filters on a field value, which compile to containment
+CREATE INDEX todos_fields_idx ON todos USING gin (fields jsonb_path_ops);
+
+ALTER TABLE saved_views
+ADD COLUMN sort_field VARCHAR(100);
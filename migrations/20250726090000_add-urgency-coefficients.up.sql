@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- Coefficients of the urgency score that differ from the defaults
+ALTER TABLE user_settings
+ADD COLUMN urgency JSONB NOT NULL DEFAULT '{}';
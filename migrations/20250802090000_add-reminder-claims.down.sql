@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE reminders
+DROP COLUMN IF EXISTS claimed_at;
@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- When a scheduler took the reminder for delivery. Others leave it alone until the lease runs out.
+ALTER TABLE reminders
+ADD COLUMN claimed_at TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,10 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS user_settings_version ON user_settings;
+
+DROP TRIGGER IF EXISTS board_columns_version ON board_columns;
+
+ALTER TABLE user_settings
+DROP COLUMN IF EXISTS version;
+
+ALTER TABLE board_columns
+DROP COLUMN IF EXISTS version;
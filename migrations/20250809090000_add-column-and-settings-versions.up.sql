@@ -0,0 +1,13 @@
+-- Add up migration script here
+ALTER TABLE board_columns
+ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+-- Settings that were never changed, and have no row, are at version 0
+ALTER TABLE user_settings
+ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+CREATE TRIGGER board_columns_version BEFORE UPDATE ON board_columns FOR EACH ROW
+EXECUTE FUNCTION bump_version ();
+
+CREATE TRIGGER user_settings_version BEFORE UPDATE ON user_settings FOR EACH ROW
+EXECUTE FUNCTION bump_version ();
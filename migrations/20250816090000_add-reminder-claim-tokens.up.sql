@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- Which scheduler holds the claim. It renews claimed_at before each delivery, and only while the
+-- claim is still its own.
+ALTER TABLE reminders
+ADD COLUMN claim_token UUID;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE comment_mentions
+DROP COLUMN IF EXISTS claim_token,
+DROP COLUMN IF EXISTS claimed_at;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- When and under which token the mention worker took the mention, as for reminders.
+ALTER TABLE comment_mentions
+ADD COLUMN claimed_at TIMESTAMP WITH TIME ZONE,
+ADD COLUMN claim_token UUID;
@@ -0,0 +1,9 @@
+-- Add down migration script here
+CREATE TABLE saved_view_shares (
+  view_id UUID NOT NULL REFERENCES saved_views (id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (now()),
+  PRIMARY KEY (view_id, user_id)
+);
+
+CREATE INDEX saved_view_shares_user_id_idx ON saved_view_shares (user_id);
@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- Views run on the todos of whoever opens them, and lists cannot be shared yet, so sharing a view
+-- shared nothing. It can come back together with access to other users' lists.
+DROP TABLE IF EXISTS saved_view_shares;
@@ -0,0 +1,37 @@
+-- Add up migration script here
+-- Name the #todo-<uuid> references of rendered notes after the current titles of their todos,
+-- among the todos of `owner`. References to todos that are gone lose their link. Rendered notes
+-- store each reference as a link labelled with the reference itself.
+CREATE FUNCTION link_todo_references (html TEXT, owner UUID) RETURNS TEXT LANGUAGE plpgsql STABLE AS $$
+DECLARE
+  reference RECORD;
+BEGIN
+  IF html IS NULL OR strpos(html, '">#todo-') = 0 THEN
+    RETURN html;
+  END IF;
+
+  FOR reference IN
+    SELECT r.id, t.title FROM (
+      SELECT DISTINCT m[1] AS id FROM regexp_matches(
+        html, '<a href="/todos/([0-9a-f-]{36})" rel="noopener noreferrer">#todo-\1</a>', 'g'
+      ) AS m
+    ) r
+    LEFT JOIN todos t ON t.id = r.id::uuid AND t.user_id = owner AND t.deleted_at IS NULL
+  LOOP
+    html := replace(
+      html,
+      format('<a href="/todos/%s" rel="noopener noreferrer">#todo-%s</a>', reference.id, reference.id),
+      CASE
+        WHEN reference.title IS NULL THEN '#todo-' || reference.id
+        ELSE format(
+          '<a href="/todos/%s" rel="noopener noreferrer">%s</a>',
+          reference.id,
+          replace(replace(replace(reference.title, '&', '&amp;'), '<', '&lt;'), '>', '&gt;')
+        )
+      END
+    );
+  END LOOP;
+
+  RETURN html;
+END
+$$;
@@ -15,9 +15,13 @@ use crate::{
         app::{AppConfig, AppEnvironment},
         state::AppContext,
     },
-    controllers::auth,
+    controllers::{
+        attachments, auth, boards, checklists, comments, custom_fields, dependencies, history,
+        lists, reminders, search, settings, templates, time_entries, todos, trash, undo, views,
+    },
     error::Result as AppResult,
     tracing::http,
+    workers,
 };
 
 /// Configuration details of our web server.
@@ -61,15 +65,41 @@ impl App {
             .on_request(http::on_request)
             .on_response(http::on_response);
 
-        let ctx = AppContext::new(&config).await?;
+        let ctx = Arc::new(AppContext::new(&config).await?);
+
+        workers::spawn(ctx.clone());
 
         let app = Router::new()
             .route("/", get(hello))
             .route("/health", get(health))
+            .merge(undo::routes())
             .fallback(page_404)
+            .nest("/attachments", attachments::download_routes())
             .nest("/auth", auth::routes())
+            .nest(
+                "/lists",
+                lists::routes()
+                    .merge(boards::routes())
+                    .merge(custom_fields::routes()),
+            )
+            .nest("/search", search::routes())
+            .nest("/settings", settings::routes())
+            .nest(
+                "/todos",
+                todos::routes()
+                    .merge(reminders::routes())
+                    .merge(dependencies::routes())
+                    .merge(checklists::routes())
+                    .merge(comments::routes())
+                    .merge(attachments::routes())
+                    .merge(history::routes()),
+            )
+            .nest("/templates", templates::routes())
+            .nest("/time", time_entries::routes())
+            .nest("/trash", trash::routes())
+            .nest("/views", views::routes())
             .layer(trace_layer)
-            .with_state(Arc::new(ctx));
+            .with_state(ctx);
 
         let listener = match TcpListener::bind(config.server.address()).await {
             Ok(listener) => listener,
@@ -5,7 +5,11 @@ use crate::error::{Error, Result};
 use config::{Config, Environment, File};
 use serde::{Deserialize, Serialize};
 
-use super::{db::DatabaseConfig, telemetry::TelemetryConfig};
+use super::{
+    auth::AuthConfig, db::DatabaseConfig, history::HistoryConfig,
+    notifications::NotificationsConfig, pagination::PaginationConfig, scheduler::SchedulerConfig,
+    storage::StorageConfig, telemetry::TelemetryConfig, trash::TrashConfig, undo::UndoConfig,
+};
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
 pub enum AppEnvironment<'a> {
@@ -49,6 +53,15 @@ pub struct AppConfig {
     pub server: ServerConfig,
     pub database: DatabaseConfig,
     pub telemetry: TelemetryConfig,
+    pub auth: AuthConfig,
+    pub scheduler: SchedulerConfig,
+    pub storage: StorageConfig,
+    pub trash: TrashConfig,
+    pub history: HistoryConfig,
+    pub undo: UndoConfig,
+    pub pagination: PaginationConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
 }
 
 impl AppConfig {
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use jsonwebtoken::{DecodingKey, EncodingKey};
+use serde::Deserialize;
+
+use crate::error::Result;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthConfig {
+    pub access: TokenConfig,
+}
+
+/// Define how access tokens are signed and for how long they are valid.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenConfig {
+    /// PEM encoded RSA private key used to sign tokens
+    pub private_key: PathBuf,
+
+    /// PEM encoded RSA public key used to verify tokens
+    pub public_key: PathBuf,
+
+    /// Seconds
+    pub ttl: i64,
+}
+
+impl TokenConfig {
+    pub fn keys(&self) -> Result<TokenKeys> {
+        let private_key = std::fs::read(&self.private_key)?;
+        let public_key = std::fs::read(&self.public_key)?;
+
+        Ok(TokenKeys {
+            encoding: EncodingKey::from_rsa_pem(&private_key)?,
+            decoding: DecodingKey::from_rsa_pem(&public_key)?,
+            ttl: self.ttl,
+        })
+    }
+}
+
+/// Keys loaded once at start up, so that requests do not hit the file system.
+#[derive(Clone)]
+pub struct TokenKeys {
+    pub encoding: EncodingKey,
+    pub decoding: DecodingKey,
+    pub ttl: i64,
+}
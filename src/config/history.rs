@@ -0,0 +1,11 @@
+use serde::Deserialize;
+
+/// How long the revisions of todos are kept.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryConfig {
+    /// Days a revision is kept before it is purged
+    pub retention: i64,
+
+    /// Seconds between two purges
+    pub interval: u64,
+}
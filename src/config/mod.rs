@@ -1,4 +1,12 @@
 pub mod app;
+pub mod auth;
 pub mod db;
+pub mod history;
+pub mod notifications;
+pub mod pagination;
+pub mod scheduler;
 pub mod state;
+pub mod storage;
 pub mod telemetry;
+pub mod trash;
+pub mod undo;
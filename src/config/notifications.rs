@@ -0,0 +1,29 @@
+use serde::Deserialize;
+
+/// Notification channels. The log channel is always available, the others are only enabled when
+/// configured.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NotificationsConfig {
+    pub email: Option<EmailConfig>,
+    pub webhook: Option<WebhookConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sender address, e.g. `Todos <noreply@example.com>`
+    pub from: String,
+    /// Use STARTTLS. Disable for local mail catchers.
+    pub tls: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+
+    /// Seconds
+    pub timeout: u64,
+}
@@ -0,0 +1,14 @@
+use serde::Deserialize;
+
+/// How listings are split into pages.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaginationConfig {
+    /// Key signing the cursors given to clients
+    pub secret: String,
+
+    /// Items on a page when the client does not ask for a number
+    pub default_limit: i64,
+
+    /// Most items on a page
+    pub max_limit: i64,
+}
@@ -0,0 +1,18 @@
+use serde::Deserialize;
+
+/// Define how often background jobs run and how much work they pick up at once.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SchedulerConfig {
+    /// Seconds between two polls for due reminders
+    pub interval: u64,
+
+    /// Maximum number of reminders claimed per poll
+    pub batch_size: i64,
+
+    /// Number of delivery attempts before a reminder is given up on
+    pub max_attempts: i32,
+
+    /// Seconds a claimed reminder is left to its scheduler before another one may retry it. The
+    /// claim is renewed before each delivery, so this only has to outlast a single one.
+    pub lease: i64,
+}
@@ -1,22 +1,37 @@
+use std::sync::Arc;
+
 use sqlx::PgPool;
 
-use crate::error::Result;
+use crate::{
+    error::Result,
+    notifications::Notifiers,
+    storage::{self, Storage},
+};
 
-use super::app::AppConfig;
+use super::{app::AppConfig, auth::TokenKeys};
 
 #[derive(Clone)]
 pub struct AppContext {
     pub db: PgPool,
     pub config: AppConfig,
+    pub access_keys: TokenKeys,
+    pub notifiers: Notifiers,
+    pub storage: Arc<dyn Storage>,
 }
 
 impl AppContext {
     pub async fn new(cfg: &AppConfig) -> Result<Self> {
         let db = cfg.database.connection_pool().await?;
+        let access_keys = cfg.auth.access.keys()?;
+        let notifiers = Notifiers::new(&cfg.notifications)?;
+        let storage = storage::from_config(&cfg.storage)?;
 
         Ok(Self {
             db,
             config: cfg.clone(),
+            access_keys,
+            notifiers,
+            storage,
         })
     }
 }
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+/// Where attachments are stored and what may be uploaded.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageConfig {
+    pub backend: BackendConfig,
+
+    /// Bytes
+    pub max_size: u64,
+
+    /// Accepted MIME types. `image/*` accepts every image type.
+    pub allowed_types: Vec<String>,
+
+    pub signed_urls: SignedUrlConfig,
+}
+
+impl StorageConfig {
+    pub fn allows(&self, content_type: &str) -> bool {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+
+        self.allowed_types
+            .iter()
+            .any(|allowed| match allowed.strip_suffix("/*") {
+                Some(family) => essence
+                    .split_once('/')
+                    .is_some_and(|(kind, _)| kind == family),
+                None => *allowed == essence,
+            })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BackendConfig {
+    Local { root: PathBuf },
+    S3(S3Config),
+}
+
+/// An S3 compatible object store, addressed with path-style URLs so that MinIO and similar
+/// servers work without DNS setup.
+#[derive(Debug, Clone, Deserialize)]
+pub struct S3Config {
+    /// e.g. `https://s3.eu-west-1.amazonaws.com` or `http://localhost:9000`
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignedUrlConfig {
+    /// Secret the download URLs are signed with.
+    pub secret: String,
+
+    /// Seconds
+    pub ttl: i64,
+}
@@ -55,7 +55,7 @@ impl TelemetryConfig {
                 }
                 // if --directive is specified, don't set a default
                 if self.directives.is_empty() {
-                    EnvFilter::try_new(&format!(
+                    EnvFilter::try_new(format!(
                         "{}={}",
                         env!("CARGO_PKG_NAME").replace('-', "_"),
                         self.log_level()
@@ -0,0 +1,14 @@
+use serde::Deserialize;
+
+/// How long deleted todos and lists stay in the trash.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrashConfig {
+    /// Days an item stays in the trash before it is purged
+    pub retention: i64,
+
+    /// Seconds between two purges
+    pub interval: u64,
+
+    /// Maximum number of todos or lists deleted per transaction
+    pub batch_size: i64,
+}
@@ -0,0 +1,14 @@
+use serde::Deserialize;
+
+/// How far back users can undo their changes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UndoConfig {
+    /// Operations of a user that are kept to undo or redo
+    pub depth: i64,
+
+    /// Seconds an operation can be undone or redone for
+    pub window: i64,
+
+    /// Seconds between two purges of expired operations
+    pub interval: u64,
+}
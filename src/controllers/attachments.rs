@@ -0,0 +1,192 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{header, StatusCode},
+    response::Response,
+    routing::get,
+    Router,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::auth::AuthUser,
+    error::{Error, Result},
+    models::{
+        attachments::{Attachment, Signature},
+        todos::Todo,
+    },
+    storage::Upload,
+};
+
+/// Stream the `file` field of a multipart body to a temporary file, then hand it to the storage
+/// backend. Size and type limits are checked while the body is read.
+async fn upload(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> Result<Response> {
+    let cfg = &ctx.config.storage;
+
+    // Fail before reading the body when the todo does not exist
+    Todo::find_by_id(&ctx.db, user.id, todo_id).await?;
+
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| Error::BadRequest(e.body_text()))?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let filename = field.file_name().unwrap_or("file").to_string();
+        let content_type = field
+            .content_type()
+            .unwrap_or("application/octet-stream")
+            .to_string();
+
+        if !cfg.allows(&content_type) {
+            return Err(Error::UnsupportedMediaType(format!(
+                "Attachments of type `{content_type}` are not allowed"
+            ))
+            .into());
+        }
+
+        let mut upload = Upload::new(cfg.max_size).await?;
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|e| Error::BadRequest(e.body_text()))?
+        {
+            upload.write(&chunk).await?;
+        }
+
+        let attachment = Attachment::create(
+            &ctx.db,
+            ctx.storage.as_ref(),
+            user.id,
+            todo_id,
+            &filename,
+            &content_type,
+            &mut upload,
+        )
+        .await?;
+
+        return Ok(Response::builder()
+            .status(StatusCode::CREATED)
+            .body(Body::from(json!(attachment).to_string()))?);
+    }
+
+    Err(Error::BadRequest("Missing multipart field `file`".into()).into())
+}
+
+async fn list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<Response> {
+    let attachments = Attachment::find_by_todo(&ctx.db, user.id, todo_id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(attachments).to_string()))?)
+}
+
+async fn show(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Response> {
+    let attachment = Attachment::find_by_id(&ctx.db, user.id, todo_id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(attachment).to_string()))?)
+}
+
+async fn remove(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Response> {
+    Attachment::delete(&ctx.db, ctx.storage.as_ref(), user.id, todo_id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+async fn signed_url(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Response> {
+    let url = Attachment::signed_url(
+        &ctx.db,
+        &ctx.config.storage.signed_urls,
+        user.id,
+        todo_id,
+        id,
+    )
+    .await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(url).to_string()))?)
+}
+
+/// Serve the contents behind a signed URL. The signature stands in for the `Authorization`
+/// header, so that links work in `<img>` tags and browser downloads.
+async fn download(
+    State(ctx): State<Arc<AppContext>>,
+    Path(id): Path<Uuid>,
+    Query(signature): Query<Signature>,
+) -> Result<Response> {
+    let (attachment, body) = Attachment::download(
+        &ctx.db,
+        ctx.storage.as_ref(),
+        &ctx.config.storage.signed_urls,
+        id,
+        &signature,
+    )
+    .await?;
+
+    let filename = attachment.filename.replace(['"', '\\'], "_");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, attachment.content_type)
+        .header(header::CONTENT_LENGTH, attachment.size)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{filename}\""),
+        )
+        .body(body)?)
+}
+
+/// Attachment routes below `/todos`.
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route(
+            "/{id}/attachments",
+            get(list)
+                .post(upload)
+                // The size limit is enforced while streaming instead
+                .layer(DefaultBodyLimit::disable()),
+        )
+        .route(
+            "/{id}/attachments/{attachment_id}",
+            get(show).delete(remove),
+        )
+        .route("/{id}/attachments/{attachment_id}/url", get(signed_url))
+}
+
+/// Downloads through signed URLs, below `/attachments`.
+pub fn download_routes() -> Router<Arc<AppContext>> {
+    Router::new().route("/{id}", get(download))
+}
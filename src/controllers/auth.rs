@@ -1,16 +1,51 @@
 use std::sync::Arc;
 
 use axum::{
-    body::Body, extract::State, http::StatusCode, response::Response, routing::post, Json, Router,
+    body::Body,
+    extract::{FromRequestParts, State},
+    http::{header::AUTHORIZATION, request::Parts, StatusCode},
+    response::Response,
+    routing::post,
+    Json, Router,
 };
 use serde_json::json;
+use uuid::Uuid;
 
 use crate::{
     config::state::AppContext,
-    error::Result,
-    models::users::{FilteredUser, RegisterUser, User},
+    error::{AuthError, Report, Result},
+    models::{
+        auth::Claims,
+        users::{FilteredUser, LoginUser, RegisterUser, User},
+    },
 };
 
+/// The user making the request, taken from the `Authorization: Bearer <token>` header.
+#[derive(Debug, Clone, Copy)]
+pub struct AuthUser {
+    pub id: Uuid,
+}
+
+impl FromRequestParts<Arc<AppContext>> for AuthUser {
+    type Rejection = Report;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        ctx: &Arc<AppContext>,
+    ) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(AuthError::MissingCredentials)?;
+
+        let claims = Claims::decode(token, &ctx.access_keys)?;
+
+        Ok(Self { id: claims.sub })
+    }
+}
+
 async fn register(
     State(ctx): State<Arc<AppContext>>,
     Json(dto): Json<RegisterUser<'static>>,
@@ -22,6 +57,20 @@ async fn register(
         .body(Body::from(json!(FilteredUser::from(user)).to_string()))?)
 }
 
+async fn login(
+    State(ctx): State<Arc<AppContext>>,
+    Json(dto): Json<LoginUser<'static>>,
+) -> Result<Response> {
+    let user = User::login(&ctx.db, &dto).await?;
+    let token = Claims::new(user.id, ctx.access_keys.ttl).encode(&ctx.access_keys)?;
+
+    Ok(Response::builder().status(StatusCode::OK).body(Body::from(
+        json!({"token": token, "user": FilteredUser::from(user)}).to_string(),
+    ))?)
+}
+
 pub fn routes() -> Router<Arc<AppContext>> {
-    Router::new().route("/register", post(register))
+    Router::new()
+        .route("/register", post(register))
+        .route("/login", post(login))
 }
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header::ETAG, StatusCode},
+    response::Response,
+    routing::{get, patch, post},
+    Json, Router,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::{
+        auth::AuthUser,
+        etag::{etag, IfMatch},
+    },
+    error::Result,
+    models::boards::{BoardColumn, CreateColumn, MoveColumn, UpdateColumn},
+};
+
+async fn create(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(list_id): Path<Uuid>,
+    Json(dto): Json<CreateColumn<'static>>,
+) -> Result<Response> {
+    let column = BoardColumn::create(&ctx.db, user.id, list_id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header(ETAG, etag(column.version))
+        .body(Body::from(json!(column).to_string()))?)
+}
+
+async fn list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(list_id): Path<Uuid>,
+) -> Result<Response> {
+    let columns = BoardColumn::find_by_list(&ctx.db, user.id, list_id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(columns).to_string()))?)
+}
+
+async fn update(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((list_id, id)): Path<(Uuid, Uuid)>,
+    IfMatch(expected): IfMatch,
+    Json(dto): Json<UpdateColumn<'static>>,
+) -> Result<Response> {
+    let column =
+        BoardColumn::update(&ctx.db, user.id, list_id, id, &dto, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(column.version))
+        .body(Body::from(json!(column).to_string()))?)
+}
+
+async fn remove(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((list_id, id)): Path<(Uuid, Uuid)>,
+    IfMatch(expected): IfMatch,
+) -> Result<Response> {
+    BoardColumn::delete(&ctx.db, user.id, list_id, id, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+async fn move_column(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((list_id, id)): Path<(Uuid, Uuid)>,
+    Json(dto): Json<MoveColumn>,
+) -> Result<Response> {
+    let column = BoardColumn::move_to(&ctx.db, user.id, list_id, id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(column.version))
+        .body(Body::from(json!(column).to_string()))?)
+}
+
+async fn board(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(list_id): Path<Uuid>,
+) -> Result<Response> {
+    let board = BoardColumn::board(&ctx.db, user.id, list_id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(board).to_string()))?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/{id}/board", get(board))
+        .route("/{id}/columns", get(list).post(create))
+        .route("/{id}/columns/{column_id}", patch(update).delete(remove))
+        .route("/{id}/columns/{column_id}/move", post(move_column))
+}
@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header::ETAG, StatusCode},
+    response::Response,
+    routing::{get, patch, post},
+    Json, Router,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::{
+        auth::AuthUser,
+        etag::{etag, IfMatch},
+    },
+    error::Result,
+    models::checklists::{ChecklistItem, MoveChecklistItem, NewChecklistItem, UpdateChecklistItem},
+};
+
+async fn create(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    Json(dto): Json<NewChecklistItem<'static>>,
+) -> Result<Response> {
+    let item = ChecklistItem::create(&ctx.db, user.id, todo_id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header(ETAG, etag(item.version))
+        .body(Body::from(json!(item).to_string()))?)
+}
+
+async fn list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<Response> {
+    let items = ChecklistItem::find_by_todo(&ctx.db, user.id, todo_id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(items).to_string()))?)
+}
+
+async fn update(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, id)): Path<(Uuid, Uuid)>,
+    IfMatch(expected): IfMatch,
+    Json(dto): Json<UpdateChecklistItem<'static>>,
+) -> Result<Response> {
+    let item =
+        ChecklistItem::update(&ctx.db, user.id, todo_id, id, &dto, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(item.version))
+        .body(Body::from(json!(item).to_string()))?)
+}
+
+async fn remove(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, id)): Path<(Uuid, Uuid)>,
+    IfMatch(expected): IfMatch,
+) -> Result<Response> {
+    ChecklistItem::delete(&ctx.db, user.id, todo_id, id, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+async fn toggle(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Response> {
+    let item = ChecklistItem::toggle(&ctx.db, user.id, todo_id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(item.version))
+        .body(Body::from(json!(item).to_string()))?)
+}
+
+async fn move_item(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, id)): Path<(Uuid, Uuid)>,
+    Json(dto): Json<MoveChecklistItem>,
+) -> Result<Response> {
+    let item = ChecklistItem::move_to(&ctx.db, user.id, todo_id, id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(item.version))
+        .body(Body::from(json!(item).to_string()))?)
+}
+
+async fn convert(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Response> {
+    let todo = ChecklistItem::convert(&ctx.db, user.id, todo_id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .body(Body::from(json!(todo).to_string()))?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/{id}/checklist", get(list).post(create))
+        .route("/{id}/checklist/{item_id}", patch(update).delete(remove))
+        .route("/{id}/checklist/{item_id}/toggle", post(toggle))
+        .route("/{id}/checklist/{item_id}/move", post(move_item))
+        .route("/{id}/checklist/{item_id}/convert", post(convert))
+}
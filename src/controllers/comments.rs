@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header::ETAG, StatusCode},
+    response::Response,
+    routing::{get, patch},
+    Json, Router,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::{
+        auth::AuthUser,
+        etag::{etag, IfMatch},
+    },
+    error::Result,
+    models::{
+        comments::{Comment, NewComment},
+        pagination::Pagination,
+    },
+};
+
+async fn create(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    Json(dto): Json<NewComment<'static>>,
+) -> Result<Response> {
+    let comment = Comment::create(&ctx.db, user.id, todo_id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header(ETAG, etag(comment.version))
+        .body(Body::from(json!(comment).to_string()))?)
+}
+
+async fn list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    pagination: Pagination,
+) -> Result<Response> {
+    let comments = Comment::find_by_todo(&ctx.db, user.id, todo_id, &pagination).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(comments).to_string()))?)
+}
+
+async fn update(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, id)): Path<(Uuid, Uuid)>,
+    IfMatch(expected): IfMatch,
+    Json(dto): Json<NewComment<'static>>,
+) -> Result<Response> {
+    let comment = Comment::update(&ctx.db, user.id, todo_id, id, &dto, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(comment.version))
+        .body(Body::from(json!(comment).to_string()))?)
+}
+
+async fn remove(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, id)): Path<(Uuid, Uuid)>,
+    IfMatch(expected): IfMatch,
+) -> Result<Response> {
+    Comment::delete(&ctx.db, user.id, todo_id, id, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/{id}/comments", get(list).post(create))
+        .route("/{id}/comments/{comment_id}", patch(update).delete(remove))
+}
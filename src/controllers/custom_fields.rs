@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header::ETAG, StatusCode},
+    response::Response,
+    routing::{get, patch},
+    Json, Router,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::{
+        auth::AuthUser,
+        etag::{etag, IfMatch},
+    },
+    error::Result,
+    models::custom_fields::{CustomField, NewCustomField, UpdateCustomField},
+};
+
+async fn create(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(list_id): Path<Uuid>,
+    Json(dto): Json<NewCustomField<'static>>,
+) -> Result<Response> {
+    let field = CustomField::create(&ctx.db, user.id, list_id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header(ETAG, etag(field.version))
+        .body(Body::from(json!(field).to_string()))?)
+}
+
+async fn list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(list_id): Path<Uuid>,
+) -> Result<Response> {
+    let fields = CustomField::find_by_list(&ctx.db, user.id, list_id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(fields).to_string()))?)
+}
+
+async fn update(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((list_id, id)): Path<(Uuid, Uuid)>,
+    IfMatch(expected): IfMatch,
+    Json(dto): Json<UpdateCustomField<'static>>,
+) -> Result<Response> {
+    let field =
+        CustomField::update(&ctx.db, user.id, list_id, id, &dto, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(field.version))
+        .body(Body::from(json!(field).to_string()))?)
+}
+
+async fn remove(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((list_id, id)): Path<(Uuid, Uuid)>,
+    IfMatch(expected): IfMatch,
+) -> Result<Response> {
+    CustomField::delete(&ctx.db, user.id, list_id, id, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/{id}/fields", get(list).post(create))
+        .route("/{id}/fields/{field_id}", patch(update).delete(remove))
+}
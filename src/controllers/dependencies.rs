@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    routing::{delete, get},
+    Json, Router,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::auth::AuthUser,
+    error::Result,
+    models::dependencies::{Dependency, NewDependency},
+};
+
+async fn create(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    Json(dto): Json<NewDependency>,
+) -> Result<Response> {
+    let dependency = Dependency::create(&ctx.db, user.id, todo_id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .body(Body::from(json!(dependency).to_string()))?)
+}
+
+async fn list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<Response> {
+    let todos = Dependency::prerequisites(&ctx.db, user.id, todo_id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(todos).to_string()))?)
+}
+
+async fn remove(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, depends_on)): Path<(Uuid, Uuid)>,
+) -> Result<Response> {
+    Dependency::delete(&ctx.db, user.id, todo_id, depends_on).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+async fn graph(State(ctx): State<Arc<AppContext>>, user: AuthUser) -> Result<Response> {
+    let graph = Dependency::graph(&ctx.db, user.id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(graph).to_string()))?)
+}
+
+/// Routes nested under `/todos`.
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/graph", get(graph))
+        .route("/{id}/dependencies", get(list).post(create))
+        .route("/{id}/dependencies/{depends_on}", delete(remove))
+}
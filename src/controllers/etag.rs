@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header::IF_MATCH, request::Parts},
+};
+
+use crate::{config::state::AppContext, error::Report, models::if_match_versions};
+
+pub use crate::models::etag;
+
+/// The versions named by the `If-Match` headers. `None` without a header or with `*`, which any
+/// existing row matches. A header naming no version of ours still has to match, so it fails the
+/// precondition rather than the request.
+#[derive(Debug, Clone, Default)]
+pub struct IfMatch(pub Option<Vec<i32>>);
+
+impl FromRequestParts<Arc<AppContext>> for IfMatch {
+    type Rejection = Report;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _ctx: &Arc<AppContext>,
+    ) -> Result<Self, Self::Rejection> {
+        let mut versions = Vec::new();
+        let mut found = false;
+
+        for value in parts.headers.get_all(IF_MATCH) {
+            found = true;
+
+            match if_match_versions(value.to_str().unwrap_or_default()) {
+                Some(named) => versions.extend(named),
+                None => return Ok(Self(None)),
+            }
+        }
+
+        Ok(Self(found.then_some(versions)))
+    }
+}
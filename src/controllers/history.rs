@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    routing::{get, post},
+    Router,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::auth::AuthUser,
+    error::Result,
+    models::{history::Revision, pagination::Pagination},
+};
+
+async fn list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    pagination: Pagination,
+) -> Result<Response> {
+    let revisions = Revision::find_by_todo(&ctx.db, user.id, todo_id, &pagination).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(revisions).to_string()))?)
+}
+
+async fn restore(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, revision)): Path<(Uuid, i32)>,
+) -> Result<Response> {
+    let todo = Revision::restore(&ctx.db, user.id, todo_id, revision).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(todo).to_string()))?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/{id}/history", get(list))
+        .route("/{id}/history/{revision}/restore", post(restore))
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header::ETAG, StatusCode},
+    response::Response,
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::{
+        auth::AuthUser,
+        etag::{etag, IfMatch},
+    },
+    error::Result,
+    models::{
+        duplicates::DuplicateList,
+        lists::{CreateList, List, MoveList, UpdateList},
+        pagination::Pagination,
+    },
+};
+
+async fn create(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Json(dto): Json<CreateList<'static>>,
+) -> Result<Response> {
+    let list = List::create(&ctx.db, user.id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header(ETAG, etag(list.version))
+        .body(Body::from(json!(list).to_string()))?)
+}
+
+async fn list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    pagination: Pagination,
+) -> Result<Response> {
+    let lists = List::paginate(&ctx.db, user.id, &pagination).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(lists).to_string()))?)
+}
+
+async fn show(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response> {
+    let list = List::find_by_id(&ctx.db, user.id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(list.version))
+        .body(Body::from(json!(list).to_string()))?)
+}
+
+async fn update(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    IfMatch(expected): IfMatch,
+    Json(dto): Json<UpdateList<'static>>,
+) -> Result<Response> {
+    let list = List::update(&ctx.db, user.id, id, &dto, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(list.version))
+        .body(Body::from(json!(list).to_string()))?)
+}
+
+async fn remove(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    IfMatch(expected): IfMatch,
+) -> Result<Response> {
+    List::delete(&ctx.db, user.id, id, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+async fn move_list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<MoveList>,
+) -> Result<Response> {
+    let list = List::move_to(&ctx.db, user.id, id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(list.version))
+        .body(Body::from(json!(list).to_string()))?)
+}
+
+async fn duplicate(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<DuplicateList<'static>>,
+) -> Result<Response> {
+    let duplicated = List::duplicate(&ctx.db, user.id, id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .body(Body::from(json!(duplicated).to_string()))?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/", get(list).post(create))
+        .route("/{id}", get(show).patch(update).delete(remove))
+        .route("/{id}/move", post(move_list))
+        .route("/{id}/duplicate", post(duplicate))
+}
@@ -1 +1,19 @@
+pub mod attachments;
 pub mod auth;
+pub mod boards;
+pub mod checklists;
+pub mod comments;
+pub mod custom_fields;
+pub mod dependencies;
+pub mod etag;
+pub mod history;
+pub mod lists;
+pub mod reminders;
+pub mod search;
+pub mod settings;
+pub mod templates;
+pub mod time_entries;
+pub mod todos;
+pub mod trash;
+pub mod undo;
+pub mod views;
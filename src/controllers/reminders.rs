@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    routing::{delete, get},
+    Json, Router,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::auth::AuthUser,
+    error::Result,
+    models::reminders::{NewReminder, Reminder},
+};
+
+async fn create(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+    Json(dto): Json<NewReminder>,
+) -> Result<Response> {
+    ctx.notifiers.channel(dto.channel)?;
+
+    let reminder = Reminder::create(&ctx.db, user.id, todo_id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .body(Body::from(json!(reminder).to_string()))?)
+}
+
+async fn list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(todo_id): Path<Uuid>,
+) -> Result<Response> {
+    let reminders = Reminder::find_by_todo(&ctx.db, user.id, todo_id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(reminders).to_string()))?)
+}
+
+async fn remove(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path((todo_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Response> {
+    Reminder::delete(&ctx.db, user.id, todo_id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+/// Routes nested under `/todos`.
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/{id}/reminders", get(list).post(create))
+        .route("/{id}/reminders/{reminder_id}", delete(remove))
+}
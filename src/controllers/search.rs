@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+    routing::get,
+    Router,
+};
+use serde_json::json;
+
+use crate::{
+    config::state::AppContext,
+    controllers::auth::AuthUser,
+    error::Result,
+    models::{
+        pagination::Pagination,
+        search::{SearchHit, SearchParams},
+    },
+};
+
+async fn search(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Query(params): Query<SearchParams>,
+    pagination: Pagination,
+) -> Result<Response> {
+    let hits = SearchHit::search(&ctx.db, user.id, &params, &pagination).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(hits).to_string()))?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new().route("/", get(search))
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header::ETAG, StatusCode},
+    response::Response,
+    routing::get,
+    Json, Router,
+};
+use serde_json::json;
+
+use crate::{
+    config::state::AppContext,
+    controllers::{
+        auth::AuthUser,
+        etag::{etag, IfMatch},
+    },
+    error::Result,
+    models::settings::{Settings, UpdateSettings},
+};
+
+async fn show(State(ctx): State<Arc<AppContext>>, user: AuthUser) -> Result<Response> {
+    let settings = Settings::find(&ctx.db, user.id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(settings.version))
+        .body(Body::from(json!(settings).to_string()))?)
+}
+
+async fn update(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    IfMatch(expected): IfMatch,
+    Json(dto): Json<UpdateSettings<'static>>,
+) -> Result<Response> {
+    let settings = Settings::update(&ctx.db, user.id, &dto, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(settings.version))
+        .body(Body::from(json!(settings).to_string()))?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new().route("/", get(show).patch(update))
+}
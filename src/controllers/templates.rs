@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header::ETAG, StatusCode},
+    response::Response,
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::{
+        auth::AuthUser,
+        etag::{etag, IfMatch},
+    },
+    error::Result,
+    models::templates::{CreateTemplate, Instantiate, Template, UpdateTemplate},
+};
+
+async fn create(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Json(dto): Json<CreateTemplate<'static>>,
+) -> Result<Response> {
+    let template = Template::create(&ctx.db, user.id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header(ETAG, etag(template.version))
+        .body(Body::from(json!(template).to_string()))?)
+}
+
+async fn list(State(ctx): State<Arc<AppContext>>, user: AuthUser) -> Result<Response> {
+    let templates = Template::find_all(&ctx.db, user.id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(templates).to_string()))?)
+}
+
+async fn show(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response> {
+    let template = Template::find_by_id(&ctx.db, user.id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(template.version))
+        .body(Body::from(json!(template).to_string()))?)
+}
+
+async fn update(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    IfMatch(expected): IfMatch,
+    Json(dto): Json<UpdateTemplate<'static>>,
+) -> Result<Response> {
+    let template = Template::update(&ctx.db, user.id, id, &dto, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(template.version))
+        .body(Body::from(json!(template).to_string()))?)
+}
+
+async fn remove(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    IfMatch(expected): IfMatch,
+) -> Result<Response> {
+    Template::delete(&ctx.db, user.id, id, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+async fn instantiate(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<Instantiate>,
+) -> Result<Response> {
+    let instantiated = Template::instantiate(&ctx.db, user.id, id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .body(Body::from(json!(instantiated).to_string()))?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/", get(list).post(create))
+        .route("/{id}", get(show).patch(update).delete(remove))
+        .route("/{id}/instantiate", post(instantiate))
+}
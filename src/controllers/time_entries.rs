@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header::ETAG, StatusCode},
+    response::Response,
+    routing::{get, patch, post},
+    Json, Router,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::{
+        auth::AuthUser,
+        etag::{etag, IfMatch},
+    },
+    error::Result,
+    models::{
+        pagination::Pagination,
+        time_entries::{
+            EntryParams, NewTimeEntry, ReportParams, StartTimer, TimeEntry, UpdateTimeEntry,
+        },
+    },
+};
+
+async fn running(State(ctx): State<Arc<AppContext>>, user: AuthUser) -> Result<Response> {
+    let entry = TimeEntry::running(&ctx.db, user.id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(entry).to_string()))?)
+}
+
+/// Start a timer. A user with a running timer gets 409.
+async fn start(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Json(dto): Json<StartTimer<'static>>,
+) -> Result<Response> {
+    let entry = TimeEntry::start(&ctx.db, user.id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .body(Body::from(json!(entry).to_string()))?)
+}
+
+async fn stop(State(ctx): State<Arc<AppContext>>, user: AuthUser) -> Result<Response> {
+    let entry = TimeEntry::stop(&ctx.db, user.id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(entry).to_string()))?)
+}
+
+async fn create(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Json(dto): Json<NewTimeEntry<'static>>,
+) -> Result<Response> {
+    let entry = TimeEntry::create(&ctx.db, user.id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header(ETAG, etag(entry.version))
+        .body(Body::from(json!(entry).to_string()))?)
+}
+
+async fn list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Query(params): Query<EntryParams>,
+    pagination: Pagination,
+) -> Result<Response> {
+    let entries = TimeEntry::find_all(&ctx.db, user.id, &params, &pagination).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(entries).to_string()))?)
+}
+
+async fn update(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    IfMatch(expected): IfMatch,
+    Json(dto): Json<UpdateTimeEntry<'static>>,
+) -> Result<Response> {
+    let entry = TimeEntry::update(&ctx.db, user.id, id, &dto, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(entry.version))
+        .body(Body::from(json!(entry).to_string()))?)
+}
+
+async fn remove(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    IfMatch(expected): IfMatch,
+) -> Result<Response> {
+    TimeEntry::delete(&ctx.db, user.id, id, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+async fn report(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Query(params): Query<ReportParams>,
+) -> Result<Response> {
+    let report = TimeEntry::report(&ctx.db, user.id, &params).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(report).to_string()))?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/timer", get(running).post(start))
+        .route("/timer/stop", post(stop))
+        .route("/entries", get(list).post(create))
+        .route("/entries/{id}", patch(update).delete(remove))
+        .route("/report", get(report))
+}
@@ -0,0 +1,331 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header::ETAG, StatusCode},
+    response::Response,
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::{
+        auth::AuthUser,
+        etag::{etag, IfMatch},
+    },
+    error::{Error, Result},
+    filter::Filter,
+    models::{
+        boards::{BoardColumn, SetStatus},
+        duplicates::DuplicateTodo,
+        pagination::Pagination,
+        quick::{QuickAdd, QuickAdded},
+        todos::{BulkTodos, CompleteScope, CreateTodo, MoveTodo, SnoozeTodo, Todo, UpdateTodo},
+        urgency::{Ranked, Urgency},
+    },
+};
+
+#[derive(Debug, Deserialize, Default)]
+struct ListParams {
+    /// A list id, or `inbox` for todos without a list.
+    list: Option<String>,
+    /// A filter in the query language of [`Filter`].
+    filter: Option<String>,
+    /// Include snoozed todos.
+    #[serde(default)]
+    snoozed: bool,
+    /// How to order the todos.
+    #[serde(default)]
+    sort: ListSort,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ListSort {
+    /// The manual order.
+    #[default]
+    Position,
+    /// The most urgent first, each todo with its score.
+    Urgency,
+}
+
+impl ListParams {
+    fn list(&self) -> Result<Option<Option<Uuid>>> {
+        match self.list.as_deref() {
+            None => Ok(None),
+            Some("inbox") => Ok(Some(None)),
+            Some(id) => {
+                let id = id
+                    .parse::<Uuid>()
+                    .map_err(|_| Error::BadRequest(format!("Invalid list `{id}`")))?;
+                Ok(Some(Some(id)))
+            }
+        }
+    }
+
+    fn filter(&self) -> Result<Option<Filter>> {
+        let filter = match self.filter.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(filter) => Some(Filter::parse(filter).map_err(Error::from)?),
+        };
+
+        if self.snoozed {
+            Ok(filter)
+        } else {
+            Ok(Some(Filter::active(filter)))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct CompleteParams {
+    #[serde(default)]
+    scope: CompleteScope,
+}
+
+async fn create(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Json(dto): Json<CreateTodo<'static>>,
+) -> Result<Response> {
+    let todo = Todo::create(&ctx.db, user.id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header(ETAG, etag(todo.version))
+        .body(Body::from(json!(todo).to_string()))?)
+}
+
+async fn list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Query(params): Query<ListParams>,
+    pagination: Pagination,
+) -> Result<Response> {
+    let filter = params.filter()?;
+    let (list, filter) = (params.list()?, filter.as_ref());
+
+    let todos = match params.sort {
+        ListSort::Position => {
+            json!(Todo::paginate(&ctx.db, user.id, list, filter, &pagination).await?)
+        }
+        ListSort::Urgency => {
+            json!(Ranked::paginate(&ctx.db, user.id, list, filter, &pagination).await?)
+        }
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(todos.to_string()))?)
+}
+
+async fn show(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response> {
+    let todo = Todo::find_by_id(&ctx.db, user.id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(todo.version))
+        .body(Body::from(json!(todo).to_string()))?)
+}
+
+async fn duplicate(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<DuplicateTodo<'static>>,
+) -> Result<Response> {
+    let todo = Todo::duplicate(&ctx.db, user.id, id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header(ETAG, etag(todo.version))
+        .body(Body::from(json!(todo).to_string()))?)
+}
+
+async fn urgency(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response> {
+    let urgency = Urgency::find_by_todo(&ctx.db, user.id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(urgency).to_string()))?)
+}
+
+async fn update(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    IfMatch(expected): IfMatch,
+    Json(dto): Json<UpdateTodo<'static>>,
+) -> Result<Response> {
+    let todo = Todo::update(&ctx.db, user.id, id, &dto, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(todo.version))
+        .body(Body::from(json!(todo).to_string()))?)
+}
+
+async fn remove(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    IfMatch(expected): IfMatch,
+) -> Result<Response> {
+    Todo::delete(&ctx.db, user.id, id, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+async fn complete(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    Query(params): Query<CompleteParams>,
+) -> Result<Response> {
+    let completion = Todo::complete(&ctx.db, user.id, id, params.scope).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(completion).to_string()))?)
+}
+
+async fn move_todo(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<MoveTodo>,
+) -> Result<Response> {
+    let todo = Todo::move_to(&ctx.db, user.id, id, &dto).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(todo.version))
+        .body(Body::from(json!(todo).to_string()))?)
+}
+
+/// Apply one action to many todos. An atomic request that was rolled back answers 409 with the
+/// per-todo results.
+async fn bulk(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Json(dto): Json<BulkTodos>,
+) -> Result<Response> {
+    let outcome = Todo::bulk(&ctx.db, user.id, &dto).await?;
+
+    let status = if outcome.committed {
+        StatusCode::OK
+    } else {
+        StatusCode::CONFLICT
+    };
+
+    Ok(Response::builder()
+        .status(status)
+        .body(Body::from(json!(outcome).to_string()))?)
+}
+
+/// Create a todo from a line of text. A dry run answers 200 with what would be created.
+async fn quick_add(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Json(dto): Json<QuickAdd<'static>>,
+) -> Result<Response> {
+    let added = QuickAdded::create(&ctx.db, user.id, &dto).await?;
+
+    let status = if dto.dry_run {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+
+    Ok(Response::builder()
+        .status(status)
+        .body(Body::from(json!(added).to_string()))?)
+}
+
+async fn set_status(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    IfMatch(expected): IfMatch,
+    Json(dto): Json<SetStatus>,
+) -> Result<Response> {
+    let completion =
+        BoardColumn::set_status(&ctx.db, user.id, id, &dto, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(completion).to_string()))?)
+}
+
+async fn stop_recurrence(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    IfMatch(expected): IfMatch,
+) -> Result<Response> {
+    let todo = Todo::stop_recurrence(&ctx.db, user.id, id, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(todo.version))
+        .body(Body::from(json!(todo).to_string()))?)
+}
+
+async fn snooze(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    IfMatch(expected): IfMatch,
+    Json(dto): Json<SnoozeTodo>,
+) -> Result<Response> {
+    let todo = Todo::snooze(&ctx.db, user.id, id, &dto, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(todo.version))
+        .body(Body::from(json!(todo).to_string()))?)
+}
+
+async fn unsnooze(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    IfMatch(expected): IfMatch,
+) -> Result<Response> {
+    let todo = Todo::unsnooze(&ctx.db, user.id, id, expected.as_deref()).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(ETAG, etag(todo.version))
+        .body(Body::from(json!(todo).to_string()))?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/", get(list).post(create))
+        .route("/bulk", post(bulk))
+        .route("/quick", post(quick_add))
+        .route("/{id}", get(show).patch(update).delete(remove))
+        .route("/{id}/complete", post(complete))
+        .route("/{id}/move", post(move_todo))
+        .route("/{id}/status", put(set_status))
+        .route("/{id}/recurrence", delete(stop_recurrence))
+        .route("/{id}/snooze", post(snooze).delete(unsnooze))
+        .route("/{id}/urgency", get(urgency))
+        .route("/{id}/duplicate", post(duplicate))
+}
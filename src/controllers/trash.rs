@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    routing::{delete, get, post},
+    Router,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::state::AppContext,
+    controllers::auth::AuthUser,
+    error::Result,
+    models::{lists::List, todos::Todo, trash::Trash},
+};
+
+async fn list(State(ctx): State<Arc<AppContext>>, user: AuthUser) -> Result<Response> {
+    let trash = Trash::find(&ctx.db, user.id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(trash).to_string()))?)
+}
+
+async fn restore_todo(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response> {
+    let todo = Todo::restore(&ctx.db, user.id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(todo).to_string()))?)
+}
+
+async fn purge_todo(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response> {
+    Todo::purge(&ctx.db, ctx.storage.as_ref(), user.id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+async fn restore_list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response> {
+    let list = List::restore(&ctx.db, user.id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(list).to_string()))?)
+}
+
+async fn purge_list(
+    State(ctx): State<Arc<AppContext>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Response> {
+    List::purge(&ctx.db, ctx.storage.as_ref(), user.id, id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/", get(list))
+        .route("/todos/{id}", delete(purge_todo))
+        .route("/todos/{id}/restore", post(restore_todo))
+        .route("/lists/{id}", delete(purge_list))
+        .route("/lists/{id}/restore", post(restore_list))
+}
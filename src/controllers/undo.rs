@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body, extract::State, http::StatusCode, response::Response, routing::post, Router,
+};
+use serde_json::json;
+
+use crate::{
+    config::state::AppContext, controllers::auth::AuthUser, error::Result, models::undo::Operation,
+};
+
+/// Revert the caller's last change. A todo or list changed since answers 409.
+async fn undo(State(ctx): State<Arc<AppContext>>, user: AuthUser) -> Result<Response> {
+    let reverted = Operation::undo(&ctx.db, user.id, &ctx.config.undo).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(reverted).to_string()))?)
+}
+
+/// Apply the caller's last undone change again. A todo or list changed since answers 409.
+async fn redo(State(ctx): State<Arc<AppContext>>, user: AuthUser) -> Result<Response> {
+    let reverted = Operation::redo(&ctx.db, user.id, &ctx.config.undo).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(json!(reverted).to_string()))?)
+}
+
+pub fn routes() -> Router<Arc<AppContext>> {
+    Router::new()
+        .route("/undo", post(undo))
+        .route("/redo", post(redo))
+}
@@ -1,12 +1,16 @@
 use argon2::password_hash::Error as PasswordHashError;
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
 
-use crate::error::{AuthError, ModelError};
+use crate::{
+    error::{AuthError, ModelError},
+    filter::SyntaxError,
+    models::etag,
+};
 
 pub type Result<T, E = Report> = color_eyre::Result<T, E>;
 
@@ -18,6 +22,22 @@ impl std::fmt::Debug for Report {
     }
 }
 
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Report {
+    /// The error this report wraps, when it is an `E`.
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: std::fmt::Display + std::fmt::Debug + Send + Sync + 'static,
+    {
+        self.0.downcast_ref()
+    }
+}
+
 impl<E> From<E> for Report
 where
     E: Into<color_eyre::Report>,
@@ -38,11 +58,19 @@ pub enum Error {
     #[error("{0}")]
     Config(#[from] config::ConfigError),
     #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
     ConfigFile(String),
     #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
     EntityAlreadyExists(String),
     #[error("Entity not found.")]
     EntityNotFound,
+    #[error(transparent)]
+    Filter(#[from] SyntaxError),
+    #[error("{0}")]
+    Forbidden(String),
     #[error("Internal server error")]
     InternalServerError,
     #[error("{0}")]
@@ -53,21 +81,49 @@ pub enum Error {
     TracingSubscriber(String),
     #[error("Page not found")]
     NotFound,
+    #[error("{0}")]
+    PayloadTooLarge(String),
+    /// An `If-Match` that names other versions than the current one, which is sent back.
+    #[error("The resource is at version {version}, not at any of {expected:?}")]
+    PreconditionFailed {
+        expected: Vec<i32>,
+        version: i32,
+        current: serde_json::Value,
+    },
     #[error(transparent)]
     Sqlx(#[from] sqlx::Error),
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
     #[error("{0}")]
+    UnsupportedMediaType(String),
+    #[error("{0}")]
     WrongCredentials(String),
 }
 
 impl Error {
     fn response(&self) -> Response {
+        if let Self::PreconditionFailed {
+            version, current, ..
+        } = self
+        {
+            return (
+                StatusCode::PRECONDITION_FAILED,
+                [(header::ETAG, etag(*version))],
+                Json(current.clone()),
+            )
+                .into_response();
+        }
+
         let (status, message) = match self {
             Self::NotFound | Self::EntityNotFound => {
                 (StatusCode::NOT_FOUND, "Page not found".to_string())
             }
-            Self::EntityAlreadyExists(e) => (StatusCode::CONFLICT, e.into()),
+            Self::BadRequest(e) => (StatusCode::BAD_REQUEST, e.into()),
+            Self::Filter(e) => (StatusCode::BAD_REQUEST, e.message.clone()),
+            Self::Conflict(e) | Self::EntityAlreadyExists(e) => (StatusCode::CONFLICT, e.into()),
+            Self::Forbidden(e) => (StatusCode::FORBIDDEN, e.into()),
+            Self::PayloadTooLarge(e) => (StatusCode::PAYLOAD_TOO_LARGE, e.into()),
+            Self::UnsupportedMediaType(e) => (StatusCode::UNSUPPORTED_MEDIA_TYPE, e.into()),
             Self::InternalServerError => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".to_string(),
@@ -81,9 +137,14 @@ impl Error {
             ),
         };
 
-        let body = Json(json!({
+        let mut body = json!({
             "message": message
-        }));
+        });
+        // Point clients at the part of a filter that could not be parsed
+        if let Self::Filter(e) = self {
+            body["position"] = json!(e.position);
+        }
+        let body = Json(body);
 
         (status, body).into_response()
     }
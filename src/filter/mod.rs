@@ -0,0 +1,328 @@
+//! A small query language for filtering todos, such as
+//! `tag:work AND (due<2d OR priority>=3) AND NOT list:someday`.
+//!
+//! ```text
+//! filter    = and { "OR" and }
+//! and       = unary { [ "AND" ] unary }
+//! unary     = "NOT" unary | primary
+//! primary   = "(" filter ")" | field op value | value
+//! op        = ":" | "=" | "!=" | "<" | "<=" | ">" | ">="
+//! ```
+//!
+//! Keywords are case-insensitive and two conditions next to each other are joined with `AND`.
+//! Values containing spaces, parentheses or quotes are written `"like \"this\""`. A value on its
+//! own matches titles.
+//!
+//! | Field                                   | Values                                            |
+//! |-----------------------------------------|---------------------------------------------------|
+//! | `tag`                                   | a tag, with or without `#`                        |
+//! | `list`                                  | a list name, or `inbox` for todos without a list  |
+//! | `title`, `notes`                        | text the field contains, ignoring case            |
+//! | `due`, `start`, `created`, `updated`, `completed` | `2d`, `-3h`, `1w`, `today`, `2025-05-01`, an RFC 3339 time, or `none` |
+//! | `priority`                              | `low`, `medium`, `high`, or their rank 1, 2 or 3  |
+//! | `is`                                    | `done`, `open`, `blocked`, `recurring`, `snoozed` |
+//! | `field.<key>`                           | a value of the custom field, or `none`            |
+//!
+//! Relative times are from now. Comparing to a day covers the whole (UTC) day: `due:today`, or
+//! `due:2d` for the day after tomorrow. Custom field values are numbers, `true` or `false`, or
+//! text, quoted when it would read as one of those; dates of date fields are text like
+//! `2025-05-01`. Ordering only matches values of the same type. A filter is compiled to SQL with
+//! all values bound as parameters.
+
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+
+use crate::models::todos::Priority;
+
+mod parser;
+mod sql;
+
+pub use sql::{Compiled, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Condition(Condition),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Tag(String),
+    /// `None` is the Inbox.
+    List(Option<String>),
+    Title(String),
+    Notes(String),
+    /// `None` matches todos without the date.
+    Date(DateField, Comparison, Option<DateValue>),
+    /// Ordered from low to high. Todos without a priority never match.
+    Priority(Comparison, Priority),
+    Is(State),
+    /// A custom field by key. `None` matches todos without a value.
+    Field(String, Comparison, Option<FieldValue>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateField {
+    Due,
+    Start,
+    Created,
+    Updated,
+    Completed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateValue {
+    /// An amount of time from now, such as `2d` or `-3h`.
+    Relative(i64, Unit),
+    /// A day relative to today: `yesterday`, `today` or `tomorrow`.
+    Today(i64),
+    Day(NaiveDate),
+    Instant(DateTime<FixedOffset>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Hours,
+    Days,
+    Weeks,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    Done,
+    Open,
+    Blocked,
+    Recurring,
+    /// Starting later, see [`Filter::active`].
+    Snoozed,
+}
+
+/// A filter that could not be parsed. `position` counts characters from 0.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{message} at position {position}")]
+pub struct SyntaxError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl Filter {
+    pub fn parse(input: &str) -> Result<Self, SyntaxError> {
+        parser::parse(input)
+    }
+
+    /// The filter of an active view: snoozed todos are left out, unless the filter asks for them
+    /// with `is:snoozed`.
+    pub fn active(filter: Option<Self>) -> Self {
+        let awake = Self::Not(Box::new(Self::Condition(Condition::Is(State::Snoozed))));
+
+        match filter {
+            Some(filter) if filter.mentions(&Condition::Is(State::Snoozed)) => filter,
+            Some(filter) => Self::And(Box::new(filter), Box::new(awake)),
+            None => awake,
+        }
+    }
+
+    fn mentions(&self, condition: &Condition) -> bool {
+        match self {
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.mentions(condition) || right.mentions(condition)
+            }
+            Self::Not(filter) => filter.mentions(condition),
+            Self::Condition(candidate) => candidate == condition,
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Or(..) => 0,
+            Self::And(..) => 1,
+            Self::Not(_) | Self::Condition(_) => 2,
+        }
+    }
+
+    /// Write the filter, in parentheses unless it binds at least as tightly as `precedence`.
+    fn operand(&self, f: &mut fmt::Formatter<'_>, precedence: u8) -> fmt::Result {
+        if self.precedence() >= precedence {
+            write!(f, "{self}")
+        } else {
+            write!(f, "({self})")
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    /// The filter in the query language, parsing back to the same filter.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Or(left, right) => {
+                left.operand(f, 0)?;
+                f.write_str(" OR ")?;
+                right.operand(f, 1)
+            }
+            Self::And(left, right) => {
+                left.operand(f, 1)?;
+                f.write_str(" AND ")?;
+                right.operand(f, 2)
+            }
+            Self::Not(filter) => {
+                f.write_str("NOT ")?;
+                filter.operand(f, 2)
+            }
+            Self::Condition(condition) => condition.fmt(f),
+        }
+    }
+}
+
+impl Comparison {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Eq => ":",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+}
+
+impl FieldValue {
+    /// The value an unquoted word stands for: `None` for `none`, a number, `true` or `false`,
+    /// and text otherwise.
+    pub fn parse(word: &str) -> Option<Self> {
+        if word.eq_ignore_ascii_case("none") {
+            return None;
+        }
+        if let Ok(value) = word.to_lowercase().parse::<bool>() {
+            return Some(Self::Bool(value));
+        }
+
+        let numeric = word
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.'));
+        match word.parse::<f64>() {
+            Ok(number) if numeric && number.is_finite() => Some(Self::Number(number)),
+            _ => Some(Self::Text(word.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tag(tag) => write!(f, "tag:{}", quote(tag, false)),
+            Self::List(None) => f.write_str("list:inbox"),
+            Self::List(Some(name)) => write!(
+                f,
+                "list:{}",
+                quote(name, name.eq_ignore_ascii_case("inbox"))
+            ),
+            Self::Title(text) => write!(f, "title:{}", quote(text, false)),
+            Self::Notes(text) => write!(f, "notes:{}", quote(text, false)),
+            Self::Date(field, comparison, value) => {
+                let field = match field {
+                    DateField::Due => "due",
+                    DateField::Start => "start",
+                    DateField::Created => "created",
+                    DateField::Updated => "updated",
+                    DateField::Completed => "completed",
+                };
+                let op = comparison.as_str();
+
+                match value {
+                    None => write!(f, "{field}{op}none"),
+                    Some(DateValue::Relative(amount, unit)) => {
+                        let unit = match unit {
+                            Unit::Hours => 'h',
+                            Unit::Days => 'd',
+                            Unit::Weeks => 'w',
+                        };
+                        write!(f, "{field}{op}{amount}{unit}")
+                    }
+                    Some(DateValue::Today(-1)) => write!(f, "{field}{op}yesterday"),
+                    Some(DateValue::Today(1)) => write!(f, "{field}{op}tomorrow"),
+                    Some(DateValue::Today(_)) => write!(f, "{field}{op}today"),
+                    Some(DateValue::Day(day)) => write!(f, "{field}{op}{day}"),
+                    Some(DateValue::Instant(at)) => write!(f, "{field}{op}{}", at.to_rfc3339()),
+                }
+            }
+            Self::Field(key, comparison, value) => {
+                let op = comparison.as_str();
+
+                match value {
+                    None => write!(f, "field.{key}{op}none"),
+                    Some(FieldValue::Text(text)) => {
+                        // A leading `=` would run into a `<` or `>` before it
+                        let typed = FieldValue::parse(text) != Some(FieldValue::Text(text.clone()))
+                            || text.starts_with('=');
+                        write!(f, "field.{key}{op}{}", quote(text, typed))
+                    }
+                    Some(FieldValue::Number(number)) => write!(f, "field.{key}{op}{number}"),
+                    Some(FieldValue::Bool(value)) => write!(f, "field.{key}{op}{value}"),
+                }
+            }
+            Self::Priority(comparison, priority) => write!(
+                f,
+                "priority{}{}",
+                comparison.as_str(),
+                match priority {
+                    Priority::Low => "low",
+                    Priority::Medium => "medium",
+                    Priority::High => "high",
+                }
+            ),
+            Self::Is(state) => f.write_str(match state {
+                State::Done => "is:done",
+                State::Open => "is:open",
+                State::Blocked => "is:blocked",
+                State::Recurring => "is:recurring",
+                State::Snoozed => "is:snoozed",
+            }),
+        }
+    }
+}
+
+/// Quote a value unless it reads back the same without quotes. `keyword` values are quoted
+/// anyway, as they would be mistaken for a keyword.
+fn quote(value: &str, keyword: bool) -> String {
+    let special = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '(' | ')' | '"' | '\\'))
+        || keyword;
+
+    if !special {
+        return value.to_string();
+    }
+
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+
+    quoted
+}
@@ -0,0 +1,475 @@
+use chrono::{DateTime, Datelike, NaiveDate};
+
+use crate::models::todos::Priority;
+
+use super::{
+    Comparison, Condition, DateField, DateValue, FieldValue, Filter, State, SyntaxError, Unit,
+};
+
+/// Deeper nesting than this is refused rather than risking the stack.
+const MAX_DEPTH: usize = 32;
+
+/// Filters with more conditions than this are refused.
+const MAX_CONDITIONS: usize = 64;
+
+/// Fields other than dates.
+const FIELDS: [&str; 6] = ["tag", "list", "title", "notes", "priority", "is"];
+
+/// Custom fields are `field.<key>`.
+const FIELD_PREFIX: &str = "field.";
+
+/// Relative times further away than this, in any unit, are refused.
+const MAX_AMOUNT: i64 = 100_000;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Op(Op),
+    Word(String),
+    Quoted(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Colon,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Self::Open => "`(`".into(),
+            Self::Close => "`)`".into(),
+            Self::Op(op) => format!("`{}`", op.as_str()),
+            Self::Word(word) => format!("`{word}`"),
+            Self::Quoted(_) => "a quoted value".into(),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Self::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+impl Op {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Colon => ":",
+            Self::Eq => "=",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+}
+
+fn error(message: impl Into<String>, position: usize) -> SyntaxError {
+    SyntaxError {
+        message: message.into(),
+        position,
+    }
+}
+
+/// Split the input into tokens with their character positions. Right after an operator a value
+/// may contain `:`, `<` and the like, so that `due<2025-05-01T10:00:00Z` needs no quotes.
+fn lex(input: &str) -> Result<Vec<(Token, usize)>, SyntaxError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let after_op = matches!(tokens.last(), Some((Token::Op(_), _)));
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let token = match c {
+            '(' => {
+                i += 1;
+                Token::Open
+            }
+            ')' => {
+                i += 1;
+                Token::Close
+            }
+            '"' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(error("Unterminated quoted value", start)),
+                        Some('"') => break,
+                        Some('\\') => match chars.get(i + 1) {
+                            Some(&escaped) => {
+                                value.push(escaped);
+                                i += 1;
+                            }
+                            None => return Err(error("Unterminated quoted value", start)),
+                        },
+                        Some(&c) => value.push(c),
+                    }
+                    i += 1;
+                }
+                i += 1;
+                Token::Quoted(value)
+            }
+            ':' | '=' | '<' | '>' | '!' if !after_op => {
+                let next = chars.get(i + 1).copied();
+                let (op, len) = match (c, next) {
+                    (':', _) => (Op::Colon, 1),
+                    ('=', _) => (Op::Eq, 1),
+                    ('!', Some('=')) => (Op::Ne, 2),
+                    ('<', Some('=')) => (Op::Le, 2),
+                    ('<', _) => (Op::Lt, 1),
+                    ('>', Some('=')) => (Op::Ge, 2),
+                    ('>', _) => (Op::Gt, 1),
+                    _ => return Err(error("Unexpected `!`, did you mean `NOT`?", start)),
+                };
+                i += len;
+                Token::Op(op)
+            }
+            _ => {
+                let ends = |c: char| {
+                    c.is_whitespace()
+                        || matches!(c, '(' | ')' | '"')
+                        || (!after_op && matches!(c, ':' | '=' | '<' | '>' | '!'))
+                };
+                while i < chars.len() && !ends(chars[i]) {
+                    i += 1;
+                }
+                Token::Word(chars[start..i].iter().collect())
+            }
+        };
+
+        tokens.push((token, start));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+    end: usize,
+    depth: usize,
+    conditions: usize,
+}
+
+pub(super) fn parse(input: &str) -> Result<Filter, SyntaxError> {
+    let mut parser = Parser {
+        tokens: lex(input)?,
+        next: 0,
+        end: input.chars().count(),
+        depth: 0,
+        conditions: 0,
+    };
+
+    let filter = parser.or()?;
+
+    match parser.peek() {
+        None => Ok(filter),
+        Some((Token::Close, position)) => Err(error("Unmatched `)`", position)),
+        Some((token, position)) => Err(error(
+            format!("Expected `AND` or `OR`, found {}", token.describe()),
+            position,
+        )),
+    }
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(&Token, usize)> {
+        self.tokens
+            .get(self.next)
+            .map(|(token, position)| (token, *position))
+    }
+
+    fn advance(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    /// The position of the next token, or the end of the input.
+    fn position(&self) -> usize {
+        self.peek().map_or(self.end, |(_, position)| position)
+    }
+
+    /// Go one level deeper for the `(` or `NOT` at `position`.
+    fn descend(&mut self, position: usize) -> Result<(), SyntaxError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(error("Filter is nested too deeply", position));
+        }
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Filter, SyntaxError> {
+        let mut filter = self.and()?;
+
+        while matches!(self.peek(), Some((token, _)) if token.is_keyword("or")) {
+            self.advance();
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter, SyntaxError> {
+        let mut filter = self.unary()?;
+
+        loop {
+            match self.peek() {
+                Some((token, _)) if token.is_keyword("and") => {
+                    self.advance();
+                }
+                // Conditions next to each other are joined with AND
+                Some((Token::Open | Token::Quoted(_), _)) => (),
+                Some((token @ Token::Word(_), _)) if !token.is_keyword("or") => (),
+                _ => break,
+            }
+
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn unary(&mut self) -> Result<Filter, SyntaxError> {
+        if matches!(self.peek(), Some((token, _)) if token.is_keyword("not")) {
+            let position = self.position();
+            self.advance();
+            self.descend(position)?;
+            let filter = Filter::Not(Box::new(self.unary()?));
+            self.depth -= 1;
+
+            return Ok(filter);
+        }
+
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Filter, SyntaxError> {
+        let position = self.position();
+
+        if !matches!(self.peek(), None | Some((Token::Open, _))) {
+            self.conditions += 1;
+            if self.conditions > MAX_CONDITIONS {
+                return Err(error("Filter has too many conditions", position));
+            }
+        }
+
+        match self.advance() {
+            None => Err(error("Expected a condition", position)),
+            Some((Token::Open, _)) => {
+                self.descend(position)?;
+                let filter = self.or()?;
+                self.depth -= 1;
+
+                match self.advance() {
+                    Some((Token::Close, _)) => Ok(filter),
+                    Some((token, position)) => Err(error(
+                        format!("Expected `)`, found {}", token.describe()),
+                        position,
+                    )),
+                    None => Err(error("Expected `)`", self.end)),
+                }
+            }
+            Some((Token::Quoted(text), _)) => Ok(Filter::Condition(Condition::Title(text))),
+            Some((Token::Word(word), _)) => match self.peek() {
+                Some((&Token::Op(op), _)) => {
+                    self.advance();
+                    self.condition(&word, position, op)
+                }
+                _ if ["and", "or", "not"]
+                    .iter()
+                    .any(|k| word.eq_ignore_ascii_case(k)) =>
+                {
+                    Err(error(
+                        format!("Expected a condition, found `{word}`"),
+                        position,
+                    ))
+                }
+                _ => Ok(Filter::Condition(Condition::Title(word))),
+            },
+            Some((token, position)) => Err(error(
+                format!("Expected a condition, found {}", token.describe()),
+                position,
+            )),
+        }
+    }
+
+    /// The rest of `field op value`, the field and operator having been read.
+    fn condition(&mut self, field: &str, at: usize, op: Op) -> Result<Filter, SyntaxError> {
+        let position = self.position();
+        let (value, quoted) = match self.advance() {
+            Some((Token::Word(value), _)) => (value, false),
+            Some((Token::Quoted(value), _)) => (value, true),
+            _ => {
+                return Err(error(
+                    format!("Expected a value after `{}`", op.as_str()),
+                    position,
+                ))
+            }
+        };
+
+        let comparison = match op {
+            Op::Colon | Op::Eq | Op::Ne => Comparison::Eq,
+            Op::Lt => Comparison::Lt,
+            Op::Le => Comparison::Le,
+            Op::Gt => Comparison::Gt,
+            Op::Ge => Comparison::Ge,
+        };
+        let field = field.to_lowercase();
+
+        if let Some(key) = field.strip_prefix(FIELD_PREFIX) {
+            if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(error(format!("Unknown field `{field}`"), at));
+            }
+
+            let value = if quoted {
+                Some(FieldValue::Text(value))
+            } else {
+                FieldValue::parse(&value)
+            };
+            if value.is_none() && comparison != Comparison::Eq {
+                return Err(error(
+                    format!("`none` cannot be compared with `{}`", op.as_str()),
+                    position,
+                ));
+            }
+
+            let filter = Filter::Condition(Condition::Field(key.to_string(), comparison, value));
+
+            return Ok(match op {
+                Op::Ne => Filter::Not(Box::new(filter)),
+                _ => filter,
+            });
+        }
+
+        let date_field = match field.as_str() {
+            "due" => Some(DateField::Due),
+            "start" => Some(DateField::Start),
+            "created" => Some(DateField::Created),
+            "updated" => Some(DateField::Updated),
+            "completed" => Some(DateField::Completed),
+            _ => None,
+        };
+
+        if date_field.is_none() && !FIELDS.contains(&field.as_str()) {
+            return Err(error(format!("Unknown field `{field}`"), at));
+        }
+        if date_field.is_none() && field != "priority" && comparison != Comparison::Eq {
+            return Err(error(
+                format!("`{field}` cannot be compared with `{}`", op.as_str()),
+                at,
+            ));
+        }
+
+        let condition = match (field.as_str(), date_field) {
+            (_, Some(date_field)) => {
+                let value = date(&value, quoted).ok_or_else(|| {
+                    error(
+                        format!("Expected a date or a time like `2d`, found `{value}`"),
+                        position,
+                    )
+                })?;
+
+                if value.is_none() && comparison != Comparison::Eq {
+                    return Err(error(
+                        format!("`none` cannot be compared with `{}`", op.as_str()),
+                        position,
+                    ));
+                }
+
+                Condition::Date(date_field, comparison, value)
+            }
+            ("tag", _) => Condition::Tag(value),
+            ("list", _) if !quoted && value.eq_ignore_ascii_case("inbox") => Condition::List(None),
+            ("list", _) => Condition::List(Some(value)),
+            ("title", _) => Condition::Title(value),
+            ("notes", _) => Condition::Notes(value),
+            ("priority", _) => Condition::Priority(
+                comparison,
+                match value.to_lowercase().as_str() {
+                    "low" | "1" => Priority::Low,
+                    "medium" | "2" => Priority::Medium,
+                    "high" | "3" => Priority::High,
+                    _ => {
+                        return Err(error(
+                            format!("Expected `low`, `medium`, `high` or 1 to 3, found `{value}`"),
+                            position,
+                        ))
+                    }
+                },
+            ),
+            // `is`, the only field left
+            _ => Condition::Is(match value.to_lowercase().as_str() {
+                "done" => State::Done,
+                "open" => State::Open,
+                "blocked" => State::Blocked,
+                "recurring" => State::Recurring,
+                "snoozed" => State::Snoozed,
+                _ => {
+                    return Err(error(
+                        format!(
+                            "Expected `done`, `open`, `blocked`, `recurring` or `snoozed`, \
+                             found `{value}`"
+                        ),
+                        position,
+                    ))
+                }
+            }),
+        };
+
+        let filter = Filter::Condition(condition);
+
+        Ok(match op {
+            Op::Ne => Filter::Not(Box::new(filter)),
+            _ => filter,
+        })
+    }
+}
+
+/// A date value: `Some(None)` for `none`, `None` when the value is not a date.
+fn date(value: &str, quoted: bool) -> Option<Option<DateValue>> {
+    let lower = value.to_lowercase();
+
+    match lower.as_str() {
+        "none" if !quoted => return Some(None),
+        "yesterday" => return Some(Some(DateValue::Today(-1))),
+        "today" => return Some(Some(DateValue::Today(0))),
+        "tomorrow" => return Some(Some(DateValue::Today(1))),
+        _ => (),
+    }
+
+    if let Ok(day) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return (1..=9999)
+            .contains(&day.year())
+            .then_some(Some(DateValue::Day(day)));
+    }
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Some(Some(DateValue::Instant(at)));
+    }
+
+    let unit = match lower.chars().last()? {
+        'h' => Unit::Hours,
+        'd' => Unit::Days,
+        'w' => Unit::Weeks,
+        _ => return None,
+    };
+    let amount: i64 = lower[..lower.len() - 1].parse().ok()?;
+
+    (amount.abs() <= MAX_AMOUNT).then_some(Some(DateValue::Relative(amount, unit)))
+}
@@ -0,0 +1,339 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use serde_json::json;
+use sqlx::{postgres::PgArguments, query::QueryAs, types::Json, Postgres};
+
+use crate::models::todos::Priority;
+
+use super::{Comparison, Condition, DateField, DateValue, FieldValue, Filter, State, Unit};
+
+/// A value bound to a placeholder of a [`Compiled`] filter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    Time(DateTime<Utc>),
+    Json(serde_json::Value),
+    Int(i32),
+    Priorities(Vec<Priority>),
+}
+
+/// Priorities in the order of their rank, from 1 for low.
+const PRIORITIES: [Priority; 3] = [Priority::Low, Priority::Medium, Priority::High];
+
+/// A filter as an SQL condition on `todos`. The text only ever comes from this module; every
+/// value of the filter is in `values`, bound to `$first`, `$first + 1` and so on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compiled {
+    pub sql: String,
+    pub values: Vec<Value>,
+}
+
+impl Compiled {
+    /// Bind the values to a query the SQL is part of.
+    pub fn bind<'q, O>(
+        self,
+        mut query: QueryAs<'q, Postgres, O, PgArguments>,
+    ) -> QueryAs<'q, Postgres, O, PgArguments> {
+        for value in self.values {
+            query = match value {
+                Value::Text(text) => query.bind(text),
+                Value::Time(at) => query.bind(at),
+                Value::Json(value) => query.bind(Json(value)),
+                Value::Int(value) => query.bind(value),
+                Value::Priorities(priorities) => query.bind(priorities),
+            };
+        }
+
+        query
+    }
+}
+
+impl Filter {
+    /// Compile to SQL whose placeholders start at `$first`, with relative times counted from
+    /// `now`.
+    pub fn compile(&self, first: usize, now: DateTime<Utc>) -> Compiled {
+        let mut compiled = Compiled {
+            sql: String::new(),
+            values: Vec::new(),
+        };
+
+        Builder {
+            compiled: &mut compiled,
+            first,
+            now,
+        }
+        .filter(self);
+
+        compiled
+    }
+}
+
+struct Builder<'a> {
+    compiled: &'a mut Compiled,
+    first: usize,
+    now: DateTime<Utc>,
+}
+
+impl Builder<'_> {
+    fn push(&mut self, sql: &str) {
+        self.compiled.sql.push_str(sql);
+    }
+
+    /// Bind a value and write its placeholder.
+    fn bind(&mut self, value: Value) {
+        self.compiled.values.push(value);
+        let placeholder = format!("${}", self.first + self.compiled.values.len() - 1);
+        self.push(&placeholder);
+    }
+
+    fn filter(&mut self, filter: &Filter) {
+        match filter {
+            Filter::And(left, right) | Filter::Or(left, right) => {
+                self.push("(");
+                self.filter(left);
+                self.push(if matches!(filter, Filter::And(..)) {
+                    " AND "
+                } else {
+                    " OR "
+                });
+                self.filter(right);
+                self.push(")");
+            }
+            Filter::Not(filter) => {
+                self.push("(NOT ");
+                self.filter(filter);
+                self.push(")");
+            }
+            Filter::Condition(condition) => {
+                self.push("(");
+                self.condition(condition);
+                self.push(")");
+            }
+        }
+    }
+
+    // Conditions never evaluate to NULL, so that NOT matches exactly what the condition does not
+    fn condition(&mut self, condition: &Condition) {
+        match condition {
+            Condition::Tag(tag) => {
+                let tag = tag.trim();
+                let tag = tag.strip_prefix('#').unwrap_or(tag).to_lowercase();
+
+                self.bind(Value::Text(tag));
+                self.push(" = ANY(todos.tags)");
+            }
+            Condition::List(None) => self.push("todos.list_id IS NULL"),
+            Condition::List(Some(name)) => {
+                self.push(
+                    "EXISTS (SELECT 1 FROM lists l WHERE l.id = todos.list_id \
+                     AND l.deleted_at IS NULL AND lower(l.name) = lower(",
+                );
+                self.bind(Value::Text(name.clone()));
+                self.push("))");
+            }
+            Condition::Title(text) => {
+                self.push("todos.title ILIKE ");
+                self.bind(Value::Text(contains(text)));
+            }
+            Condition::Notes(text) => {
+                self.push("COALESCE(todos.notes ILIKE ");
+                self.bind(Value::Text(contains(text)));
+                self.push(", false)");
+            }
+            Condition::Date(field, comparison, value) => {
+                let column = match field {
+                    DateField::Due => "todos.due_at",
+                    DateField::Start => "todos.start_at",
+                    DateField::Created => "todos.created_at",
+                    DateField::Updated => "todos.updated_at",
+                    DateField::Completed => "todos.completed_at",
+                };
+
+                match value.map(|value| self.resolve(value, *comparison)) {
+                    None => {
+                        self.push(column);
+                        self.push(" IS NULL");
+                    }
+                    Some(Period::Instant(at)) => {
+                        self.push("COALESCE(");
+                        self.push(column);
+                        self.push(operator(*comparison));
+                        self.bind(Value::Time(at));
+                        self.push(", false)");
+                    }
+                    Some(Period::Day(start, end)) => {
+                        self.push("COALESCE(");
+                        self.push(column);
+                        match comparison {
+                            Comparison::Eq => {
+                                self.push(" >= ");
+                                self.bind(Value::Time(start));
+                                self.push(" AND ");
+                                self.push(column);
+                                self.push(" < ");
+                                self.bind(Value::Time(end));
+                            }
+                            Comparison::Lt => {
+                                self.push(" < ");
+                                self.bind(Value::Time(start));
+                            }
+                            Comparison::Le => {
+                                self.push(" < ");
+                                self.bind(Value::Time(end));
+                            }
+                            Comparison::Gt => {
+                                self.push(" >= ");
+                                self.bind(Value::Time(end));
+                            }
+                            Comparison::Ge => {
+                                self.push(" >= ");
+                                self.bind(Value::Time(start));
+                            }
+                        }
+                        self.push(", false)");
+                    }
+                }
+            }
+            Condition::Field(key, _, None) => {
+                self.push("NOT (todos.fields ? ");
+                self.bind(Value::Text(key.clone()));
+                self.push(")");
+            }
+            // Containment, so that the index on the fields is used. Text fields also match the
+            // value as text, like `3` of a text field holding `"3"`.
+            Condition::Field(key, Comparison::Eq, Some(value)) => {
+                self.push("(todos.fields @> ");
+                self.bind(Value::Json(json!({ key: json_value(value) })));
+                if !matches!(value, FieldValue::Text(_)) {
+                    self.push(" OR todos.fields @> ");
+                    self.bind(Value::Json(json!({ key: text(value) })));
+                }
+                self.push(")");
+            }
+            Condition::Field(key, comparison, Some(value)) => {
+                let value = json_value(value);
+
+                self.push("COALESCE(jsonb_typeof(todos.fields -> ");
+                self.bind(Value::Text(key.clone()));
+                self.push(") = jsonb_typeof(");
+                self.bind(Value::Json(value.clone()));
+                self.push(") AND todos.fields -> ");
+                self.bind(Value::Text(key.clone()));
+                self.push(match comparison {
+                    Comparison::Lt => " < ",
+                    Comparison::Le => " <= ",
+                    Comparison::Gt => " > ",
+                    // Equality is containment above
+                    Comparison::Ge | Comparison::Eq => " >= ",
+                });
+                self.bind(Value::Json(value));
+                self.push(", false)");
+            }
+            Condition::Priority(comparison, priority) => {
+                let rank = match priority {
+                    Priority::Low => 1,
+                    Priority::Medium => 2,
+                    Priority::High => 3,
+                };
+
+                self.push("COALESCE(array_position(");
+                self.bind(Value::Priorities(PRIORITIES.to_vec()));
+                self.push(", todos.priority)");
+                self.push(operator(*comparison));
+                self.bind(Value::Int(rank));
+                self.push(", false)");
+            }
+            Condition::Is(State::Done) => self.push("todos.completed_at IS NOT NULL"),
+            Condition::Is(State::Open) => self.push("todos.completed_at IS NULL"),
+            // The same as `blocked` in todos::COLUMNS
+            Condition::Is(State::Blocked) => self.push(
+                "EXISTS (SELECT 1 FROM todo_dependencies d JOIN todos p ON p.id = d.depends_on_id \
+                 WHERE d.todo_id = todos.id AND p.completed_at IS NULL AND p.deleted_at IS NULL)",
+            ),
+            Condition::Is(State::Recurring) => self.push("todos.series_id IS NOT NULL"),
+            Condition::Is(State::Snoozed) => {
+                self.push("COALESCE(todos.start_at > ");
+                self.bind(Value::Time(self.now));
+                self.push(", false)");
+            }
+        }
+    }
+
+    /// The time a value stands for. Compared for equality, a relative time stands for the day it
+    /// falls on.
+    fn resolve(&self, value: DateValue, comparison: Comparison) -> Period {
+        let midnight = |date: NaiveDate| date.and_time(NaiveTime::MIN).and_utc();
+        let day = |date: NaiveDate| {
+            let end = date.succ_opt().map_or(DateTime::<Utc>::MAX_UTC, midnight);
+            Period::Day(midnight(date), end)
+        };
+
+        match value {
+            DateValue::Relative(amount, unit) => {
+                let at = self.now
+                    + match unit {
+                        Unit::Hours => Duration::hours(amount),
+                        Unit::Days => Duration::days(amount),
+                        Unit::Weeks => Duration::weeks(amount),
+                    };
+
+                match comparison {
+                    Comparison::Eq => day(at.date_naive()),
+                    _ => Period::Instant(at),
+                }
+            }
+            DateValue::Today(offset) => day(self.now.date_naive() + Duration::days(offset)),
+            DateValue::Day(date) => day(date),
+            DateValue::Instant(at) => Period::Instant(at.to_utc()),
+        }
+    }
+}
+
+/// A point in time, or the `[start, end)` of a day.
+enum Period {
+    Instant(DateTime<Utc>),
+    Day(DateTime<Utc>, DateTime<Utc>),
+}
+
+/// The JSON a field value is stored as.
+fn json_value(value: &FieldValue) -> serde_json::Value {
+    match value {
+        FieldValue::Text(text) => json!(text),
+        FieldValue::Number(number) => json!(number),
+        FieldValue::Bool(value) => json!(value),
+    }
+}
+
+/// A field value as it reads in a filter, without quotes.
+fn text(value: &FieldValue) -> String {
+    match value {
+        FieldValue::Text(text) => text.clone(),
+        FieldValue::Number(number) => number.to_string(),
+        FieldValue::Bool(value) => value.to_string(),
+    }
+}
+
+/// The SQL operator of a comparison, with spaces around it.
+fn operator(comparison: Comparison) -> &'static str {
+    match comparison {
+        Comparison::Eq => " = ",
+        Comparison::Lt => " < ",
+        Comparison::Le => " <= ",
+        Comparison::Gt => " > ",
+        Comparison::Ge => " >= ",
+    }
+}
+
+/// An ILIKE pattern matching text that contains `text`.
+fn contains(text: &str) -> String {
+    let mut pattern = String::with_capacity(text.len() + 2);
+    pattern.push('%');
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+
+    pattern
+}
@@ -2,5 +2,9 @@ pub mod app;
 pub mod config;
 pub mod controllers;
 pub mod error;
+pub mod filter;
 pub mod models;
+pub mod notifications;
+pub mod storage;
 pub mod tracing;
+pub mod workers;
@@ -0,0 +1,367 @@
+use axum::body::Body;
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::{FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    config::storage::SignedUrlConfig,
+    error::{Error, Result},
+    storage::{Storage, Upload},
+};
+
+use super::todos::Todo;
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: Uuid,
+    pub todo_id: Uuid,
+    pub user_id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    /// Bytes
+    pub size: i64,
+    /// Hex SHA-256 of the contents.
+    pub checksum: String,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+/// A time-limited download link that does not need an `Authorization` header.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedUrl {
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// The query string of a signed URL.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Signature {
+    /// Unix timestamp
+    pub expires: i64,
+    pub signature: String,
+}
+
+fn mac(secret: &str, id: Uuid, expires: i64) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{id}:{expires}").as_bytes());
+    mac
+}
+
+/// Sign the download of attachment `id` until `expires`.
+pub fn sign(secret: &str, id: Uuid, expires: i64) -> String {
+    hex::encode(mac(secret, id, expires).finalize().into_bytes())
+}
+
+/// Check a signature made by [`sign`] and that it has not expired at `now`.
+pub fn verify(secret: &str, id: Uuid, signature: &Signature, now: DateTime<Utc>) -> Result<()> {
+    let valid = hex::decode(&signature.signature).is_ok_and(|bytes| {
+        mac(secret, id, signature.expires)
+            .verify_slice(&bytes)
+            .is_ok()
+    });
+
+    if !valid {
+        return Err(Error::Forbidden("Invalid download signature".into()).into());
+    }
+    if signature.expires < now.timestamp() {
+        return Err(Error::Forbidden("Download link has expired".into()).into());
+    }
+
+    Ok(())
+}
+
+/// Strip any directories from a client supplied file name.
+fn sanitize_filename(filename: &str) -> String {
+    let name: String = filename
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(255)
+        .collect();
+
+    match name.trim() {
+        "" | "." | ".." => "file".into(),
+        name => name.into(),
+    }
+}
+
+/// Serialise uploads and deletions of the same contents, so a blob is never deleted while
+/// another attachment starts using it.
+async fn lock_blob(conn: &mut PgConnection, checksum: &str) -> Result<()> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+        .bind(format!("blob:{checksum}"))
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+impl Attachment {
+    /// The storage key of the contents, shared by all attachments with the same checksum.
+    pub fn key(&self) -> String {
+        blob_key(&self.checksum)
+    }
+
+    /// Attach a finished upload to a todo. Contents that are already stored are not uploaded
+    /// again.
+    #[tracing::instrument(skip(db, storage, upload))]
+    pub async fn create(
+        db: &PgPool,
+        storage: &dyn Storage,
+        user_id: Uuid,
+        todo_id: Uuid,
+        filename: &str,
+        content_type: &str,
+        upload: &mut Upload,
+    ) -> Result<Self> {
+        let checksum = upload.finish().await?;
+
+        let mut txn = db.begin().await?;
+
+        let todo = Todo::find_by_id(&mut *txn, user_id, todo_id).await?;
+
+        lock_blob(&mut txn, &checksum).await?;
+
+        let stored: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM attachments WHERE checksum = $1)")
+                .bind(&checksum)
+                .fetch_one(&mut *txn)
+                .await?;
+
+        if !stored {
+            storage
+                .put(&blob_key(&checksum), upload.path(), content_type, &checksum)
+                .await?;
+        }
+
+        let attachment = sqlx::query_as::<_, Self>(
+            "INSERT INTO attachments (todo_id, user_id, filename, content_type, size, checksum) \
+             VALUES ($1, $2, $3, $4, $5, $6) RETURNING *",
+        )
+        .bind(todo.id)
+        .bind(user_id)
+        .bind(sanitize_filename(filename))
+        .bind(content_type)
+        .bind(upload.size() as i64)
+        .bind(&checksum)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(attachment)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_todo(db: &PgPool, user_id: Uuid, todo_id: Uuid) -> Result<Vec<Self>> {
+        let attachments = sqlx::query_as::<_, Self>(
+            "SELECT a.* FROM attachments a JOIN todos t ON t.id = a.todo_id \
+             WHERE a.todo_id = $1 AND a.user_id = $2 AND t.deleted_at IS NULL \
+             ORDER BY a.created_at, a.id",
+        )
+        .bind(todo_id)
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(attachments)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_id(db: &PgPool, user_id: Uuid, todo_id: Uuid, id: Uuid) -> Result<Self> {
+        let attachment = sqlx::query_as::<_, Self>(
+            "SELECT a.* FROM attachments a JOIN todos t ON t.id = a.todo_id \
+             WHERE a.id = $1 AND a.todo_id = $2 AND a.user_id = $3 AND t.deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(todo_id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        attachment.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Delete an attachment, and its contents once no other attachment uses them.
+    #[tracing::instrument(skip(db, storage))]
+    pub async fn delete(
+        db: &PgPool,
+        storage: &dyn Storage,
+        user_id: Uuid,
+        todo_id: Uuid,
+        id: Uuid,
+    ) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        let attachment = sqlx::query_as::<_, Self>(
+            "DELETE FROM attachments a USING todos t \
+             WHERE a.id = $1 AND a.todo_id = $2 AND a.user_id = $3 \
+             AND t.id = a.todo_id AND t.deleted_at IS NULL RETURNING a.*",
+        )
+        .bind(id)
+        .bind(todo_id)
+        .bind(user_id)
+        .fetch_optional(&mut *txn)
+        .await?
+        .ok_or(Error::EntityNotFound)?;
+
+        let orphans = Self::release(&mut txn, &[attachment.checksum]).await?;
+
+        txn.commit().await?;
+
+        Self::delete_orphans(db, storage, &orphans).await;
+
+        Ok(())
+    }
+
+    /// The distinct checksums of the attachments of `todo_ids`.
+    pub(crate) async fn checksums(
+        conn: &mut PgConnection,
+        todo_ids: &[Uuid],
+    ) -> Result<Vec<String>> {
+        let checksums =
+            sqlx::query_scalar("SELECT DISTINCT checksum FROM attachments WHERE todo_id = ANY($1)")
+                .bind(todo_ids)
+                .fetch_all(conn)
+                .await?;
+
+        Ok(checksums)
+    }
+
+    /// Copy the attachments of the todos `from` to the todos `to`, pairwise. The copies share
+    /// the stored contents of the originals.
+    pub(crate) async fn copy(conn: &mut PgConnection, from: &[Uuid], to: &[Uuid]) -> Result<()> {
+        // In a fixed order, so that concurrent copies do not deadlock
+        let mut checksums = Self::checksums(&mut *conn, from).await?;
+        checksums.sort();
+        for checksum in &checksums {
+            lock_blob(&mut *conn, checksum).await?;
+        }
+
+        sqlx::query(
+            "INSERT INTO attachments (todo_id, user_id, filename, content_type, size, checksum) \
+             SELECT m.new, a.user_id, a.filename, a.content_type, a.size, a.checksum \
+             FROM attachments a JOIN UNNEST($1::uuid[], $2::uuid[]) AS m (old, new) \
+             ON m.old = a.todo_id ORDER BY a.created_at, a.id",
+        )
+        .bind(from)
+        .bind(to)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The checksums of `checksums` that no attachment uses any more. Call after deleting
+    /// attachment rows, in the same transaction, and hand the result to
+    /// [`Attachment::delete_orphans`] once it has been committed.
+    pub(crate) async fn release(
+        conn: &mut PgConnection,
+        checksums: &[String],
+    ) -> Result<Vec<String>> {
+        let orphans = sqlx::query_scalar(
+            "SELECT c FROM UNNEST($1::text[]) AS c \
+             WHERE NOT EXISTS (SELECT 1 FROM attachments WHERE checksum = c)",
+        )
+        .bind(checksums)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(orphans)
+    }
+
+    /// Delete the stored contents of released `orphans`, unless an attachment has started using
+    /// them again since. A failure leaves the contents behind, it is only logged.
+    pub(crate) async fn delete_orphans(db: &PgPool, storage: &dyn Storage, orphans: &[String]) {
+        for checksum in orphans {
+            if let Err(e) = Self::delete_orphan(db, storage, checksum).await {
+                tracing::warn!(
+                    checksum,
+                    "Failed to delete unused attachment contents: {e:?}"
+                );
+            }
+        }
+    }
+
+    async fn delete_orphan(db: &PgPool, storage: &dyn Storage, checksum: &str) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        lock_blob(&mut txn, checksum).await?;
+
+        let shared: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM attachments WHERE checksum = $1)")
+                .bind(checksum)
+                .fetch_one(&mut *txn)
+                .await?;
+
+        if !shared {
+            storage.delete(&blob_key(checksum)).await?;
+        }
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// A download link for one of the user's attachments.
+    #[tracing::instrument(skip(db, cfg))]
+    pub async fn signed_url(
+        db: &PgPool,
+        cfg: &SignedUrlConfig,
+        user_id: Uuid,
+        todo_id: Uuid,
+        id: Uuid,
+    ) -> Result<SignedUrl> {
+        let attachment = Self::find_by_id(db, user_id, todo_id, id).await?;
+
+        let expires = Utc::now().timestamp() + cfg.ttl;
+        let signature = sign(&cfg.secret, attachment.id, expires);
+
+        Ok(SignedUrl {
+            url: format!(
+                "/attachments/{}?expires={expires}&signature={signature}",
+                attachment.id
+            ),
+            expires_at: Utc
+                .timestamp_opt(expires, 0)
+                .single()
+                .ok_or(Error::InternalServerError)?,
+        })
+    }
+
+    /// The attachment behind a signed URL together with its contents.
+    #[tracing::instrument(skip(db, storage, cfg))]
+    pub async fn download(
+        db: &PgPool,
+        storage: &dyn Storage,
+        cfg: &SignedUrlConfig,
+        id: Uuid,
+        signature: &Signature,
+    ) -> Result<(Self, Body)> {
+        verify(&cfg.secret, id, signature, Utc::now())?;
+
+        let attachment = sqlx::query_as::<_, Self>(
+            "SELECT a.* FROM attachments a JOIN todos t ON t.id = a.todo_id \
+             WHERE a.id = $1 AND t.deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(Error::EntityNotFound)?;
+
+        let body = storage.get(&attachment.key()).await?;
+
+        Ok((attachment, body))
+    }
+}
+
+fn blob_key(checksum: &str) -> String {
+    format!("blobs/{}/{checksum}", &checksum[..2])
+}
@@ -0,0 +1,46 @@
+use chrono::Utc;
+use jsonwebtoken::{errors::ErrorKind, Algorithm, Header, Validation};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    config::auth::TokenKeys,
+    error::{AuthError, AuthResult, Result},
+};
+
+/// The claims carried by an access token. `sub` is the id of the authenticated user.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Claims {
+    pub sub: Uuid,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+impl Claims {
+    pub fn new(user_id: Uuid, ttl: i64) -> Self {
+        let now = Utc::now().timestamp();
+
+        Self {
+            sub: user_id,
+            iat: now,
+            exp: now + ttl,
+        }
+    }
+
+    pub fn encode(&self, keys: &TokenKeys) -> Result<String> {
+        let token = jsonwebtoken::encode(&Header::new(Algorithm::RS256), self, &keys.encoding)?;
+
+        Ok(token)
+    }
+
+    pub fn decode(token: &str, keys: &TokenKeys) -> AuthResult<Self> {
+        let data =
+            jsonwebtoken::decode::<Self>(token, &keys.decoding, &Validation::new(Algorithm::RS256))
+                .map_err(|e| match e.kind() {
+                    ErrorKind::ExpiredSignature => AuthError::ExpiredCredentials,
+                    _ => AuthError::MissingCredentials,
+                })?;
+
+        Ok(data.claims)
+    }
+}
@@ -0,0 +1,453 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    filter::Filter,
+};
+
+use super::{
+    lists::List,
+    position::{self, Placement},
+    todos::{self, CompleteScope, Completion, Todo},
+};
+
+/// A status column on the board of a list. Todos reference their column through `status_id`.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardColumn {
+    pub id: Uuid,
+    pub list_id: Uuid,
+    pub name: String,
+    pub position: String,
+    /// Maximum number of cards the column may hold.
+    pub wip_limit: Option<i32>,
+    /// Cards entering this column are completed.
+    pub is_done: bool,
+    pub version: i32,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateColumn<'a> {
+    pub name: Cow<'a, str>,
+    pub wip_limit: Option<i32>,
+    #[serde(default)]
+    pub is_done: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateColumn<'a> {
+    pub name: Option<Cow<'a, str>>,
+    /// `null` removes the limit.
+    #[serde(default, deserialize_with = "super::nullable")]
+    pub wip_limit: Option<Option<i32>>,
+    pub is_done: Option<bool>,
+}
+
+/// Where to move a column: right after `after`, right before `before`, or last when neither is
+/// given.
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveColumn {
+    pub after: Option<Uuid>,
+    pub before: Option<Uuid>,
+}
+
+/// The column to put a card in, `null` taking it off the board.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetStatus {
+    pub column_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Lane {
+    #[serde(flatten)]
+    pub column: BoardColumn,
+    pub cards: Vec<Todo>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Board {
+    pub list: List,
+    pub columns: Vec<Lane>,
+    /// Todos of the list that are not in any column.
+    pub unassigned: Vec<Todo>,
+}
+
+fn validate(wip_limit: Option<i32>, is_done: bool) -> Result<()> {
+    if wip_limit.is_some_and(|limit| limit < 1) {
+        return Err(Error::BadRequest("wipLimit must be at least 1".into()).into());
+    }
+    if is_done && wip_limit.is_some() {
+        return Err(Error::BadRequest("The done column cannot have a WIP limit".into()).into());
+    }
+
+    Ok(())
+}
+
+impl BoardColumn {
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn create(
+        db: &PgPool,
+        user_id: Uuid,
+        list_id: Uuid,
+        dto: &CreateColumn<'_>,
+    ) -> Result<Self> {
+        validate(dto.wip_limit, dto.is_done)?;
+
+        let mut txn = db.begin().await?;
+
+        List::find_by_id(&mut *txn, user_id, list_id).await?;
+
+        if dto.is_done {
+            Self::clear_done(&mut txn, list_id).await?;
+        }
+
+        let position = Self::next_position(&mut txn, list_id).await?;
+
+        let column = sqlx::query_as::<_, Self>(
+            "INSERT INTO board_columns (list_id, name, position, wip_limit, is_done) \
+             VALUES ($1, $2, $3, $4, $5) RETURNING *",
+        )
+        .bind(list_id)
+        .bind(&dto.name)
+        .bind(position)
+        .bind(dto.wip_limit)
+        .bind(dto.is_done)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(column)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_list(db: &PgPool, user_id: Uuid, list_id: Uuid) -> Result<Vec<Self>> {
+        List::find_by_id(db, user_id, list_id).await?;
+
+        let columns = sqlx::query_as::<_, Self>(
+            "SELECT * FROM board_columns WHERE list_id = $1 ORDER BY position, id",
+        )
+        .bind(list_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(columns)
+    }
+
+    /// The position key of a column appended to the board of a list.
+    async fn next_position(conn: &mut PgConnection, list_id: Uuid) -> Result<String> {
+        let last: Option<String> = sqlx::query_scalar(
+            "SELECT position FROM board_columns WHERE list_id = $1 ORDER BY position DESC LIMIT 1",
+        )
+        .bind(list_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        if let Some(key) = position::append(last.as_deref()) {
+            return Ok(key);
+        }
+
+        let ids: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM board_columns WHERE list_id = $1 ORDER BY position, id",
+        )
+        .bind(list_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        position::rebalance(conn, "board_columns", &ids).await
+    }
+
+    /// Lock a column of one of the user's lists for the rest of the transaction.
+    pub(crate) async fn lock(conn: &mut PgConnection, user_id: Uuid, id: Uuid) -> Result<Self> {
+        let column = sqlx::query_as::<_, Self>(
+            "SELECT c.* FROM board_columns c JOIN lists l ON l.id = c.list_id \
+             WHERE c.id = $1 AND l.user_id = $2 AND l.deleted_at IS NULL FOR UPDATE OF c",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await?;
+
+        column.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Fail when a locked column has no room for another card under its WIP limit.
+    pub(crate) async fn check_capacity(&self, conn: &mut PgConnection) -> Result<()> {
+        let Some(limit) = self.wip_limit else {
+            return Ok(());
+        };
+
+        let cards: i64 = sqlx::query_scalar(
+            "SELECT count(*) FROM todos WHERE status_id = $1 AND deleted_at IS NULL",
+        )
+        .bind(self.id)
+        .fetch_one(conn)
+        .await?;
+
+        if cards >= i64::from(limit) {
+            return Err(Error::Conflict(format!(
+                "Column `{}` has reached its WIP limit of {limit}",
+                self.name
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+
+    async fn clear_done(conn: &mut PgConnection, list_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE board_columns SET is_done = false WHERE list_id = $1 AND is_done")
+            .bind(list_id)
+            .execute(conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Update a column, provided it is still at the `expected` version when one is given.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn update(
+        db: &PgPool,
+        user_id: Uuid,
+        list_id: Uuid,
+        id: Uuid,
+        dto: &UpdateColumn<'_>,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let column = Self::lock(&mut txn, user_id, id).await?;
+        if column.list_id != list_id {
+            return Err(Error::EntityNotFound.into());
+        }
+        super::check_version(&column, column.version, expected)?;
+
+        let wip_limit = dto.wip_limit.unwrap_or(column.wip_limit);
+        let is_done = dto.is_done.unwrap_or(column.is_done);
+        validate(wip_limit, is_done)?;
+
+        if is_done && !column.is_done {
+            Self::clear_done(&mut txn, list_id).await?;
+        }
+
+        let column = sqlx::query_as::<_, Self>(
+            "UPDATE board_columns SET name = COALESCE($2, name), wip_limit = $3, is_done = $4, \
+             updated_at = now() WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .bind(&dto.name)
+        .bind(wip_limit)
+        .bind(is_done)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(column)
+    }
+
+    /// Delete a column, provided it is still at the `expected` version when one is given. Its
+    /// cards become unassigned.
+    #[tracing::instrument(skip(db))]
+    pub async fn delete(
+        db: &PgPool,
+        user_id: Uuid,
+        list_id: Uuid,
+        id: Uuid,
+        expected: Option<&[i32]>,
+    ) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        let column = Self::lock(&mut txn, user_id, id).await?;
+        if column.list_id != list_id {
+            return Err(Error::EntityNotFound.into());
+        }
+        super::check_version(&column, column.version, expected)?;
+
+        sqlx::query("DELETE FROM board_columns WHERE id = $1")
+            .bind(id)
+            .execute(&mut *txn)
+            .await?;
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn move_to(
+        db: &PgPool,
+        user_id: Uuid,
+        list_id: Uuid,
+        id: Uuid,
+        dto: &MoveColumn,
+    ) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let column = Self::lock(&mut txn, user_id, id).await?;
+        if column.list_id != list_id {
+            return Err(Error::EntityNotFound.into());
+        }
+
+        let siblings: Vec<(Uuid, String)> = sqlx::query_as(
+            "SELECT id, position FROM board_columns WHERE list_id = $1 AND id <> $2 \
+             ORDER BY position, id",
+        )
+        .bind(list_id)
+        .bind(id)
+        .fetch_all(&mut *txn)
+        .await?;
+
+        match position::place(&siblings, id, dto.after, dto.before)? {
+            Placement::Single(key) => {
+                sqlx::query(
+                    "UPDATE board_columns SET position = $2, updated_at = now() WHERE id = $1",
+                )
+                .bind(id)
+                .bind(key)
+                .execute(&mut *txn)
+                .await?;
+            }
+            Placement::Rebalance(keys) => {
+                let (ids, keys): (Vec<Uuid>, Vec<String>) = keys.into_iter().unzip();
+
+                sqlx::query(
+                    "UPDATE board_columns SET position = v.position \
+                     FROM UNNEST($1::uuid[], $2::text[]) AS v (id, position) \
+                     WHERE board_columns.id = v.id",
+                )
+                .bind(ids)
+                .bind(keys)
+                .execute(&mut *txn)
+                .await?;
+            }
+        }
+
+        let column = Self::lock(&mut txn, user_id, id).await?;
+
+        txn.commit().await?;
+
+        Ok(column)
+    }
+
+    /// The columns of a list, each with its cards in their manual order. Snoozed todos are left
+    /// out.
+    #[tracing::instrument(skip(db))]
+    pub async fn board(db: &PgPool, user_id: Uuid, list_id: Uuid) -> Result<Board> {
+        let list = List::find_by_id(db, user_id, list_id).await?;
+        let columns = Self::find_by_list(db, user_id, list_id).await?;
+
+        let active = Filter::active(None);
+        let todos = Todo::find_all(db, user_id, Some(Some(list_id)), Some(&active)).await?;
+
+        let mut lanes: Vec<Lane> = columns
+            .into_iter()
+            .map(|column| Lane {
+                column,
+                cards: Vec::new(),
+            })
+            .collect();
+        let mut unassigned = Vec::new();
+
+        for todo in todos {
+            match lanes
+                .iter_mut()
+                .find(|lane| Some(lane.column.id) == todo.status_id)
+            {
+                Some(lane) => lane.cards.push(todo),
+                None => unassigned.push(todo),
+            }
+        }
+
+        Ok(Board {
+            list,
+            columns: lanes,
+            unassigned,
+        })
+    }
+
+    /// Move a card to another column of its list. The column is locked while its cards are
+    /// counted, so concurrent moves cannot exceed the WIP limit. Entering the done column
+    /// completes the todo, leaving it reopens the todo. `expected` is the version of the todo
+    /// the client last saw.
+    #[tracing::instrument(skip(db))]
+    pub async fn set_status(
+        db: &PgPool,
+        user_id: Uuid,
+        todo_id: Uuid,
+        dto: &SetStatus,
+        expected: Option<&[i32]>,
+    ) -> Result<Completion> {
+        let mut txn = db.begin().await?;
+
+        let todo = Todo::lock(&mut txn, user_id, todo_id).await?;
+        super::check_version(&todo, todo.version, expected)?;
+
+        let Some(column_id) = dto.column_id else {
+            // Taking a card off the board from the done column reopens it, too
+            let todo = sqlx::query_as::<_, Todo>(&format!(
+                "UPDATE todos SET status_id = NULL, updated_at = now(), \
+                 completed_at = CASE WHEN EXISTS (SELECT 1 FROM board_columns c \
+                 WHERE c.id = todos.status_id AND c.is_done) THEN NULL ELSE completed_at END \
+                 WHERE id = $1 RETURNING {}",
+                todos::COLUMNS
+            ))
+            .bind(todo.id)
+            .fetch_one(&mut *txn)
+            .await?;
+
+            txn.commit().await?;
+
+            return Ok(Completion { todo, next: None });
+        };
+
+        let column = Self::lock(&mut txn, user_id, column_id).await?;
+
+        if Some(column.list_id) != todo.list_id {
+            return Err(Error::BadRequest(
+                "The column belongs to the board of another list".into(),
+            )
+            .into());
+        }
+        if todo.status_id == Some(column.id) {
+            return Ok(Completion { todo, next: None });
+        }
+
+        column.check_capacity(&mut txn).await?;
+
+        let todo = sqlx::query_as::<_, Todo>(&format!(
+            "UPDATE todos SET status_id = $2, updated_at = now(), \
+             completed_at = CASE WHEN $3 THEN completed_at END \
+             WHERE id = $1 RETURNING {}",
+            todos::COLUMNS
+        ))
+        .bind(todo.id)
+        .bind(column.id)
+        .bind(column.is_done)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        let completion = if column.is_done {
+            Todo::complete_locked(&mut txn, todo, CompleteScope::Occurrence).await?
+        } else {
+            Completion { todo, next: None }
+        };
+
+        txn.commit().await?;
+
+        Ok(completion)
+    }
+}
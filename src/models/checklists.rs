@@ -0,0 +1,327 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::{
+    notes,
+    position::{self, Placement},
+    todos::{self, Todo},
+};
+
+/// Longest todo title, in characters. Items may be longer than that.
+const MAX_TITLE_LEN: usize = 255;
+
+/// A lightweight step of a todo, too small to be a todo of its own.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistItem {
+    pub id: Uuid,
+    pub todo_id: Uuid,
+    pub text: String,
+    pub checked: bool,
+    pub position: String,
+    /// Counts the changes to the item.
+    pub version: i32,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewChecklistItem<'a> {
+    pub text: Cow<'a, str>,
+    #[serde(default)]
+    pub checked: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateChecklistItem<'a> {
+    pub text: Option<Cow<'a, str>>,
+    pub checked: Option<bool>,
+}
+
+/// Where to move an item: right after `after`, right before `before`, or last when neither is
+/// given.
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveChecklistItem {
+    pub after: Option<Uuid>,
+    pub before: Option<Uuid>,
+}
+
+impl ChecklistItem {
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn create(
+        db: &PgPool,
+        user_id: Uuid,
+        todo_id: Uuid,
+        dto: &NewChecklistItem<'_>,
+    ) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        // Locking the todo keeps concurrent appends from picking the same key
+        let todo = Todo::lock(&mut txn, user_id, todo_id).await?;
+
+        let position = Self::next_position(&mut txn, todo.id).await?;
+
+        let item = sqlx::query_as::<_, Self>(
+            "INSERT INTO checklist_items (todo_id, text, checked, position) \
+             VALUES ($1, $2, $3, $4) RETURNING *",
+        )
+        .bind(todo.id)
+        .bind(&dto.text)
+        .bind(dto.checked)
+        .bind(position)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        notes::refresh(&mut txn, todo.id).await?;
+
+        txn.commit().await?;
+
+        Ok(item)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_todo(db: &PgPool, user_id: Uuid, todo_id: Uuid) -> Result<Vec<Self>> {
+        let todo = Todo::find_by_id(db, user_id, todo_id).await?;
+
+        let items = sqlx::query_as::<_, Self>(
+            "SELECT * FROM checklist_items WHERE todo_id = $1 ORDER BY position, id",
+        )
+        .bind(todo.id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(items)
+    }
+
+    /// The position key of an item appended to the checklist of a todo.
+    pub(crate) async fn next_position(conn: &mut PgConnection, todo_id: Uuid) -> Result<String> {
+        let last: Option<String> = sqlx::query_scalar(
+            "SELECT position FROM checklist_items WHERE todo_id = $1 ORDER BY position DESC LIMIT 1",
+        )
+        .bind(todo_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        if let Some(key) = position::append(last.as_deref()) {
+            return Ok(key);
+        }
+
+        let ids: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM checklist_items WHERE todo_id = $1 ORDER BY position, id",
+        )
+        .bind(todo_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        position::rebalance(conn, "checklist_items", &ids).await
+    }
+
+    /// Lock an item of one of the user's todos for the rest of the transaction.
+    async fn lock(conn: &mut PgConnection, user_id: Uuid, todo_id: Uuid, id: Uuid) -> Result<Self> {
+        let item = sqlx::query_as::<_, Self>(
+            "SELECT c.* FROM checklist_items c JOIN todos t ON t.id = c.todo_id \
+             WHERE c.id = $1 AND c.todo_id = $2 AND t.user_id = $3 AND t.deleted_at IS NULL \
+             FOR UPDATE OF c",
+        )
+        .bind(id)
+        .bind(todo_id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await?;
+
+        item.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Update an item, provided it is still at the `expected` version when one is given.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn update(
+        db: &PgPool,
+        user_id: Uuid,
+        todo_id: Uuid,
+        id: Uuid,
+        dto: &UpdateChecklistItem<'_>,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, todo_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        let item = sqlx::query_as::<_, Self>(
+            "UPDATE checklist_items SET text = COALESCE($2, text), \
+             checked = COALESCE($3, checked), updated_at = now() WHERE id = $1 RETURNING *",
+        )
+        .bind(current.id)
+        .bind(&dto.text)
+        .bind(dto.checked)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        notes::refresh(&mut txn, todo_id).await?;
+
+        txn.commit().await?;
+
+        Ok(item)
+    }
+
+    /// Flip `checked`.
+    #[tracing::instrument(skip(db))]
+    pub async fn toggle(db: &PgPool, user_id: Uuid, todo_id: Uuid, id: Uuid) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let item = sqlx::query_as::<_, Self>(
+            "UPDATE checklist_items c SET checked = NOT c.checked, updated_at = now() FROM todos t \
+             WHERE c.id = $1 AND c.todo_id = $2 AND t.id = c.todo_id AND t.user_id = $3 \
+             AND t.deleted_at IS NULL RETURNING c.*",
+        )
+        .bind(id)
+        .bind(todo_id)
+        .bind(user_id)
+        .fetch_optional(&mut *txn)
+        .await?
+        .ok_or(Error::EntityNotFound)?;
+
+        notes::refresh(&mut txn, todo_id).await?;
+
+        txn.commit().await?;
+
+        Ok(item)
+    }
+
+    /// Delete an item, provided it is still at the `expected` version when one is given.
+    #[tracing::instrument(skip(db))]
+    pub async fn delete(
+        db: &PgPool,
+        user_id: Uuid,
+        todo_id: Uuid,
+        id: Uuid,
+        expected: Option<&[i32]>,
+    ) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, todo_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        sqlx::query("DELETE FROM checklist_items WHERE id = $1")
+            .bind(current.id)
+            .execute(&mut *txn)
+            .await?;
+
+        notes::refresh(&mut txn, todo_id).await?;
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn move_to(
+        db: &PgPool,
+        user_id: Uuid,
+        todo_id: Uuid,
+        id: Uuid,
+        dto: &MoveChecklistItem,
+    ) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        Self::lock(&mut txn, user_id, todo_id, id).await?;
+
+        let siblings: Vec<(Uuid, String)> = sqlx::query_as(
+            "SELECT id, position FROM checklist_items WHERE todo_id = $1 AND id <> $2 \
+             ORDER BY position, id",
+        )
+        .bind(todo_id)
+        .bind(id)
+        .fetch_all(&mut *txn)
+        .await?;
+
+        match position::place(&siblings, id, dto.after, dto.before)? {
+            Placement::Single(key) => {
+                sqlx::query(
+                    "UPDATE checklist_items SET position = $2, updated_at = now() WHERE id = $1",
+                )
+                .bind(id)
+                .bind(key)
+                .execute(&mut *txn)
+                .await?;
+            }
+            Placement::Rebalance(keys) => {
+                let (ids, keys): (Vec<Uuid>, Vec<String>) = keys.into_iter().unzip();
+
+                sqlx::query(
+                    "UPDATE checklist_items SET position = v.position \
+                     FROM UNNEST($1::uuid[], $2::text[]) AS v (id, position) \
+                     WHERE checklist_items.id = v.id",
+                )
+                .bind(ids)
+                .bind(keys)
+                .execute(&mut *txn)
+                .await?;
+            }
+        }
+
+        let item = Self::lock(&mut txn, user_id, todo_id, id).await?;
+
+        txn.commit().await?;
+
+        Ok(item)
+    }
+
+    /// Turn an item into a todo of its own, placed last in the list of the todo it belonged to.
+    /// A checked item becomes a completed todo. Items too long for a title stay items.
+    #[tracing::instrument(skip(db))]
+    pub async fn convert(db: &PgPool, user_id: Uuid, todo_id: Uuid, id: Uuid) -> Result<Todo> {
+        let mut txn = db.begin().await?;
+
+        let parent = Todo::lock(&mut txn, user_id, todo_id).await?;
+        let item = Self::lock(&mut txn, user_id, todo_id, id).await?;
+        let title = convertible_title(&item.text)?;
+
+        let position = Todo::next_position(&mut txn, user_id, parent.list_id).await?;
+
+        let todo = sqlx::query_as::<_, Todo>(&format!(
+            "INSERT INTO todos (user_id, title, list_id, position, completed_at) \
+             VALUES ($1, $2, $3, $4, CASE WHEN $5 THEN now() END) RETURNING {}",
+            todos::COLUMNS
+        ))
+        .bind(user_id)
+        .bind(title)
+        .bind(parent.list_id)
+        .bind(position)
+        .bind(item.checked)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        sqlx::query("DELETE FROM checklist_items WHERE id = $1")
+            .bind(item.id)
+            .execute(&mut *txn)
+            .await?;
+
+        notes::refresh(&mut txn, todo_id).await?;
+
+        txn.commit().await?;
+
+        Ok(todo)
+    }
+}
+
+/// The title of the todo an item with `text` converts to.
+pub fn convertible_title(text: &str) -> Result<&str> {
+    if text.chars().count() > MAX_TITLE_LEN {
+        return Err(Error::BadRequest(format!(
+            "Only items of at most {MAX_TITLE_LEN} characters can become a todo"
+        ))
+        .into());
+    }
+
+    Ok(text)
+}
@@ -0,0 +1,411 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, PgConnection, PgPool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    notifications::Notification,
+};
+
+use super::{
+    pagination::{CursorPage, Keyset, Pagination},
+    todos::Todo,
+};
+
+/// Columns selected for a [`Comment`], from `comments c JOIN users u ON u.id = c.author_id`.
+const COLUMNS: &str = "c.id, c.todo_id, c.author_id, u.username AS author, c.body, \
+    ARRAY(SELECT mu.username FROM comment_mentions m JOIN users mu ON mu.id = m.user_id \
+    WHERE m.comment_id = c.id ORDER BY mu.username) AS mentions, \
+    c.version, c.edited_at, c.created_at";
+
+/// The order of listed comments, oldest first.
+const KEYSET: Keyset = Keyset {
+    scope: "comments",
+    column: "c.created_at",
+    id: "c.id",
+    kind: "timestamptz",
+};
+
+/// Whether the user `u` can see the todo `t`, and so may be notified about its comments. Todos
+/// are only ever visible to their owner for now.
+const CAN_SEE_TODO: &str = "u.id = t.user_id";
+
+/// Longest username accepted at registration.
+const MAX_USERNAME_LEN: usize = 48;
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment {
+    pub id: Uuid,
+    pub todo_id: Uuid,
+    pub author_id: Uuid,
+    /// Username of the author.
+    pub author: String,
+    pub body: String,
+    /// Usernames of the users mentioned in the body.
+    pub mentions: Vec<String>,
+    pub version: i32,
+    pub edited_at: Option<DateTime<FixedOffset>>,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewComment<'a> {
+    pub body: Cow<'a, str>,
+}
+
+/// A mention whose user has not been notified yet, claimed by the mention worker.
+#[derive(Debug, Clone, FromRow)]
+pub struct PendingMention {
+    pub comment_id: Uuid,
+    /// Notification attempts, this one included.
+    pub attempts: i32,
+    pub user_id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub todo_id: Uuid,
+    pub title: String,
+    pub author: String,
+    pub body: String,
+}
+
+impl PendingMention {
+    pub fn notification(&self) -> Notification {
+        Notification {
+            user_id: self.user_id,
+            username: self.username.clone(),
+            email: self.email.clone(),
+            todo_id: Some(self.todo_id),
+            subject: format!("{} mentioned you on \"{}\"", self.author, self.title),
+            body: self.body.clone(),
+        }
+    }
+}
+
+/// The usernames mentioned as `@username` in `body`, lowercased and in order of first
+/// appearance. An `@` inside a word, as in an email address, is not a mention.
+pub fn parse_mentions(body: &str) -> Vec<String> {
+    let is_name = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+
+    let mut mentions: Vec<String> = Vec::new();
+    let mut previous = None;
+
+    for (i, c) in body.char_indices() {
+        let starts_mention =
+            c == '@' && !previous.is_some_and(|p: char| p.is_alphanumeric() || p == '_');
+        previous = Some(c);
+
+        if !starts_mention {
+            continue;
+        }
+
+        let rest = &body[i + 1..];
+        let end = rest.find(|c: char| !is_name(c)).unwrap_or(rest.len());
+        // Punctuation ending a sentence is not part of the name
+        let name = rest[..end].trim_end_matches(['.', '-']).to_lowercase();
+
+        if !name.is_empty() && name.chars().count() <= MAX_USERNAME_LEN && !mentions.contains(&name)
+        {
+            mentions.push(name);
+        }
+    }
+
+    mentions
+}
+
+impl Comment {
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn create(
+        db: &PgPool,
+        user_id: Uuid,
+        todo_id: Uuid,
+        dto: &NewComment<'_>,
+    ) -> Result<Self> {
+        if dto.body.trim().is_empty() {
+            return Err(Error::BadRequest("Comment body cannot be empty".into()).into());
+        }
+
+        let mut txn = db.begin().await?;
+
+        let todo = Todo::find_by_id(&mut *txn, user_id, todo_id).await?;
+
+        let id: Uuid = sqlx::query_scalar(
+            "INSERT INTO comments (todo_id, author_id, body) VALUES ($1, $2, $3) RETURNING id",
+        )
+        .bind(todo.id)
+        .bind(user_id)
+        .bind(&dto.body)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        Self::save_mentions(&mut txn, id, user_id, &dto.body).await?;
+
+        let comment = Self::find_by_id(&mut *txn, todo.id, id).await?;
+
+        txn.commit().await?;
+
+        Ok(comment)
+    }
+
+    async fn find_by_id<'e, E>(db: E, todo_id: Uuid, id: Uuid) -> Result<Self>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let comment = sqlx::query_as::<_, Self>(&format!(
+            "SELECT {COLUMNS} FROM comments c JOIN users u ON u.id = c.author_id \
+             WHERE c.id = $1 AND c.todo_id = $2 AND c.deleted_at IS NULL"
+        ))
+        .bind(id)
+        .bind(todo_id)
+        .fetch_optional(db)
+        .await?;
+
+        comment.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Lock a comment the user wrote on one of their todos for the rest of the transaction.
+    async fn lock(conn: &mut PgConnection, user_id: Uuid, todo_id: Uuid, id: Uuid) -> Result<Self> {
+        let todo = Todo::find_by_id(&mut *conn, user_id, todo_id).await?;
+
+        let comment = sqlx::query_as::<_, Self>(&format!(
+            "SELECT {COLUMNS} FROM comments c JOIN users u ON u.id = c.author_id \
+             WHERE c.id = $1 AND c.todo_id = $2 AND c.author_id = $3 AND c.deleted_at IS NULL \
+             FOR UPDATE OF c"
+        ))
+        .bind(id)
+        .bind(todo.id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await?;
+
+        comment.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Resolve the mentions in `body` against usernames. Mentions that were removed from the
+    /// body are dropped, those already stored keep their notification state. Authors mentioning
+    /// themselves are ignored.
+    async fn save_mentions(
+        conn: &mut PgConnection,
+        id: Uuid,
+        author_id: Uuid,
+        body: &str,
+    ) -> Result<()> {
+        let usernames = parse_mentions(body);
+
+        sqlx::query(
+            "DELETE FROM comment_mentions m USING users u \
+             WHERE m.comment_id = $1 AND u.id = m.user_id AND NOT lower(u.username) = ANY($2)",
+        )
+        .bind(id)
+        .bind(&usernames)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO comment_mentions (comment_id, user_id) \
+             SELECT $1, u.id FROM users u WHERE lower(u.username) = ANY($2) AND u.id <> $3 \
+             ON CONFLICT (comment_id, user_id) DO NOTHING",
+        )
+        .bind(id)
+        .bind(&usernames)
+        .bind(author_id)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The comments on a todo, oldest first.
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_todo(
+        db: &PgPool,
+        user_id: Uuid,
+        todo_id: Uuid,
+        pagination: &Pagination,
+    ) -> Result<CursorPage<Self>> {
+        let todo = Todo::find_by_id(db, user_id, todo_id).await?;
+
+        let sql = format!(
+            "SELECT {COLUMNS} FROM comments c JOIN users u ON u.id = c.author_id \
+             WHERE c.todo_id = $1 AND c.deleted_at IS NULL{}",
+            pagination.clause(&KEYSET, 2)?
+        );
+
+        let comments = pagination
+            .bind(sqlx::query_as::<_, Self>(&sql).bind(todo.id))
+            .fetch_all(db)
+            .await?;
+
+        Ok(pagination.page(&KEYSET, comments, |comment| {
+            (comment.created_at.to_rfc3339(), comment.id)
+        }))
+    }
+
+    /// Edit the body of a comment, provided it is still at the `expected` version when one is
+    /// given. Only its author may do so.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn update(
+        db: &PgPool,
+        user_id: Uuid,
+        todo_id: Uuid,
+        id: Uuid,
+        dto: &NewComment<'_>,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        if dto.body.trim().is_empty() {
+            return Err(Error::BadRequest("Comment body cannot be empty".into()).into());
+        }
+
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, todo_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        sqlx::query("UPDATE comments SET body = $2, edited_at = now() WHERE id = $1")
+            .bind(current.id)
+            .bind(&dto.body)
+            .execute(&mut *txn)
+            .await?;
+
+        Self::save_mentions(&mut txn, id, user_id, &dto.body).await?;
+
+        let comment = Self::find_by_id(&mut *txn, current.todo_id, id).await?;
+
+        txn.commit().await?;
+
+        Ok(comment)
+    }
+
+    /// Soft delete a comment, provided it is still at the `expected` version when one is given.
+    /// Only its author may do so.
+    #[tracing::instrument(skip(db))]
+    pub async fn delete(
+        db: &PgPool,
+        user_id: Uuid,
+        todo_id: Uuid,
+        id: Uuid,
+        expected: Option<&[i32]>,
+    ) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, todo_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        sqlx::query("UPDATE comments SET deleted_at = now() WHERE id = $1")
+            .bind(current.id)
+            .execute(&mut *txn)
+            .await?;
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Claim up to `limit` mentions that still need a notification for `lease` seconds under
+    /// `token`, counting the attempt. Like reminders, the claim is committed on its own and each
+    /// mention is marked on its own afterwards. Mentions in deleted comments, and those of users
+    /// who can no longer see the todo, are left alone.
+    pub async fn claim_mentions<'e, E>(
+        db: E,
+        token: Uuid,
+        limit: i64,
+        max_attempts: i32,
+        lease: i64,
+    ) -> Result<Vec<PendingMention>>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let mentions = sqlx::query_as::<_, PendingMention>(&format!(
+            "WITH claimed AS ( \
+             UPDATE comment_mentions SET claimed_at = now(), claim_token = $4, \
+             attempts = attempts + 1 \
+             WHERE (comment_id, user_id) IN ( \
+             SELECT m.comment_id, m.user_id FROM comment_mentions m \
+             JOIN comments c ON c.id = m.comment_id \
+             JOIN todos t ON t.id = c.todo_id \
+             JOIN users u ON u.id = m.user_id \
+             WHERE m.notified_at IS NULL AND m.attempts < $2 \
+             AND (m.claimed_at IS NULL OR m.claimed_at <= now() - make_interval(secs => $3)) \
+             AND c.deleted_at IS NULL AND t.deleted_at IS NULL AND {CAN_SEE_TODO} \
+             ORDER BY m.created_at LIMIT $1 \
+             FOR UPDATE OF m SKIP LOCKED) \
+             RETURNING comment_id, user_id, attempts, created_at) \
+             SELECT m.comment_id, m.attempts, u.id AS user_id, u.username, u.email, \
+             t.id AS todo_id, t.title, a.username AS author, c.body \
+             FROM claimed m \
+             JOIN comments c ON c.id = m.comment_id \
+             JOIN todos t ON t.id = c.todo_id \
+             JOIN users u ON u.id = m.user_id \
+             JOIN users a ON a.id = c.author_id \
+             ORDER BY m.created_at"
+        ))
+        .bind(limit)
+        .bind(max_attempts)
+        .bind(lease as f64)
+        .bind(token)
+        .fetch_all(db)
+        .await?;
+
+        Ok(mentions)
+    }
+
+    /// Restart the lease of a mention claimed under `token`, right before its user is notified.
+    /// Returns false when the claim is no longer held by `token`.
+    pub async fn renew_mention<'e, E>(
+        db: E,
+        comment_id: Uuid,
+        user_id: Uuid,
+        token: Uuid,
+    ) -> Result<bool>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let result = sqlx::query(
+            "UPDATE comment_mentions SET claimed_at = now() \
+             WHERE comment_id = $1 AND user_id = $2 AND claim_token = $3 AND notified_at IS NULL",
+        )
+        .bind(comment_id)
+        .bind(user_id)
+        .bind(token)
+        .execute(db)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn mark_notified<'e, E>(db: E, comment_id: Uuid, user_id: Uuid) -> Result<()>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(
+            "UPDATE comment_mentions SET notified_at = now(), claimed_at = NULL, \
+             claim_token = NULL WHERE comment_id = $1 AND user_id = $2",
+        )
+        .bind(comment_id)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_failed<'e, E>(db: E, comment_id: Uuid, user_id: Uuid) -> Result<()>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(
+            "UPDATE comment_mentions SET claimed_at = NULL, claim_token = NULL \
+             WHERE comment_id = $1 AND user_id = $2",
+        )
+        .bind(comment_id)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
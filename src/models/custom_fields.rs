@@ -0,0 +1,389 @@
+//! Custom fields add typed metadata to the todos of a list, such as a customer or story points.
+//! The values of a todo are in `todos.fields`, by the key of their field.
+
+use std::borrow::Cow;
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::{Error, Report, Result};
+
+use super::lists::List;
+
+/// Longest name, and key, of a field.
+const MAX_NAME_LEN: usize = 100;
+
+/// Longest value of a text field, in characters.
+const MAX_TEXT_LEN: usize = 1000;
+
+/// The values of `todos.fields` a todo keeps when it moves from its list to the list `$2`: those
+/// of the fields both lists have, of the same kind. A select value must be one of the options of
+/// the new list.
+pub(crate) const KEPT_ON_MOVE: &str = "(SELECT COALESCE(jsonb_object_agg(v.key, v.value), '{}') \
+    FROM jsonb_each(todos.fields) v \
+    JOIN custom_fields n ON n.list_id = $2 AND n.key = v.key \
+    JOIN custom_fields o ON o.list_id = todos.list_id AND o.key = v.key AND o.kind = n.kind \
+    WHERE n.kind <> 'select' OR v.value #>> '{}' = ANY(n.options))";
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum FieldKind {
+    Text,
+    Number,
+    /// A day, stored as `YYYY-MM-DD` so that values compare in order.
+    Date,
+    /// One of the options of the field.
+    Select,
+    Checkbox,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomField {
+    pub id: Uuid,
+    pub list_id: Uuid,
+    pub name: String,
+    /// The name in lowercase words joined by underscores. Values and filters refer to the field
+    /// by its key.
+    pub key: String,
+    pub kind: FieldKind,
+    /// The choices of a select field, empty for the other kinds.
+    pub options: Vec<String>,
+    pub version: i32,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewCustomField<'a> {
+    pub name: Cow<'a, str>,
+    pub kind: FieldKind,
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+/// The kind of a field cannot change, as its values would not fit.
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCustomField<'a> {
+    /// Renaming a field renames its key, in the values of the todos too.
+    pub name: Option<Cow<'a, str>>,
+    /// Values that are no longer an option are cleared.
+    pub options: Option<Vec<String>>,
+}
+
+/// The key of a field named `name`: its words in lowercase, joined by underscores.
+pub fn field_key(name: &str) -> Result<String> {
+    let key = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("_");
+
+    if key.is_empty() {
+        return Err(Error::BadRequest("A field name needs a letter or a digit".into()).into());
+    }
+    if name.trim().chars().count() > MAX_NAME_LEN || key.chars().count() > MAX_NAME_LEN {
+        return Err(Error::BadRequest(format!(
+            "A field name has at most {MAX_NAME_LEN} characters"
+        ))
+        .into());
+    }
+
+    Ok(key)
+}
+
+/// Check the options of a field of `kind`: select fields need distinct, non-empty options, and
+/// the other kinds take none.
+fn validate_options(kind: FieldKind, options: &[String]) -> Result<Vec<String>> {
+    if kind != FieldKind::Select {
+        if !options.is_empty() {
+            return Err(Error::BadRequest("Only select fields have options".into()).into());
+        }
+        return Ok(Vec::new());
+    }
+
+    let mut validated: Vec<String> = Vec::with_capacity(options.len());
+    for option in options.iter().map(|option| option.trim()) {
+        if option.is_empty() {
+            return Err(Error::BadRequest("Options cannot be empty".into()).into());
+        }
+        if !validated.iter().any(|candidate| candidate == option) {
+            validated.push(option.to_string());
+        }
+    }
+
+    if validated.is_empty() {
+        return Err(Error::BadRequest("A select field needs options".into()).into());
+    }
+
+    Ok(validated)
+}
+
+/// Apply `changes`, by field name or key, to the values of a todo in a list with `fields`. A
+/// `null` clears a value.
+pub fn merge_values(
+    fields: &[CustomField],
+    current: &Map<String, Value>,
+    changes: &Map<String, Value>,
+) -> Result<Map<String, Value>> {
+    let mut merged = current.clone();
+
+    for (name, value) in changes {
+        let key = field_key(name)?;
+        let field = fields
+            .iter()
+            .find(|field| field.key == key)
+            .ok_or_else(|| Error::BadRequest(format!("Unknown field `{name}`")))?;
+
+        match value {
+            Value::Null => merged.remove(&key),
+            value => merged.insert(key, field.check(value)?),
+        };
+    }
+
+    Ok(merged)
+}
+
+/// Turn a unique violation on the key of a field into an error the client can act on.
+fn duplicate(e: sqlx::Error, key: &str) -> Report {
+    match e {
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            Error::EntityAlreadyExists(format!("The list already has a field `{key}`")).into()
+        }
+        e => Report::from(e),
+    }
+}
+
+impl CustomField {
+    /// Check a value for the field, returning it as it is stored.
+    pub fn check(&self, value: &Value) -> Result<Value> {
+        let invalid = |expected: &str| {
+            Report::from(Error::BadRequest(format!(
+                "`{}` takes {expected}",
+                self.key
+            )))
+        };
+
+        match (self.kind, value) {
+            (FieldKind::Text, Value::String(text)) if text.chars().count() <= MAX_TEXT_LEN => {
+                Ok(value.clone())
+            }
+            (FieldKind::Text, _) => Err(invalid(&format!(
+                "text of at most {MAX_TEXT_LEN} characters"
+            ))),
+            (FieldKind::Number, Value::Number(_)) => Ok(value.clone()),
+            (FieldKind::Number, _) => Err(invalid("a number")),
+            (FieldKind::Date, Value::String(text)) => NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .map(|day| Value::String(day.format("%Y-%m-%d").to_string()))
+                .map_err(|_| invalid("a day like `2025-05-01`")),
+            (FieldKind::Date, _) => Err(invalid("a day like `2025-05-01`")),
+            (FieldKind::Select, Value::String(text)) if self.options.contains(text) => {
+                Ok(value.clone())
+            }
+            (FieldKind::Select, _) => Err(invalid(&format!(
+                "one of {}",
+                self.options
+                    .iter()
+                    .map(|option| format!("`{option}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+            (FieldKind::Checkbox, Value::Bool(_)) => Ok(value.clone()),
+            (FieldKind::Checkbox, _) => Err(invalid("`true` or `false`")),
+        }
+    }
+
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn create(
+        db: &PgPool,
+        user_id: Uuid,
+        list_id: Uuid,
+        dto: &NewCustomField<'_>,
+    ) -> Result<Self> {
+        let key = field_key(&dto.name)?;
+        let options = validate_options(dto.kind, &dto.options)?;
+
+        let mut txn = db.begin().await?;
+
+        let list = List::find_by_id(&mut *txn, user_id, list_id).await?;
+
+        let field = sqlx::query_as::<_, Self>(
+            "INSERT INTO custom_fields (list_id, name, key, kind, options) \
+             VALUES ($1, $2, $3, $4, $5) RETURNING *",
+        )
+        .bind(list.id)
+        .bind(dto.name.trim())
+        .bind(&key)
+        .bind(dto.kind)
+        .bind(&options)
+        .fetch_one(&mut *txn)
+        .await
+        .map_err(|e| duplicate(e, &key))?;
+
+        txn.commit().await?;
+
+        Ok(field)
+    }
+
+    /// The fields of one of the user's lists, oldest first.
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_list(db: &PgPool, user_id: Uuid, list_id: Uuid) -> Result<Vec<Self>> {
+        let mut conn = db.acquire().await?;
+
+        let list = List::find_by_id(&mut *conn, user_id, list_id).await?;
+
+        Self::find_all(&mut conn, list.id).await
+    }
+
+    async fn find_all(conn: &mut PgConnection, list_id: Uuid) -> Result<Vec<Self>> {
+        let fields = sqlx::query_as::<_, Self>(
+            "SELECT * FROM custom_fields WHERE list_id = $1 ORDER BY created_at, id",
+        )
+        .bind(list_id)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(fields)
+    }
+
+    /// Lock a field of one of the user's lists for the rest of the transaction.
+    async fn lock(conn: &mut PgConnection, user_id: Uuid, list_id: Uuid, id: Uuid) -> Result<Self> {
+        let list = List::find_by_id(&mut *conn, user_id, list_id).await?;
+
+        let field = sqlx::query_as::<_, Self>(
+            "SELECT * FROM custom_fields WHERE id = $1 AND list_id = $2 FOR UPDATE",
+        )
+        .bind(id)
+        .bind(list.id)
+        .fetch_optional(conn)
+        .await?;
+
+        field.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Rename a field or change its options, provided it is still at the `expected` version when
+    /// one is given.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn update(
+        db: &PgPool,
+        user_id: Uuid,
+        list_id: Uuid,
+        id: Uuid,
+        dto: &UpdateCustomField<'_>,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, list_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        let key = dto.name.as_deref().map(field_key).transpose()?;
+        let options = dto
+            .options
+            .as_deref()
+            .map(|options| validate_options(current.kind, options))
+            .transpose()?;
+
+        let field = sqlx::query_as::<_, Self>(
+            "UPDATE custom_fields SET name = COALESCE($2, name), key = COALESCE($3, key), \
+             options = COALESCE($4, options), updated_at = now() WHERE id = $1 RETURNING *",
+        )
+        .bind(current.id)
+        .bind(dto.name.as_deref().map(str::trim))
+        .bind(&key)
+        .bind(&options)
+        .fetch_one(&mut *txn)
+        .await
+        .map_err(|e| duplicate(e, key.as_deref().unwrap_or_default()))?;
+
+        if field.key != current.key {
+            sqlx::query(
+                "UPDATE todos SET fields = fields - $2::text || jsonb_build_object($3::text, fields -> $2), \
+                 updated_at = now() WHERE list_id = $1 AND fields ? $2",
+            )
+            .bind(field.list_id)
+            .bind(&current.key)
+            .bind(&field.key)
+            .execute(&mut *txn)
+            .await?;
+        }
+        if options.is_some() {
+            sqlx::query(
+                "UPDATE todos SET fields = fields - $2::text, updated_at = now() \
+                 WHERE list_id = $1 AND fields ? $2 AND NOT fields ->> $2 = ANY($3)",
+            )
+            .bind(field.list_id)
+            .bind(&field.key)
+            .bind(&field.options)
+            .execute(&mut *txn)
+            .await?;
+        }
+
+        txn.commit().await?;
+
+        Ok(field)
+    }
+
+    /// Delete a field and its values, provided it is still at the `expected` version when one is
+    /// given.
+    #[tracing::instrument(skip(db))]
+    pub async fn delete(
+        db: &PgPool,
+        user_id: Uuid,
+        list_id: Uuid,
+        id: Uuid,
+        expected: Option<&[i32]>,
+    ) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, list_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        sqlx::query(
+            "UPDATE todos SET fields = fields - $2::text, updated_at = now() \
+             WHERE list_id = $1 AND fields ? $2",
+        )
+        .bind(current.list_id)
+        .bind(&current.key)
+        .execute(&mut *txn)
+        .await?;
+
+        sqlx::query("DELETE FROM custom_fields WHERE id = $1")
+            .bind(current.id)
+            .execute(&mut *txn)
+            .await?;
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Apply `changes` to the values of a todo in `list_id`, see [`merge_values`]. Todos in the
+    /// Inbox have no fields.
+    pub(crate) async fn merge(
+        conn: &mut PgConnection,
+        list_id: Option<Uuid>,
+        current: &Map<String, Value>,
+        changes: &Map<String, Value>,
+    ) -> Result<Map<String, Value>> {
+        if changes.is_empty() {
+            return Ok(current.clone());
+        }
+
+        let Some(list_id) = list_id else {
+            return Err(
+                Error::BadRequest("Todos in the Inbox have no custom fields".into()).into(),
+            );
+        };
+        let fields = Self::find_all(conn, list_id).await?;
+
+        merge_values(&fields, current, changes)
+    }
+}
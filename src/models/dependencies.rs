@@ -0,0 +1,200 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, PgPool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::todos::{self, Todo};
+
+/// `todo_id` cannot be completed before `depends_on_id` is.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Dependency {
+    pub todo_id: Uuid,
+    pub depends_on_id: Uuid,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewDependency {
+    pub depends_on: Uuid,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Node {
+    pub id: Uuid,
+    pub title: String,
+    pub completed: bool,
+    pub blocked: bool,
+    /// Open and not blocked, i.e. something that can be worked on right now.
+    pub actionable: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Dependency>,
+}
+
+/// The path from `from` to `to` following `(todo, depends_on)` edges, both ends included.
+pub fn find_path(edges: &[(Uuid, Uuid)], from: Uuid, to: Uuid) -> Option<Vec<Uuid>> {
+    let mut adjacent: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for (todo, depends_on) in edges {
+        adjacent.entry(*todo).or_default().push(*depends_on);
+    }
+
+    let mut parents: HashMap<Uuid, Uuid> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            let mut path = vec![to];
+            while let Some(parent) = parents.get(path.last()?) {
+                path.push(*parent);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for next in adjacent.get(&current).into_iter().flatten() {
+            if *next != from && !parents.contains_key(next) {
+                parents.insert(*next, current);
+                queue.push_back(*next);
+            }
+        }
+    }
+
+    None
+}
+
+impl Dependency {
+    /// Make `todo_id` depend on `dto.depends_on`. Edges that would close a cycle are rejected.
+    #[tracing::instrument(skip(db))]
+    pub async fn create(
+        db: &PgPool,
+        user_id: Uuid,
+        todo_id: Uuid,
+        dto: &NewDependency,
+    ) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let todo = Todo::find_by_id(&mut *txn, user_id, todo_id).await?;
+        let depends_on = Todo::find_by_id(&mut *txn, user_id, dto.depends_on).await?;
+
+        // Serialise changes to a user's graph, so that two concurrent inserts cannot each add
+        // half of a cycle
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1::text, 0))")
+            .bind(user_id)
+            .execute(&mut *txn)
+            .await?;
+
+        let edges: Vec<(Uuid, Uuid)> = sqlx::query_as(
+            "SELECT d.todo_id, d.depends_on_id FROM todo_dependencies d \
+             JOIN todos t ON t.id = d.todo_id WHERE t.user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_all(&mut *txn)
+        .await?;
+
+        if let Some(path) = find_path(&edges, depends_on.id, todo.id) {
+            let path: Vec<String> = std::iter::once(todo.id)
+                .chain(path)
+                .map(|id| id.to_string())
+                .collect();
+
+            return Err(Error::Conflict(format!(
+                "Dependency would create a cycle: {}",
+                path.join(" -> ")
+            ))
+            .into());
+        }
+
+        let dependency = sqlx::query_as::<_, Self>(
+            "INSERT INTO todo_dependencies (todo_id, depends_on_id) VALUES ($1, $2) \
+             ON CONFLICT (todo_id, depends_on_id) DO UPDATE SET todo_id = EXCLUDED.todo_id \
+             RETURNING *",
+        )
+        .bind(todo.id)
+        .bind(depends_on.id)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(dependency)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn delete<'e, E>(db: E, user_id: Uuid, todo_id: Uuid, depends_on: Uuid) -> Result<()>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let result = sqlx::query(
+            "DELETE FROM todo_dependencies d USING todos t \
+             WHERE d.todo_id = $1 AND d.depends_on_id = $2 AND t.id = d.todo_id AND t.user_id = $3",
+        )
+        .bind(todo_id)
+        .bind(depends_on)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::EntityNotFound.into());
+        }
+
+        Ok(())
+    }
+
+    /// The todos `todo_id` depends on.
+    #[tracing::instrument(skip(db))]
+    pub async fn prerequisites<'e, E>(db: E, user_id: Uuid, todo_id: Uuid) -> Result<Vec<Todo>>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let todos = sqlx::query_as::<_, Todo>(&format!(
+            "SELECT {} FROM todos JOIN todo_dependencies d ON d.depends_on_id = todos.id \
+             WHERE d.todo_id = $1 AND todos.user_id = $2 AND todos.deleted_at IS NULL \
+             ORDER BY todos.position, todos.id",
+            todos::COLUMNS
+        ))
+        .bind(todo_id)
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(todos)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn graph(db: &PgPool, user_id: Uuid) -> Result<Graph> {
+        let nodes = Todo::find_all(db, user_id, None, None)
+            .await?
+            .into_iter()
+            .map(|todo| Node {
+                id: todo.id,
+                completed: todo.completed_at.is_some(),
+                actionable: todo.completed_at.is_none() && !todo.blocked,
+                blocked: todo.blocked,
+                title: todo.title,
+            })
+            .collect();
+
+        let edges = sqlx::query_as::<_, Self>(
+            "SELECT d.* FROM todo_dependencies d JOIN todos t ON t.id = d.todo_id \
+             JOIN todos p ON p.id = d.depends_on_id \
+             WHERE t.user_id = $1 AND t.deleted_at IS NULL AND p.deleted_at IS NULL \
+             ORDER BY d.created_at",
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(Graph { nodes, edges })
+    }
+}
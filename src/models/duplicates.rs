@@ -0,0 +1,294 @@
+//! Deep copies of todos and lists. A copy takes along the checklists, tags and custom field values,
+//! and on request the comments and attachments. References between the copied todos, their
+//! dependencies and `#todo-` links in notes, point at the copies.
+//!
+//! Todos do not nest: the subtasks of a todo are the items of its checklist, and are copied with
+//! it.
+
+use std::{borrow::Cow, collections::HashMap};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::{
+    attachments::Attachment,
+    boards::BoardColumn,
+    lists::{CreateList, List},
+    notes,
+    todos::{self, Todo},
+};
+
+/// What a copy takes along besides the todos and their checklists, that is their subtasks.
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyOptions {
+    #[serde(default)]
+    pub comments: bool,
+    /// The copies share the stored contents of the originals.
+    #[serde(default)]
+    pub attachments: bool,
+    /// Start the copies over: open, out of the done column, with their checklists unchecked.
+    #[serde(default)]
+    pub reset: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateTodo<'a> {
+    /// The title of the copy, the original's by default.
+    pub title: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub options: CopyOptions,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateList<'a> {
+    /// The name of the copy, the original's by default.
+    pub name: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub options: CopyOptions,
+}
+
+/// The copy of a list with its todos.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicatedList {
+    pub list: List,
+    pub todos: Vec<Todo>,
+}
+
+/// Copy `sources` to `list_id`, at `positions`, with the board columns of `columns` mapped onto
+/// theirs. The copies get the titles of `sources`. Returns the ids of the copies, in the order of
+/// `sources`.
+async fn copy_todos(
+    conn: &mut PgConnection,
+    sources: &[Todo],
+    list_id: Option<Uuid>,
+    positions: &[String],
+    columns: &HashMap<Uuid, Uuid>,
+    options: CopyOptions,
+) -> Result<Vec<Uuid>> {
+    let copies: HashMap<Uuid, Uuid> = sources
+        .iter()
+        .map(|todo| (todo.id, Uuid::new_v4()))
+        .collect();
+    let from: Vec<Uuid> = sources.iter().map(|todo| todo.id).collect();
+    let to: Vec<Uuid> = from.iter().map(|id| copies[id]).collect();
+
+    for ((todo, id), position) in sources.iter().zip(&to).zip(positions) {
+        let notes = todo.notes.as_deref().map(|text| {
+            let text = notes::remap(text, &copies);
+            match options.reset {
+                true => notes::uncheck(&text),
+                false => text,
+            }
+        });
+        let status_id = todo
+            .status_id
+            .map(|status| columns.get(&status).copied().unwrap_or(status));
+
+        // The copy is not an occurrence of the recurrence of the original
+        sqlx::query(
+            "INSERT INTO todos (id, user_id, title, notes, due_at, start_at, notify_on_start, \
+             completed_at, list_id, position, status_id, tags, priority, estimate_minutes, fields) \
+             SELECT $2, user_id, $3, $4, due_at, start_at, notify_on_start, \
+             CASE WHEN $5 THEN NULL ELSE completed_at END, $6, $7, \
+             CASE WHEN $5 AND EXISTS (SELECT 1 FROM board_columns c WHERE c.id = $8 AND c.is_done) \
+             THEN NULL ELSE $8 END, tags, priority, estimate_minutes, fields \
+             FROM todos WHERE id = $1",
+        )
+        .bind(todo.id)
+        .bind(id)
+        .bind(&todo.title)
+        .bind(notes)
+        .bind(options.reset)
+        .bind(list_id)
+        .bind(position)
+        .bind(status_id)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query(
+        "INSERT INTO checklist_items (todo_id, text, checked, position) \
+         SELECT m.new, c.text, c.checked AND NOT $3, c.position FROM checklist_items c \
+         JOIN UNNEST($1::uuid[], $2::uuid[]) AS m (old, new) ON m.old = c.todo_id \
+         ORDER BY c.position, c.id",
+    )
+    .bind(&from)
+    .bind(&to)
+    .bind(options.reset)
+    .execute(&mut *conn)
+    .await?;
+
+    // Prerequisites among the originals are replaced by their copies; the others are shared
+    sqlx::query(
+        "INSERT INTO todo_dependencies (todo_id, depends_on_id) \
+         SELECT m.new, COALESCE(p.new, d.depends_on_id) FROM todo_dependencies d \
+         JOIN UNNEST($1::uuid[], $2::uuid[]) AS m (old, new) ON m.old = d.todo_id \
+         LEFT JOIN UNNEST($1::uuid[], $2::uuid[]) AS p (old, new) ON p.old = d.depends_on_id \
+         JOIN todos t ON t.id = d.depends_on_id AND t.deleted_at IS NULL",
+    )
+    .bind(&from)
+    .bind(&to)
+    .execute(&mut *conn)
+    .await?;
+
+    if options.comments {
+        sqlx::query(
+            "INSERT INTO comments (todo_id, author_id, body, edited_at, created_at) \
+             SELECT m.new, c.author_id, c.body, c.edited_at, c.created_at FROM comments c \
+             JOIN UNNEST($1::uuid[], $2::uuid[]) AS m (old, new) ON m.old = c.todo_id \
+             WHERE c.deleted_at IS NULL",
+        )
+        .bind(&from)
+        .bind(&to)
+        .execute(&mut *conn)
+        .await?;
+    }
+    if options.attachments {
+        Attachment::copy(&mut *conn, &from, &to).await?;
+    }
+
+    // Render the notes of the copies, whose references were remapped
+    for (todo, id) in sources.iter().zip(&to) {
+        if todo.notes.is_some() {
+            notes::save(&mut *conn, *id).await?;
+        }
+    }
+
+    Ok(to)
+}
+
+impl Todo {
+    /// Copy one of the user's todos to the end of its list. The copy depends on the todos the
+    /// original depends on.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn duplicate(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        dto: &DuplicateTodo<'_>,
+    ) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let mut todo = Self::lock(&mut txn, user_id, id).await?;
+        if let Some(title) = &dto.title {
+            todo.title = title.trim().to_string();
+            if todo.title.is_empty() {
+                return Err(Error::BadRequest("A todo needs a title".into()).into());
+            }
+        }
+
+        // The copy joins the board column of the original, unless it leaves the done column
+        if let Some(status_id) = todo.status_id {
+            let column = BoardColumn::lock(&mut txn, user_id, status_id).await?;
+            if !(dto.options.reset && column.is_done) {
+                column.check_capacity(&mut txn).await?;
+            }
+        }
+
+        let position = Self::next_position(&mut txn, user_id, todo.list_id).await?;
+        let copies = copy_todos(
+            &mut txn,
+            std::slice::from_ref(&todo),
+            todo.list_id,
+            &[position],
+            &HashMap::new(),
+            dto.options,
+        )
+        .await?;
+
+        let copy = Self::find_by_id(&mut *txn, user_id, copies[0]).await?;
+
+        txn.commit().await?;
+
+        Ok(copy)
+    }
+}
+
+impl List {
+    /// Copy one of the user's lists to the end, with its todos, board columns and custom fields.
+    /// Dependencies on todos of other lists are shared by the copies.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn duplicate(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        dto: &DuplicateList<'_>,
+    ) -> Result<DuplicatedList> {
+        let mut txn = db.begin().await?;
+
+        let original = Self::find_by_id(&mut *txn, user_id, id).await?;
+        let name = dto.name.as_deref().unwrap_or(&original.name).trim();
+        if name.is_empty() {
+            return Err(Error::BadRequest("A list needs a name".into()).into());
+        }
+        let list = Self::insert(&mut txn, user_id, &CreateList { name: name.into() }).await?;
+
+        sqlx::query(
+            "INSERT INTO custom_fields (list_id, name, key, kind, options) \
+             SELECT $2, name, key, kind, options FROM custom_fields WHERE list_id = $1 \
+             ORDER BY created_at, id",
+        )
+        .bind(original.id)
+        .bind(list.id)
+        .execute(&mut *txn)
+        .await?;
+
+        let from: Vec<Uuid> =
+            sqlx::query_scalar("SELECT id FROM board_columns WHERE list_id = $1 FOR UPDATE")
+                .bind(original.id)
+                .fetch_all(&mut *txn)
+                .await?;
+        let columns: HashMap<Uuid, Uuid> = from.iter().map(|id| (*id, Uuid::new_v4())).collect();
+        let to: Vec<Uuid> = from.iter().map(|id| columns[id]).collect();
+
+        sqlx::query(
+            "INSERT INTO board_columns (id, list_id, name, position, wip_limit, is_done) \
+             SELECT m.new, $3, c.name, c.position, c.wip_limit, c.is_done FROM board_columns c \
+             JOIN UNNEST($1::uuid[], $2::uuid[]) AS m (old, new) ON m.old = c.id",
+        )
+        .bind(&from)
+        .bind(&to)
+        .bind(list.id)
+        .execute(&mut *txn)
+        .await?;
+
+        let todos: Vec<Todo> = sqlx::query_as(&format!(
+            "SELECT {} FROM todos WHERE list_id = $1 AND deleted_at IS NULL \
+             ORDER BY position, id FOR UPDATE OF todos",
+            todos::COLUMNS
+        ))
+        .bind(original.id)
+        .fetch_all(&mut *txn)
+        .await?;
+        let positions: Vec<String> = todos.iter().map(|todo| todo.position.clone()).collect();
+
+        let copies = copy_todos(
+            &mut txn,
+            &todos,
+            Some(list.id),
+            &positions,
+            &columns,
+            dto.options,
+        )
+        .await?;
+
+        let todos: Vec<Todo> = sqlx::query_as(&format!(
+            "SELECT {} FROM todos WHERE id = ANY($1) ORDER BY position, id",
+            todos::COLUMNS
+        ))
+        .bind(&copies)
+        .fetch_all(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(DuplicatedList { list, todos })
+    }
+}
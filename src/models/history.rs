@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{types::Json, FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::{
+    notes,
+    pagination::{CursorPage, Keyset, Pagination},
+    reminders::Reminder,
+    todos::{Priority, Todo},
+};
+
+/// The fields a restore brings back. The others follow from actions of their own, like
+/// completing or moving a todo.
+const RESTORED: [&str; 6] = [
+    "title",
+    "notes",
+    "dueAt",
+    "tags",
+    "priority",
+    "estimateMinutes",
+];
+
+/// The order of listed revisions, oldest first.
+const KEYSET: Keyset = Keyset {
+    scope: "todo_revisions",
+    column: "revision",
+    id: "id",
+    kind: "integer",
+};
+
+/// The creation of or a change to a todo, recorded by the database in the transaction making it.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Revision {
+    pub id: Uuid,
+    pub todo_id: Uuid,
+    /// `None` once the user who made the change is deleted.
+    pub actor_id: Option<Uuid>,
+    /// Numbers the revisions of a todo from 1, its creation.
+    pub revision: i32,
+    /// The changed fields, by their name in a [`Todo`].
+    pub changes: Json<HashMap<String, Change>>,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Change {
+    pub old: Value,
+    pub new: Value,
+}
+
+/// The values of [`RESTORED`] fields as of a revision. Fields left out did not change since.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Restored {
+    title: Option<String>,
+    #[serde(default, deserialize_with = "super::nullable")]
+    notes: Option<Option<String>>,
+    #[serde(default, deserialize_with = "super::nullable")]
+    due_at: Option<Option<DateTime<FixedOffset>>>,
+    tags: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "super::nullable")]
+    priority: Option<Option<Priority>>,
+    #[serde(default, deserialize_with = "super::nullable")]
+    estimate_minutes: Option<Option<i32>>,
+}
+
+/// The value of `field` right after `revision`, from revisions in ascending order. `None` when
+/// no revision changed the field, so that it still has its current value.
+pub fn value_at<'a>(revisions: &'a [Revision], revision: i32, field: &str) -> Option<&'a Value> {
+    let (before, after): (Vec<_>, Vec<_>) = revisions
+        .iter()
+        .partition(|candidate| candidate.revision <= revision);
+
+    before
+        .iter()
+        .rev()
+        .find_map(|candidate| candidate.changes.get(field).map(|change| &change.new))
+        .or_else(|| {
+            after
+                .iter()
+                .find_map(|candidate| candidate.changes.get(field).map(|change| &change.old))
+        })
+}
+
+impl Revision {
+    /// The revisions of a todo, oldest first.
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_todo(
+        db: &PgPool,
+        user_id: Uuid,
+        todo_id: Uuid,
+        pagination: &Pagination,
+    ) -> Result<CursorPage<Self>> {
+        let todo = Todo::find_by_id(db, user_id, todo_id).await?;
+
+        let sql = format!(
+            "SELECT * FROM todo_revisions WHERE todo_id = $1{}",
+            pagination.clause(&KEYSET, 2)?
+        );
+
+        let query = sqlx::query_as::<_, Self>(&sql).bind(todo.id);
+        let revisions = pagination.bind(query).fetch_all(db).await?;
+
+        Ok(pagination.page(&KEYSET, revisions, |revision| {
+            (revision.revision.to_string(), revision.id)
+        }))
+    }
+
+    /// Bring the title, notes, due date, tags, priority and estimate of a todo back to what they
+    /// were right after a revision. The restore is a revision of its own.
+    #[tracing::instrument(skip(db))]
+    pub async fn restore(db: &PgPool, user_id: Uuid, todo_id: Uuid, revision: i32) -> Result<Todo> {
+        let mut txn = db.begin().await?;
+
+        let todo = Todo::lock(&mut txn, user_id, todo_id).await?;
+
+        let revisions = sqlx::query_as::<_, Self>(
+            "SELECT * FROM todo_revisions WHERE todo_id = $1 ORDER BY revision",
+        )
+        .bind(todo.id)
+        .fetch_all(&mut *txn)
+        .await?;
+
+        if !revisions
+            .iter()
+            .any(|candidate| candidate.revision == revision)
+        {
+            return Err(Error::EntityNotFound.into());
+        }
+
+        let values: Map<String, Value> = RESTORED
+            .iter()
+            .filter_map(|field| {
+                value_at(&revisions, revision, field)
+                    .map(|value| (field.to_string(), value.clone()))
+            })
+            .collect();
+        let restored: Restored = serde_json::from_value(Value::Object(values))?;
+
+        sqlx::query(
+            "UPDATE todos SET title = COALESCE($2, title), \
+             notes = CASE WHEN $3 THEN $4 ELSE notes END, \
+             notes_html = CASE WHEN $3 THEN NULL ELSE notes_html END, \
+             due_at = CASE WHEN $5 THEN $6 ELSE due_at END, tags = COALESCE($7, tags), \
+             priority = CASE WHEN $8 THEN $9 ELSE priority END, \
+             estimate_minutes = CASE WHEN $10 THEN $11 ELSE estimate_minutes END, \
+             updated_at = now() WHERE id = $1",
+        )
+        .bind(todo.id)
+        .bind(&restored.title)
+        .bind(restored.notes.is_some())
+        .bind(restored.notes.clone().flatten())
+        .bind(restored.due_at.is_some())
+        .bind(restored.due_at.flatten())
+        .bind(&restored.tags)
+        .bind(restored.priority.is_some())
+        .bind(restored.priority.flatten())
+        .bind(restored.estimate_minutes.is_some())
+        .bind(restored.estimate_minutes.flatten())
+        .execute(&mut *txn)
+        .await?;
+
+        if let Some(due_at) = restored.due_at {
+            Reminder::reschedule(&mut *txn, todo.id, due_at).await?;
+        }
+        // Renders the restored notes
+        if restored.notes.is_some() {
+            notes::save(&mut txn, todo.id).await?;
+        }
+
+        let todo = Todo::find_by_id(&mut *txn, user_id, todo.id).await?;
+
+        txn.commit().await?;
+
+        Ok(todo)
+    }
+
+    /// Delete the revisions older than `retention`. Returns how many were deleted.
+    #[tracing::instrument(skip(db))]
+    pub async fn purge_expired(db: &PgPool, retention: Duration) -> Result<u64> {
+        let purged = sqlx::query("DELETE FROM todo_revisions WHERE created_at < $1")
+            .bind(Utc::now() - retention)
+            .execute(db)
+            .await?
+            .rows_affected();
+
+        Ok(purged)
+    }
+}
@@ -0,0 +1,375 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, PgConnection, PgPool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    storage::Storage,
+};
+
+use super::{
+    attachments::Attachment,
+    pagination::{CursorPage, Keyset, Pagination},
+    position::{self, Placement},
+};
+
+/// The order of listed lists.
+const KEYSET: Keyset = Keyset {
+    scope: "lists",
+    column: "position",
+    id: "id",
+    kind: "varchar",
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct List {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub position: String,
+    /// Counts the changes to the list.
+    pub version: i32,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+    /// When the list was moved to the trash.
+    pub deleted_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateList<'a> {
+    pub name: Cow<'a, str>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateList<'a> {
+    pub name: Option<Cow<'a, str>>,
+}
+
+/// Where to move a list: right after `after`, right before `before`, or last when neither is
+/// given.
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveList {
+    pub after: Option<Uuid>,
+    pub before: Option<Uuid>,
+}
+
+impl List {
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn create(db: &PgPool, user_id: Uuid, dto: &CreateList<'_>) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let list = Self::insert(&mut txn, user_id, dto).await?;
+
+        txn.commit().await?;
+
+        Ok(list)
+    }
+
+    /// Add a list after the others, as part of a larger change.
+    pub(crate) async fn insert(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        dto: &CreateList<'_>,
+    ) -> Result<Self> {
+        let position = Self::next_position(&mut *conn, user_id).await?;
+
+        let list = sqlx::query_as::<_, Self>(
+            "INSERT INTO lists (user_id, name, position) VALUES ($1, $2, $3) RETURNING *",
+        )
+        .bind(user_id)
+        .bind(&dto.name)
+        .bind(position)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok(list)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_id<'e, E>(db: E, user_id: Uuid, id: Uuid) -> Result<Self>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let list = sqlx::query_as::<_, Self>(
+            "SELECT * FROM lists WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        list.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_all<'e, E>(db: E, user_id: Uuid) -> Result<Vec<Self>>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let lists = sqlx::query_as::<_, Self>(
+            "SELECT * FROM lists WHERE user_id = $1 AND deleted_at IS NULL ORDER BY position, id",
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(lists)
+    }
+
+    /// The position key of a list appended after the user's others. Appends of the same user
+    /// wait for each other until the end of the transaction, so that they neither pick the same
+    /// key nor race a rebalance.
+    async fn next_position(conn: &mut PgConnection, user_id: Uuid) -> Result<String> {
+        // There may be no list yet to lock, so the user stands in for them
+        sqlx::query("SELECT 1 FROM users WHERE id = $1 FOR NO KEY UPDATE")
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await?;
+
+        let last: Option<String> = sqlx::query_scalar(
+            "SELECT position FROM lists WHERE user_id = $1 AND deleted_at IS NULL ORDER BY position DESC LIMIT 1",
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        if let Some(key) = position::append(last.as_deref()) {
+            return Ok(key);
+        }
+
+        let ids: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM lists WHERE user_id = $1 AND deleted_at IS NULL ORDER BY position, id",
+        )
+        .bind(user_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        position::rebalance(conn, "lists", &ids).await
+    }
+
+    /// Lock one of the user's lists for the rest of the transaction.
+    async fn lock(conn: &mut PgConnection, user_id: Uuid, id: Uuid) -> Result<Self> {
+        let list = sqlx::query_as::<_, Self>(
+            "SELECT * FROM lists WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await?;
+
+        list.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Rename a list, provided it is still at the `expected` version when one is given.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn update(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        dto: &UpdateList<'_>,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        let list = sqlx::query_as::<_, Self>(
+            "UPDATE lists SET name = COALESCE($2, name), updated_at = now() \
+             WHERE id = $1 RETURNING *",
+        )
+        .bind(current.id)
+        .bind(&dto.name)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(list)
+    }
+
+    /// A page of the lists of [`List::find_all`], in the same order.
+    #[tracing::instrument(skip(db))]
+    pub async fn paginate(
+        db: &PgPool,
+        user_id: Uuid,
+        pagination: &Pagination,
+    ) -> Result<CursorPage<Self>> {
+        let sql = format!(
+            "SELECT * FROM lists WHERE user_id = $1 AND deleted_at IS NULL{}",
+            pagination.clause(&KEYSET, 2)?
+        );
+
+        let lists = pagination
+            .bind(sqlx::query_as::<_, Self>(&sql).bind(user_id))
+            .fetch_all(db)
+            .await?;
+
+        Ok(pagination.page(&KEYSET, lists, |list| (list.position.clone(), list.id)))
+    }
+
+    /// Move a list to the trash together with its todos.
+    #[tracing::instrument(skip(db))]
+    pub async fn delete(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        expected: Option<&[i32]>,
+    ) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        sqlx::query("UPDATE lists SET deleted_at = now() WHERE id = $1")
+            .bind(id)
+            .execute(&mut *txn)
+            .await?;
+
+        // `now()` is fixed for the transaction, which is how a restore tells the todos trashed
+        // with the list apart from those trashed before
+        sqlx::query(
+            "UPDATE todos SET deleted_at = now() WHERE list_id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .execute(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Take a list out of the trash, together with the todos that were trashed with it. The list
+    /// goes back to the end.
+    #[tracing::instrument(skip(db))]
+    pub async fn restore(db: &PgPool, user_id: Uuid, id: Uuid) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let list = sqlx::query_as::<_, Self>(
+            "SELECT * FROM lists WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL \
+             FOR UPDATE",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *txn)
+        .await?
+        .ok_or(Error::EntityNotFound)?;
+
+        sqlx::query("UPDATE todos SET deleted_at = NULL WHERE list_id = $1 AND deleted_at = $2")
+            .bind(list.id)
+            .bind(list.deleted_at)
+            .execute(&mut *txn)
+            .await?;
+
+        let position = Self::next_position(&mut txn, user_id).await?;
+
+        let list = sqlx::query_as::<_, Self>(
+            "UPDATE lists SET deleted_at = NULL, position = $2, updated_at = now() \
+             WHERE id = $1 RETURNING *",
+        )
+        .bind(list.id)
+        .bind(position)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(list)
+    }
+
+    /// Permanently delete a list from the trash, together with its todos.
+    #[tracing::instrument(skip(db, storage))]
+    pub async fn purge(db: &PgPool, storage: &dyn Storage, user_id: Uuid, id: Uuid) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        let todo_ids: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT t.id FROM todos t JOIN lists l ON l.id = t.list_id \
+             WHERE l.id = $1 AND l.user_id = $2 AND l.deleted_at IS NOT NULL",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_all(&mut *txn)
+        .await?;
+        let checksums = Attachment::checksums(&mut txn, &todo_ids).await?;
+
+        let result = sqlx::query(
+            "DELETE FROM lists WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL",
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *txn)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::EntityNotFound.into());
+        }
+
+        let orphans = Attachment::release(&mut txn, &checksums).await?;
+
+        txn.commit().await?;
+
+        Attachment::delete_orphans(db, storage, &orphans).await;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn move_to(db: &PgPool, user_id: Uuid, id: Uuid, dto: &MoveList) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        sqlx::query(
+            "SELECT id FROM lists WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *txn)
+        .await?
+        .ok_or(Error::EntityNotFound)?;
+
+        let siblings: Vec<(Uuid, String)> = sqlx::query_as(
+            "SELECT id, position FROM lists WHERE user_id = $1 AND id <> $2 AND deleted_at IS NULL \
+             ORDER BY position, id",
+        )
+        .bind(user_id)
+        .bind(id)
+        .fetch_all(&mut *txn)
+        .await?;
+
+        match position::place(&siblings, id, dto.after, dto.before)? {
+            Placement::Single(key) => {
+                sqlx::query("UPDATE lists SET position = $2, updated_at = now() WHERE id = $1")
+                    .bind(id)
+                    .bind(key)
+                    .execute(&mut *txn)
+                    .await?;
+            }
+            Placement::Rebalance(keys) => {
+                let (ids, keys): (Vec<Uuid>, Vec<String>) = keys.into_iter().unzip();
+
+                sqlx::query(
+                    "UPDATE lists SET position = v.position \
+                     FROM UNNEST($1::uuid[], $2::text[]) AS v (id, position) \
+                     WHERE lists.id = v.id",
+                )
+                .bind(ids)
+                .bind(keys)
+                .execute(&mut *txn)
+                .await?;
+            }
+        }
+
+        let list = Self::find_by_id(&mut *txn, user_id, id).await?;
+
+        txn.commit().await?;
+
+        Ok(list)
+    }
+}
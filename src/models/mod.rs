@@ -1 +1,105 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::json;
+
+use crate::error::{Error, Report, Result};
+
+pub mod attachments;
+pub mod auth;
+pub mod boards;
+pub mod checklists;
+pub mod comments;
+pub mod custom_fields;
+pub mod dependencies;
+pub mod duplicates;
+pub mod history;
+pub mod lists;
+pub mod notes;
+pub mod pagination;
+pub mod position;
+pub mod quick;
+pub mod recurrences;
+pub mod reminders;
+pub mod rrule;
+pub mod search;
+pub mod settings;
+pub mod templates;
+pub mod time_entries;
+pub mod todos;
+pub mod trash;
+pub mod undo;
+pub mod urgency;
 pub mod users;
+pub mod views;
+
+/// Tell a missing field (`None`) apart from an explicit `null` (`Some(None)`). Use together with
+/// `#[serde(default)]`.
+pub(crate) fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+/// The strong `ETag` of a row at a version.
+pub fn etag(version: i32) -> String {
+    format!("\"{version}\"")
+}
+
+/// The versions named by the value of an `If-Match` header, in order. `None` for `*`, which any
+/// current version matches. Weak and malformed entity tags never match a strong comparison, so
+/// they are left out rather than rejected.
+pub fn if_match_versions(value: &str) -> Option<Vec<i32>> {
+    if value.trim() == "*" {
+        return None;
+    }
+
+    let mut versions = Vec::new();
+    let mut rest = value;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+
+        let (weak, tag) = match rest.strip_prefix("W/") {
+            Some(tag) => (true, tag),
+            None => (false, rest),
+        };
+        // A tag runs up to the next quote, commas included
+        let Some((tag, after)) = tag.strip_prefix('"').and_then(|tag| tag.split_once('"')) else {
+            // Not an entity tag, skip to the next one
+            match rest.find(',') {
+                Some(end) => rest = &rest[end..],
+                None => return Some(versions),
+            }
+            continue;
+        };
+
+        if let (false, Ok(version)) = (weak, tag.parse()) {
+            versions.push(version);
+        }
+        rest = after;
+    }
+}
+
+/// The error for a change that expected other versions of a row than the `current` one.
+pub(crate) fn stale(current: &impl Serialize, version: i32, expected: &[i32]) -> Report {
+    Error::PreconditionFailed {
+        expected: expected.to_vec(),
+        version,
+        current: json!(current),
+    }
+    .into()
+}
+
+/// Fail with [`Error::PreconditionFailed`] unless `current` is at one of the `expected` versions,
+/// when a client gave some.
+pub(crate) fn check_version(
+    current: &impl Serialize,
+    version: i32,
+    expected: Option<&[i32]>,
+) -> Result<()> {
+    match expected {
+        Some(expected) if !expected.contains(&version) => Err(stale(current, version, expected)),
+        _ => Ok(()),
+    }
+}
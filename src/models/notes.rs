@@ -0,0 +1,372 @@
+//! Todo notes are CommonMark. Their task list mirrors the checklist of the todo, and
+//! `#todo-<uuid>` links to another todo of the same user. The rendered notes are stored with the
+//! references as they are written; `link_todo_references` in the database names them after the
+//! current titles of their todos whenever a todo is read, so that renames and deletions show
+//! right away.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    sync::LazyLock,
+};
+
+use pulldown_cmark::{html, CowStr, Event, LinkType, Options, Parser, Tag, TagEnd};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::checklists::ChecklistItem;
+
+const REFERENCE: &str = "#todo-";
+
+/// Allows the markup of CommonMark and checkboxes of task lists, nothing that runs.
+static SANITIZER: LazyLock<ammonia::Builder<'static>> = LazyLock::new(|| {
+    let mut builder = ammonia::Builder::default();
+    builder
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["checked", "disabled"])
+        .add_tag_attribute_values("input", "type", ["checkbox"]);
+    builder
+});
+
+/// An item of a task list, `- [ ] text`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    pub text: String,
+    pub checked: bool,
+    /// Where the `[ ]` of the item is in the notes.
+    pub marker: Range<usize>,
+    /// Where the item is in the notes, up to its nested list if it has one.
+    pub item: Range<usize>,
+}
+
+fn parser(notes: &str) -> Parser<'_> {
+    Parser::new_ext(
+        notes,
+        Options::ENABLE_TASKLISTS | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES,
+    )
+}
+
+/// The items of the task lists of the notes, in order.
+pub fn tasks(notes: &str) -> Vec<Task> {
+    let mut tasks = Vec::new();
+    // The task whose text is being read
+    let mut current: Option<Task> = None;
+    let mut item = 0..0;
+
+    for (event, range) in parser(notes).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Item) => item = range,
+            Event::TaskListMarker(checked) => {
+                current = Some(Task {
+                    text: String::new(),
+                    checked,
+                    marker: range,
+                    item: item.clone(),
+                });
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(task) = current.as_mut() {
+                    task.text.push_str(&text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some(task) = current.as_mut() {
+                    task.text.push(' ');
+                }
+            }
+            // A nested list ends the text of its item
+            Event::Start(Tag::List(_)) | Event::End(TagEnd::Item) => {
+                if let Some(mut task) = current.take() {
+                    task.text = task.text.trim().to_string();
+                    if matches!(event, Event::Start(_)) {
+                        task.item.end = range.start;
+                    }
+                    tasks.push(task);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    tasks
+}
+
+/// Check or uncheck the tasks of the notes whose text is in `checklist`.
+pub fn check(notes: &str, checklist: &HashMap<String, bool>) -> String {
+    let mut notes = notes.to_string();
+
+    for task in tasks(&notes).into_iter().rev() {
+        match checklist.get(&task.text) {
+            Some(&checked) if checked != task.checked => {
+                notes.replace_range(task.marker, if checked { "[x]" } else { "[ ]" });
+            }
+            _ => {}
+        }
+    }
+
+    notes
+}
+
+/// Uncheck every task of the notes.
+pub fn uncheck(notes: &str) -> String {
+    let unchecked = tasks(notes)
+        .into_iter()
+        .map(|task| (task.text, false))
+        .collect();
+
+    check(notes, &unchecked)
+}
+
+/// Take the tasks whose text is not in `checklist` out of the notes, whole lines at a time. Tasks
+/// without text are left alone, as they never make it into a checklist.
+pub fn prune(notes: &str, checklist: &HashMap<String, bool>) -> String {
+    let mut notes = notes.to_string();
+
+    for task in tasks(&notes).into_iter().rev() {
+        if task.text.is_empty() || checklist.contains_key(&task.text) {
+            continue;
+        }
+
+        let line_start = |at: usize| notes[..at].rfind('\n').map_or(0, |i| i + 1);
+        let start = line_start(task.item.start);
+        let end = match task.item.end {
+            end if end >= notes.len() => notes.len(),
+            end => line_start(end),
+        };
+        notes.replace_range(start..end.max(start), "");
+    }
+
+    notes
+}
+
+/// A piece of text, split at its references.
+enum Piece<'a> {
+    Text(&'a str),
+    Reference(Uuid),
+}
+
+/// Split a text at its `#todo-<uuid>` references.
+fn split_references(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(REFERENCE) {
+        let after = start + REFERENCE.len();
+
+        match rest
+            .get(after..after + 36)
+            .and_then(|id| Uuid::try_parse(id).ok())
+        {
+            Some(id) => {
+                pieces.push(Piece::Text(&rest[..start]));
+                pieces.push(Piece::Reference(id));
+                rest = &rest[after + 36..];
+            }
+            None => {
+                pieces.push(Piece::Text(&rest[..after]));
+                rest = &rest[after..];
+            }
+        }
+    }
+    pieces.push(Piece::Text(rest));
+
+    pieces
+}
+
+/// The todos the notes reference, outside of code.
+pub fn references(notes: &str) -> Vec<Uuid> {
+    let mut seen = HashSet::new();
+
+    parser(notes)
+        .filter_map(|event| match event {
+            Event::Text(text) => Some(
+                split_references(&text)
+                    .into_iter()
+                    .filter_map(|piece| match piece {
+                        Piece::Reference(id) => Some(id),
+                        Piece::Text(_) => None,
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            _ => None,
+        })
+        .flatten()
+        .filter(|id| seen.insert(*id))
+        .collect()
+}
+
+/// Point the references of the notes to the todos of `copies` at their copies instead.
+pub fn remap(notes: &str, copies: &HashMap<Uuid, Uuid>) -> String {
+    split_references(notes)
+        .into_iter()
+        .map(|piece| match piece {
+            Piece::Text(text) => text.to_string(),
+            Piece::Reference(id) => format!("{REFERENCE}{}", copies.get(&id).unwrap_or(&id)),
+        })
+        .collect()
+}
+
+/// Render the notes to sanitised HTML. References become links to their todos, labelled
+/// with the reference itself until they are read; the others are left as they are.
+pub fn render(notes: &str) -> String {
+    let mut events = Vec::new();
+    // References are not linked within links
+    let mut links = 0;
+
+    for event in parser(notes) {
+        match event {
+            Event::Start(Tag::Link { .. }) => links += 1,
+            Event::End(TagEnd::Link) => links -= 1,
+            Event::Text(text) if links == 0 && text.contains(REFERENCE) => {
+                for piece in split_references(&text) {
+                    match piece {
+                        Piece::Text("") => {}
+                        Piece::Text(text) => events.push(Event::Text(text.to_string().into())),
+                        Piece::Reference(id) => {
+                            events.push(Event::Start(Tag::Link {
+                                link_type: LinkType::Inline,
+                                dest_url: format!("/todos/{id}").into(),
+                                title: CowStr::Borrowed(""),
+                                id: CowStr::Borrowed(""),
+                            }));
+                            events.push(Event::Text(format!("{REFERENCE}{id}").into()));
+                            events.push(Event::End(TagEnd::Link));
+                        }
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+        events.push(event);
+    }
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, events.into_iter());
+
+    SANITIZER.clean(&unsafe_html).to_string()
+}
+
+/// Render the notes of a todo and store them.
+async fn store(conn: &mut PgConnection, todo_id: Uuid, notes: &str) -> Result<()> {
+    sqlx::query("UPDATE todos SET notes = $2, notes_html = $3 WHERE id = $1")
+        .bind(todo_id)
+        .bind(notes)
+        .bind(render(notes))
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+async fn find_notes(conn: &mut PgConnection, todo_id: Uuid) -> Result<Option<String>> {
+    let notes = sqlx::query_scalar("SELECT notes FROM todos WHERE id = $1")
+        .bind(todo_id)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(notes)
+}
+
+/// Save the notes of up to `limit` todos that were written before notes were rendered, skipping
+/// those locked by others, as if they had just been written. Returns how many were saved.
+pub async fn save_unrendered(conn: &mut PgConnection, limit: i64) -> Result<usize> {
+    // Saving is no change of the user's, there is nothing for them to undo
+    sqlx::query("SELECT set_config('todos.undoing', 'on', true)")
+        .execute(&mut *conn)
+        .await?;
+
+    let ids: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT id FROM todos WHERE notes IS NOT NULL AND notes_html IS NULL \
+         LIMIT $1 FOR UPDATE SKIP LOCKED",
+    )
+    .bind(limit)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for id in &ids {
+        save(&mut *conn, *id).await?;
+    }
+
+    Ok(ids.len())
+}
+
+/// Bring the checklist of a todo in line with the task list of its freshly written notes:
+/// tasks check or uncheck the items of the same text, and the others are appended to it.
+pub(crate) async fn save(conn: &mut PgConnection, todo_id: Uuid) -> Result<()> {
+    let Some(notes) = find_notes(&mut *conn, todo_id).await? else {
+        return Ok(());
+    };
+
+    let items: Vec<(String, bool)> = sqlx::query_as(
+        "SELECT text, checked FROM checklist_items WHERE todo_id = $1 ORDER BY position, id",
+    )
+    .bind(todo_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let checklist: HashMap<_, _> = items
+        .iter()
+        .map(|(text, checked)| (text.as_str(), *checked))
+        .collect();
+    let mut added = HashSet::new();
+
+    for task in tasks(&notes) {
+        match checklist.get(task.text.as_str()) {
+            Some(&checked) if checked != task.checked => {
+                sqlx::query(
+                    "UPDATE checklist_items SET checked = $3, updated_at = now() \
+                     WHERE todo_id = $1 AND text = $2",
+                )
+                .bind(todo_id)
+                .bind(&task.text)
+                .bind(task.checked)
+                .execute(&mut *conn)
+                .await?;
+            }
+            Some(_) => {}
+            None if task.text.is_empty() || !added.insert(task.text.clone()) => {}
+            None => {
+                let position = ChecklistItem::next_position(&mut *conn, todo_id).await?;
+
+                sqlx::query(
+                    "INSERT INTO checklist_items (todo_id, text, checked, position) \
+                     VALUES ($1, $2, $3, $4)",
+                )
+                .bind(todo_id)
+                .bind(&task.text)
+                .bind(task.checked)
+                .bind(position)
+                .execute(&mut *conn)
+                .await?;
+            }
+        }
+    }
+
+    store(conn, todo_id, &notes).await
+}
+
+/// Bring the task list of the notes of a todo in line with its checklist, after a change to it:
+/// tasks follow the items of the same text, and lose their line once there is none.
+pub(crate) async fn refresh(conn: &mut PgConnection, todo_id: Uuid) -> Result<()> {
+    let Some(notes) = find_notes(&mut *conn, todo_id).await? else {
+        return Ok(());
+    };
+
+    let checklist: Vec<(String, bool)> = sqlx::query_as(
+        "SELECT text, checked FROM checklist_items WHERE todo_id = $1 ORDER BY position, id",
+    )
+    .bind(todo_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // Of items with the same text, the first one wins
+    let checklist = checklist.into_iter().rev().collect();
+    let checked = check(&prune(&notes, &checklist), &checklist);
+    if checked == notes {
+        return Ok(());
+    }
+
+    store(conn, todo_id, &checked).await
+}
@@ -0,0 +1,244 @@
+use std::{cmp::Ordering, sync::Arc};
+
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::{postgres::PgArguments, query::QueryAs, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    config::{pagination::PaginationConfig, state::AppContext},
+    error::{Error, Report, Result},
+};
+
+/// The order of a listing paginated by keyset: by `column`, then by `id` for rows with the same
+/// value.
+#[derive(Debug, Clone, Copy)]
+pub struct Keyset {
+    /// Names the listing, so that its cursors cannot be used on another one.
+    pub scope: &'static str,
+    pub column: &'static str,
+    pub id: &'static str,
+    /// The SQL type of `column`, which the key of a cursor is cast to.
+    pub kind: &'static str,
+}
+
+/// Where a page starts: right after the row with `key` and `id`, or right before it for the
+/// pages `before`. Clients get it signed and encoded, see [`Cursor::encode`].
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Cursor {
+    pub scope: String,
+    pub key: String,
+    pub id: Uuid,
+    pub before: bool,
+}
+
+fn mac(secret: &str, payload: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+impl Cursor {
+    /// The cursor as an opaque string: its payload and an HMAC of it.
+    pub fn encode(&self, secret: &str) -> String {
+        let payload = URL_SAFE_NO_PAD.encode(json!(self).to_string());
+        let signature = hex::encode(mac(secret, &payload).finalize().into_bytes());
+
+        format!("{payload}.{signature}")
+    }
+
+    /// Read a cursor made by [`Cursor::encode`] with the same secret.
+    pub fn decode(cursor: &str, secret: &str) -> Result<Self> {
+        let invalid = || Error::BadRequest("Invalid cursor".into());
+
+        let (payload, signature) = cursor.split_once('.').ok_or_else(invalid)?;
+        let valid = hex::decode(signature)
+            .is_ok_and(|bytes| mac(secret, payload).verify_slice(&bytes).is_ok());
+        if !valid {
+            return Err(invalid().into());
+        }
+
+        let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+        serde_json::from_slice(&payload).map_err(|_| invalid().into())
+    }
+}
+
+/// `?cursor=&limit=` query parameters.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct CursorParams {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Keyset pagination of a request, read from its [`CursorParams`]. The limit is capped by the
+/// `pagination` config.
+#[derive(Debug, Clone)]
+pub struct Pagination {
+    pub cursor: Option<Cursor>,
+    pub limit: i64,
+    secret: String,
+}
+
+impl FromRequestParts<Arc<AppContext>> for Pagination {
+    type Rejection = Report;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        ctx: &Arc<AppContext>,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<CursorParams>::from_request_parts(parts, ctx)
+            .await
+            .map_err(|rejection| Error::BadRequest(rejection.body_text()))?;
+
+        Self::new(&params, &ctx.config.pagination)
+    }
+}
+
+impl Pagination {
+    pub fn new(params: &CursorParams, config: &PaginationConfig) -> Result<Self> {
+        let cursor = params
+            .cursor
+            .as_deref()
+            .filter(|cursor| !cursor.is_empty())
+            .map(|cursor| Cursor::decode(cursor, &config.secret))
+            .transpose()?;
+
+        Ok(Self {
+            cursor,
+            limit: params
+                .limit
+                .unwrap_or(config.default_limit)
+                .clamp(1, config.max_limit),
+            secret: config.secret.clone(),
+        })
+    }
+
+    /// The condition on rows past the cursor, their order and the limit, to follow a `WHERE`
+    /// clause. Placeholders start at `$first`, see [`Pagination::bind`].
+    pub fn clause(&self, keyset: &Keyset, first: usize) -> Result<String> {
+        let Keyset {
+            column, id, kind, ..
+        } = keyset;
+
+        let Some(cursor) = &self.cursor else {
+            return Ok(format!(" ORDER BY {column}, {id} LIMIT ${first}"));
+        };
+        if cursor.scope != keyset.scope {
+            return Err(Error::BadRequest("Invalid cursor".into()).into());
+        }
+
+        let (op, direction) = if cursor.before {
+            ("<", " DESC")
+        } else {
+            (">", "")
+        };
+        let (key, limit) = (first + 1, first + 2);
+
+        Ok(format!(
+            " AND ({column}, {id}) {op} (${first}::{kind}, ${key}) \
+             ORDER BY {column}{direction}, {id}{direction} LIMIT ${limit}"
+        ))
+    }
+
+    /// Bind the values of [`Pagination::clause`]. One row more than the limit is fetched to
+    /// know whether there are more.
+    pub fn bind<'q, O>(
+        &self,
+        query: QueryAs<'q, Postgres, O, PgArguments>,
+    ) -> QueryAs<'q, Postgres, O, PgArguments> {
+        match &self.cursor {
+            Some(cursor) => query.bind(cursor.key.clone()).bind(cursor.id),
+            None => query,
+        }
+        .bind(self.limit + 1)
+    }
+
+    /// The page of rows that are all in memory and sorted, for orders SQL cannot give. `order`
+    /// compares a row to the row of the cursor.
+    pub fn slice<T>(
+        &self,
+        keyset: &Keyset,
+        rows: Vec<T>,
+        key: impl Fn(&T) -> (String, Uuid),
+        order: impl Fn(&T, &Cursor) -> Ordering,
+    ) -> Result<CursorPage<T>> {
+        let limit = self.limit as usize + 1;
+
+        let rows = match &self.cursor {
+            None => rows.into_iter().take(limit).collect(),
+            Some(cursor) if cursor.scope != keyset.scope => {
+                return Err(Error::BadRequest("Invalid cursor".into()).into())
+            }
+            Some(cursor) if cursor.before => rows
+                .into_iter()
+                .rev()
+                .filter(|row| order(row, cursor).is_lt())
+                .take(limit)
+                .collect(),
+            Some(cursor) => rows
+                .into_iter()
+                .filter(|row| order(row, cursor).is_gt())
+                .take(limit)
+                .collect(),
+        };
+
+        Ok(self.page(keyset, rows, key))
+    }
+
+    /// The page of rows fetched with [`Pagination::clause`], `key` giving the key and id of a
+    /// row.
+    pub fn page<T>(
+        &self,
+        keyset: &Keyset,
+        mut rows: Vec<T>,
+        key: impl Fn(&T) -> (String, Uuid),
+    ) -> CursorPage<T> {
+        let before = self.cursor.as_ref().is_some_and(|cursor| cursor.before);
+        let more = rows.len() as i64 > self.limit;
+
+        rows.truncate(self.limit as usize);
+        if before {
+            rows.reverse();
+        }
+
+        let cursor = |row: &T, before: bool| {
+            let (key, id) = key(row);
+            Cursor {
+                scope: keyset.scope.into(),
+                key,
+                id,
+                before,
+            }
+            .encode(&self.secret)
+        };
+        // Going back, the rows after the page are the ones the client came from
+        let (next, prev) = match before {
+            true => (true, more),
+            false => (more, self.cursor.is_some()),
+        };
+
+        CursorPage {
+            next_cursor: rows.last().filter(|_| next).map(|row| cursor(row, false)),
+            prev_cursor: rows.first().filter(|_| prev).map(|row| cursor(row, true)),
+            items: rows,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    /// The page after this one, `None` on the last page.
+    pub next_cursor: Option<String>,
+    /// The page before this one, `None` on the first page.
+    pub prev_cursor: Option<String>,
+}
@@ -0,0 +1,210 @@
+//! Fractional indexing for manually ordered rows.
+//!
+//! Every row carries a `position` key, a base 62 string that sorts in byte order (the columns use
+//! `COLLATE "C"`). Moving a row only needs a key between its new neighbours, so a move is a
+//! single-row update. Keys grow when the same gap is split over and over, appending to the end
+//! included, and once a key would exceed [`MAX_LEN`] the whole set of siblings is spread out
+//! again.
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+const DIGITS: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const BASE: u8 = 62;
+
+/// Keys longer than this trigger a rebalance of their siblings.
+pub const MAX_LEN: usize = 32;
+
+/// Where a moved row ends up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Placement {
+    /// Only the moved row needs the new key.
+    Single(String),
+    /// Every sibling, including the moved row, gets a new key.
+    Rebalance(Vec<(Uuid, String)>),
+}
+
+fn digit(c: u8) -> Option<u8> {
+    DIGITS.iter().position(|d| *d == c).map(|i| i as u8)
+}
+
+fn decode(key: &str) -> Result<Vec<u8>> {
+    let digits = key
+        .bytes()
+        .map(digit)
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| Error::BadRequest(format!("Invalid position key `{key}`")))?;
+
+    // A trailing zero would leave no room below the key
+    if digits.is_empty() || digits.last() == Some(&0) {
+        return Err(Error::BadRequest(format!("Invalid position key `{key}`")).into());
+    }
+
+    Ok(digits)
+}
+
+fn encode(digits: &[u8]) -> String {
+    digits.iter().map(|d| DIGITS[*d as usize] as char).collect()
+}
+
+fn midpoint(lower: &[u8], upper: Option<&[u8]>) -> Vec<u8> {
+    if let Some(upper) = upper {
+        // Keep the common prefix, treating the missing digits of `lower` as zeros
+        let common = upper
+            .iter()
+            .enumerate()
+            .take_while(|(i, d)| lower.get(*i).copied().unwrap_or(0) == **d)
+            .count();
+
+        if common > 0 {
+            let mut key = upper[..common].to_vec();
+            key.extend(midpoint(
+                lower.get(common..).unwrap_or_default(),
+                Some(&upper[common..]),
+            ));
+            return key;
+        }
+    }
+
+    let low = lower.first().copied().unwrap_or(0);
+    let high = upper.map_or(BASE, |upper| upper[0]);
+
+    if high - low > 1 {
+        vec![(low + high) / 2]
+    } else if upper.is_some_and(|upper| upper.len() > 1) {
+        vec![high]
+    } else {
+        let mut key = vec![low];
+        key.extend(midpoint(lower.get(1..).unwrap_or_default(), None));
+        key
+    }
+}
+
+/// A key that sorts strictly between `lower` and `upper`. A missing bound is open.
+pub fn between(lower: Option<&str>, upper: Option<&str>) -> Result<String> {
+    let low = lower.map(decode).transpose()?;
+    let high = upper.map(decode).transpose()?;
+
+    if let (Some(lower), Some(upper)) = (lower, upper) {
+        if lower >= upper {
+            return Err(Error::BadRequest(format!(
+                "Position `{lower}` does not sort before `{upper}`"
+            ))
+            .into());
+        }
+    }
+
+    Ok(encode(&midpoint(
+        low.as_deref().unwrap_or_default(),
+        high.as_deref(),
+    )))
+}
+
+/// The key of a row appended after `last`, the key of the last sibling. `None` when the key would
+/// exceed [`MAX_LEN`], or `last` is corrupt: the siblings then need a [`rebalance`] first.
+pub fn append(last: Option<&str>) -> Option<String> {
+    between(last, None).ok().filter(|key| key.len() <= MAX_LEN)
+}
+
+/// Spread out the keys of `ids`, all the rows of `table` that are ordered among each other, in
+/// their order. Returns the key of a row appended after them.
+pub(crate) async fn rebalance(
+    conn: &mut PgConnection,
+    table: &str,
+    ids: &[Uuid],
+) -> Result<String> {
+    let mut keys = spread(ids.len() + 1);
+    let appended = keys.pop().unwrap_or_default();
+
+    sqlx::query(&format!(
+        "UPDATE {table} SET position = v.position \
+         FROM UNNEST($1::uuid[], $2::text[]) AS v (id, position) WHERE {table}.id = v.id"
+    ))
+    .bind(ids)
+    .bind(keys)
+    .execute(conn)
+    .await?;
+
+    Ok(appended)
+}
+
+/// `count` evenly spaced keys, in order.
+pub fn spread(count: usize) -> Vec<String> {
+    let slots = count as u128 + 1;
+    // One digit more than strictly needed leaves room for later moves
+    let mut width = 1;
+    while (BASE as u128).pow(width) < slots {
+        width += 1;
+    }
+    width += 1;
+
+    let space = (BASE as u128).pow(width);
+
+    (1..slots)
+        .map(|i| {
+            let mut value = i * space / slots;
+            let mut digits = vec![0; width as usize];
+            for digit in digits.iter_mut().rev() {
+                *digit = (value % BASE as u128) as u8;
+                value /= BASE as u128;
+            }
+            while digits.last() == Some(&0) {
+                digits.pop();
+            }
+            encode(&digits)
+        })
+        .collect()
+}
+
+/// Work out the new key for `moved`, placed right after `after` and/or right before `before`,
+/// or at the end when neither is given. `siblings` are the other rows of the target scope in
+/// their current order.
+pub fn place(
+    siblings: &[(Uuid, String)],
+    moved: Uuid,
+    after: Option<Uuid>,
+    before: Option<Uuid>,
+) -> Result<Placement> {
+    let index_of = |id: Uuid| {
+        siblings
+            .iter()
+            .position(|(sibling, _)| *sibling == id)
+            .ok_or_else(|| Error::BadRequest(format!("`{id}` is not a sibling of `{moved}`")))
+    };
+
+    let index = match (after, before) {
+        (Some(after), Some(before)) => {
+            let index = index_of(after)? + 1;
+            if index_of(before)? != index {
+                return Err(
+                    Error::BadRequest("`after` and `before` are not adjacent".into()).into(),
+                );
+            }
+            index
+        }
+        (Some(after), None) => index_of(after)? + 1,
+        (None, Some(before)) => index_of(before)?,
+        (None, None) => siblings.len(),
+    };
+
+    let lower = index
+        .checked_sub(1)
+        .and_then(|i| siblings.get(i))
+        .map(|(_, key)| key.as_str());
+    let upper = siblings.get(index).map(|(_, key)| key.as_str());
+
+    // Ties, corrupt keys and keys that grew too long are all fixed by spreading the siblings out
+    match between(lower, upper) {
+        Ok(key) if key.len() <= MAX_LEN => Ok(Placement::Single(key)),
+        _ => {
+            let mut ids: Vec<Uuid> = siblings.iter().map(|(id, _)| *id).collect();
+            ids.insert(index, moved);
+
+            Ok(Placement::Rebalance(
+                ids.into_iter().zip(spread(siblings.len() + 1)).collect(),
+            ))
+        }
+    }
+}
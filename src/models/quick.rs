@@ -0,0 +1,668 @@
+use std::borrow::Cow;
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Months, NaiveDate, NaiveTime, Timelike, Weekday,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::{
+    recurrences::{parse_timezone, NewRecurrence},
+    rrule::{resolve_local, Frequency, RRule, WeekdayNum},
+    settings::Settings,
+    todos::{normalize_tag, CreateTodo, Priority, Todo},
+};
+
+/// Days looked ahead for the first occurrence of a recurrence.
+const MAX_DAYS_AHEAD: i64 = 400;
+
+/// Most periods between two occurrences, as in `every 999 days`.
+const MAX_INTERVAL: u32 = 999;
+
+/// Todos due on a day without a time are due at the end of it.
+pub(crate) fn end_of_day() -> NaiveTime {
+    NaiveTime::from_hms_opt(23, 59, 59).expect("valid time")
+}
+
+/// The text of a todo to create, such as `Pay rent every month on the 1st #finance !high`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickAdd<'a> {
+    pub text: Cow<'a, str>,
+    /// Only parse the text, without creating the todo.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// What was read from a quick-add text.
+#[derive(Debug, Serialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Parsed {
+    /// The words left once everything else is taken out.
+    pub title: String,
+    pub due_at: Option<DateTime<FixedOffset>>,
+    pub rrule: Option<String>,
+    pub tags: Vec<String>,
+    /// The list as written after `@`.
+    pub list: Option<String>,
+    /// The list of that name, once looked up.
+    pub list_id: Option<Uuid>,
+    pub priority: Option<Priority>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickAdded {
+    pub parsed: Parsed,
+    /// `None` for a dry run.
+    pub todo: Option<Todo>,
+}
+
+impl QuickAdded {
+    /// Parse the text in the user's timezone and create the todo it describes.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn create(db: &PgPool, user_id: Uuid, dto: &QuickAdd<'_>) -> Result<Self> {
+        let settings = Settings::find(db, user_id).await?;
+        let tz = parse_timezone(&settings.timezone)?;
+
+        let mut parsed = parse(&dto.text, chrono::Utc::now().with_timezone(&tz));
+
+        if parsed.title.is_empty() {
+            return Err(Error::BadRequest("A todo needs a title".into()).into());
+        }
+        if let Some(name) = &parsed.list {
+            let list_id: Option<Uuid> = sqlx::query_scalar(
+                "SELECT id FROM lists WHERE user_id = $1 AND deleted_at IS NULL \
+                 AND lower(name) = lower($2) ORDER BY position LIMIT 1",
+            )
+            .bind(user_id)
+            .bind(name)
+            .fetch_optional(db)
+            .await?;
+
+            parsed.list_id =
+                Some(list_id.ok_or_else(|| Error::BadRequest(format!("Unknown list `{name}`")))?);
+        }
+
+        if dto.dry_run {
+            return Ok(Self { parsed, todo: None });
+        }
+
+        let todo = Todo::create(
+            db,
+            user_id,
+            &CreateTodo {
+                title: parsed.title.as_str().into(),
+                notes: None,
+                due_at: parsed.due_at,
+                start_at: None,
+                list_id: parsed.list_id,
+                recurrence: parsed.rrule.as_deref().map(|rrule| NewRecurrence {
+                    rrule: rrule.into(),
+                    timezone: tz.name().into(),
+                    dtstart: None,
+                }),
+                tags: parsed.tags.iter().map(|tag| tag.as_str().into()).collect(),
+                priority: parsed.priority,
+                estimate_minutes: None,
+                fields: Default::default(),
+            },
+        )
+        .await?;
+
+        Ok(Self {
+            parsed,
+            todo: Some(todo),
+        })
+    }
+}
+
+/// A word of the text. Quoted words are kept in the title as they are.
+#[derive(Debug)]
+struct Word {
+    text: String,
+    /// Lowercase, without trailing punctuation.
+    lower: String,
+    quoted: bool,
+}
+
+fn words(text: &str) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let quoted = c == '"';
+        let mut text = String::new();
+        let mut in_quotes = false;
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() && !in_quotes {
+                break;
+            }
+            chars.next();
+            if c == '"' {
+                in_quotes = !in_quotes;
+            } else {
+                text.push(c);
+            }
+        }
+
+        if !text.is_empty() {
+            let lower = text
+                .to_lowercase()
+                .trim_end_matches([',', '.', ';'])
+                .to_string();
+            words.push(Word {
+                text,
+                lower,
+                quoted,
+            });
+        }
+    }
+
+    words
+}
+
+/// Read the due date, recurrence, tags, list and priority out of `text`, relative to `now`.
+///
+/// - `#tag` adds a tag, `@list` (or `@"two words"`) puts the todo in a list
+/// - `!high`, `!medium` and `!low`, or `!1` to `!3`, set the priority
+/// - `today`, `tonight`, `tomorrow`, `friday`, `next friday`, `in 3 days`, `in 2 hours`,
+///   `jun 1`, `1 june 2026`, `2026-06-01`, `on the 15th`
+/// - `at 5pm`, `17:30`, `noon`
+/// - `daily`, `every day`, `every 2 weeks`, `every other month`, `every weekday`,
+///   `every monday and thursday`, `every week on friday`, `every month on the 1st`
+///
+/// Words in quotes are never read as anything but the title. Without a time a todo is due at the
+/// end of the day, and a time alone that has passed today is for tomorrow.
+pub fn parse(text: &str, now: DateTime<Tz>) -> Parsed {
+    let words = words(text);
+    let mut parser = Parser {
+        words: &words,
+        now,
+        date: None,
+        time: None,
+        rule: None,
+        parsed: Parsed::default(),
+    };
+    let mut title = Vec::new();
+
+    let mut i = 0;
+    while i < words.len() {
+        match parser.read(i) {
+            Some(read) => i += read,
+            None => {
+                title.push(words[i].text.as_str());
+                i += 1;
+            }
+        }
+    }
+
+    let mut parsed = parser.due();
+    parsed.title = title.join(" ");
+    parsed
+}
+
+struct Parser<'a> {
+    words: &'a [Word],
+    now: DateTime<Tz>,
+    date: Option<NaiveDate>,
+    time: Option<NaiveTime>,
+    rule: Option<RRule>,
+    parsed: Parsed,
+}
+
+impl Parser<'_> {
+    /// The lowercase word at `i`, unless it is quoted.
+    fn word(&self, i: usize) -> Option<&str> {
+        self.words
+            .get(i)
+            .filter(|word| !word.quoted)
+            .map(|word| word.lower.as_str())
+    }
+
+    fn today(&self) -> NaiveDate {
+        self.now.date_naive()
+    }
+
+    /// Read whatever starts at word `i`, returning the number of words read.
+    fn read(&mut self, i: usize) -> Option<usize> {
+        let word = self.words.get(i).filter(|word| !word.quoted)?;
+
+        if let Some(tag) = word.text.strip_prefix('#') {
+            let tag = normalize_tag(tag).ok()?;
+            if !self.parsed.tags.contains(&tag) {
+                self.parsed.tags.push(tag);
+            }
+            return Some(1);
+        }
+        if let Some(list) = word.text.strip_prefix('@').filter(|list| !list.is_empty()) {
+            self.parsed.list = Some(list.to_string());
+            return Some(1);
+        }
+        if let Some(priority) = priority(&word.lower) {
+            self.parsed.priority = Some(priority);
+            return Some(1);
+        }
+        if self.rule.is_none() {
+            if let Some((read, rule)) = self.recurrence(i) {
+                self.rule = Some(rule);
+                return Some(read);
+            }
+        }
+        if self.date.is_none() {
+            if let Some((read, date, time)) = self.date(i) {
+                self.date = Some(date);
+                if time.is_some() {
+                    self.time = time;
+                }
+                return Some(read);
+            }
+        }
+        if self.time.is_none() {
+            if let Some((read, time)) = self.time(i) {
+                self.time = Some(time);
+                return Some(read);
+            }
+        }
+
+        None
+    }
+
+    /// `every ...` and the like.
+    fn recurrence(&self, i: usize) -> Option<(usize, RRule)> {
+        let rule = |freq, interval| RRule {
+            freq,
+            interval,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            week_start: Weekday::Mon,
+        };
+
+        let (mut read, mut rule) = match self.word(i)? {
+            "daily" => (1, rule(Frequency::Daily, 1)),
+            "weekly" => (1, rule(Frequency::Weekly, 1)),
+            "monthly" => (1, rule(Frequency::Monthly, 1)),
+            "yearly" | "annually" => (1, rule(Frequency::Yearly, 1)),
+            "every" | "each" => {
+                let next = self.word(i + 1)?;
+
+                if let Some(freq) = frequency(next) {
+                    (2, rule(freq, 1))
+                } else if matches!(next, "weekday" | "weekdays") {
+                    let mut rule = rule(Frequency::Weekly, 1);
+                    rule.by_day = [
+                        Weekday::Mon,
+                        Weekday::Tue,
+                        Weekday::Wed,
+                        Weekday::Thu,
+                        Weekday::Fri,
+                    ]
+                    .into_iter()
+                    .map(|weekday| WeekdayNum {
+                        ordinal: None,
+                        weekday,
+                    })
+                    .collect();
+                    (2, rule)
+                } else if let Some((read, days)) = self.weekdays(i + 1) {
+                    let mut rule = rule(Frequency::Weekly, 1);
+                    rule.by_day = days;
+                    (1 + read, rule)
+                } else {
+                    let interval = match next {
+                        "other" => 2,
+                        _ => next
+                            .parse()
+                            .ok()
+                            .filter(|n| (2..=MAX_INTERVAL).contains(n))?,
+                    };
+                    (3, rule(frequency(self.word(i + 2)?)?, interval))
+                }
+            }
+            _ => return None,
+        };
+
+        // `on friday` for weeks, `on the 1st` for months
+        if self.word(i + read) == Some("on") {
+            match rule.freq {
+                Frequency::Weekly if rule.by_day.is_empty() => {
+                    if let Some((days_read, days)) = self.weekdays(i + read + 1) {
+                        rule.by_day = days;
+                        read += 1 + days_read;
+                    }
+                }
+                Frequency::Monthly => {
+                    if let Some((day_read, day)) = self.month_day(i + read + 1) {
+                        rule.by_month_day = vec![day];
+                        read += 1 + day_read;
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Some((read, rule))
+    }
+
+    /// One or more weekdays, such as `monday and thursday` or `tue, fri`.
+    fn weekdays(&self, i: usize) -> Option<(usize, Vec<WeekdayNum>)> {
+        let mut days: Vec<WeekdayNum> = Vec::new();
+        let mut read = 0;
+
+        // `mondays` as well as `monday`
+        while let Some(day) = self.word(i + read).and_then(|word| {
+            weekday(word, true).or_else(|| weekday(word.strip_suffix('s')?, false))
+        }) {
+            if !days.iter().any(|num| num.weekday == day) {
+                days.push(WeekdayNum {
+                    ordinal: None,
+                    weekday: day,
+                });
+            }
+            read += 1;
+
+            if self.word(i + read) == Some("and")
+                && self
+                    .word(i + read + 1)
+                    .is_some_and(|word| weekday(word, true).is_some())
+            {
+                read += 1;
+            }
+        }
+
+        (!days.is_empty()).then_some((read, days))
+    }
+
+    /// `the 1st` or `the last day`, as a day of the month counted from the end when negative.
+    fn month_day(&self, i: usize) -> Option<(usize, i8)> {
+        if self.word(i) != Some("the") {
+            return None;
+        }
+        if self.word(i + 1) == Some("last") && self.word(i + 2) == Some("day") {
+            return Some((3, -1));
+        }
+
+        let day = ordinal(self.word(i + 1)?)?;
+        Some((2, day as i8))
+    }
+
+    /// A day, and the time for phrases that give one too, such as `tonight` or `in 2 hours`.
+    fn date(&self, i: usize) -> Option<(usize, NaiveDate, Option<NaiveTime>)> {
+        let today = self.today();
+        let word = self.word(i)?;
+
+        match word {
+            "today" => return Some((1, today, None)),
+            "tonight" => return Some((1, today, NaiveTime::from_hms_opt(20, 0, 0))),
+            "tomorrow" => return Some((1, today.succ_opt()?, None)),
+            "in" => {
+                let amount = match self.word(i + 1)? {
+                    "a" | "an" => 1,
+                    amount => amount.parse::<u32>().ok()?,
+                };
+                let unit = self.word(i + 2)?;
+                let unit = unit.strip_suffix('s').unwrap_or(unit);
+
+                let minutes = match unit {
+                    "minute" | "min" => Some(i64::from(amount)),
+                    "hour" | "hr" => Some(i64::from(amount) * 60),
+                    _ => None,
+                };
+                if let Some(minutes) = minutes {
+                    let at = self
+                        .now
+                        .naive_local()
+                        .checked_add_signed(Duration::try_minutes(minutes)?)?;
+                    let time = at.time().with_second(0)?.with_nanosecond(0)?;
+                    return Some((3, at.date(), Some(time)));
+                }
+
+                let date = match unit {
+                    "day" => today.checked_add_signed(Duration::try_days(amount.into())?)?,
+                    "week" => today.checked_add_signed(Duration::try_weeks(amount.into())?)?,
+                    "month" => today.checked_add_months(Months::new(amount))?,
+                    "year" => today.checked_add_months(Months::new(amount.checked_mul(12)?))?,
+                    _ => return None,
+                };
+                return Some((3, date, None));
+            }
+            _ => (),
+        }
+
+        // `on`, `next` and `this` only go with what follows them
+        let (skip, prefixed) = match word {
+            "on" | "next" | "this" => (1, true),
+            _ => (0, false),
+        };
+
+        if let Some(weekday) = self.word(i + skip).and_then(|word| weekday(word, prefixed)) {
+            let ahead =
+                (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+            let ahead = if ahead == 0 { 7 } else { ahead };
+            return Some((skip + 1, today + Duration::days(ahead.into()), None));
+        }
+        if let Some((read, date)) = self.day(i + skip) {
+            return Some((skip + read, date, None));
+        }
+        if word == "on" {
+            let (read, day) = self.month_day(i + 1)?;
+            let date = (0..MAX_DAYS_AHEAD)
+                .filter_map(|ahead| today.checked_add_signed(Duration::days(ahead)))
+                .find(|date| matches_month_day(*date, day))?;
+            return Some((1 + read, date, None));
+        }
+
+        None
+    }
+
+    /// `2026-06-01`, `jun 1`, `june 1st 2026` or `1 june`. Without a year, the next such day.
+    fn day(&self, i: usize) -> Option<(usize, NaiveDate)> {
+        let word = self.word(i)?;
+
+        if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+            return Some((1, date));
+        }
+
+        let (read, month, day) = match (month(word), self.word(i + 1)) {
+            (Some(month), Some(next)) => (2, month, ordinal(next)?),
+            (None, Some(next)) => (2, month(next)?, ordinal(word)?),
+            _ => return None,
+        };
+
+        let year = self
+            .word(i + read)
+            .filter(|year| year.len() == 4)
+            .and_then(|year| year.parse::<i32>().ok());
+
+        match year {
+            Some(year) => Some((read + 1, NaiveDate::from_ymd_opt(year, month, day)?)),
+            None => {
+                let today = self.today();
+                let date = (today.year()..today.year() + 8)
+                    .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+                    .find(|date| *date >= today)?;
+                Some((read, date))
+            }
+        }
+    }
+
+    /// `at 5pm`, `at 17:30`, `5:30pm`, `noon` or `midnight`.
+    fn time(&self, i: usize) -> Option<(usize, NaiveTime)> {
+        let (skip, at) = match self.word(i)? {
+            "at" => (1, true),
+            _ => (0, false),
+        };
+        let word = self.word(i + skip)?;
+
+        let time = match word {
+            "noon" => NaiveTime::from_hms_opt(12, 0, 0),
+            "midnight" => Some(NaiveTime::MIN),
+            _ => clock(word, at),
+        }?;
+
+        Some((skip + 1, time))
+    }
+
+    /// Settle the due date from what was read, the first occurrence of a recurrence when no day
+    /// was given.
+    fn due(mut self) -> Parsed {
+        let tz = self.now.timezone();
+        let today = self.today();
+        let time = self.time.unwrap_or_else(end_of_day);
+        let at = |date: NaiveDate| resolve_local(&tz, date.and_time(time)).with_timezone(&tz);
+
+        let date = match (self.date, &self.rule) {
+            (Some(date), _) => Some(date),
+            (None, Some(rule)) => (0..MAX_DAYS_AHEAD)
+                .filter_map(|ahead| today.checked_add_signed(Duration::days(ahead)))
+                .find(|date| matches_rule(rule, *date) && at(*date) > self.now),
+            (None, None) if self.time.is_some() => Some(today)
+                .filter(|today| at(*today) > self.now)
+                .or_else(|| today.succ_opt()),
+            (None, None) => None,
+        };
+
+        self.parsed.due_at = date.map(|date| at(date).fixed_offset());
+        self.parsed.rrule = self.rule.map(|rule| rule.to_string());
+        self.parsed
+    }
+}
+
+fn priority(word: &str) -> Option<Priority> {
+    match word.strip_prefix('!')? {
+        "high" | "h" | "1" => Some(Priority::High),
+        "medium" | "med" | "m" | "2" => Some(Priority::Medium),
+        "low" | "l" | "3" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn frequency(word: &str) -> Option<Frequency> {
+    match word.strip_suffix('s').unwrap_or(word) {
+        "day" => Some(Frequency::Daily),
+        "week" => Some(Frequency::Weekly),
+        "month" => Some(Frequency::Monthly),
+        "year" => Some(Frequency::Yearly),
+        _ => None,
+    }
+}
+
+/// A weekday by its name, or also by its abbreviation when `short` (abbreviations like `sun`
+/// and `sat` are words of their own otherwise).
+fn weekday(word: &str, short: bool) -> Option<Weekday> {
+    let weekday = match word {
+        "monday" => Weekday::Mon,
+        "tuesday" => Weekday::Tue,
+        "wednesday" => Weekday::Wed,
+        "thursday" => Weekday::Thu,
+        "friday" => Weekday::Fri,
+        "saturday" => Weekday::Sat,
+        "sunday" => Weekday::Sun,
+        _ if !short => return None,
+        "mon" => Weekday::Mon,
+        "tue" | "tues" => Weekday::Tue,
+        "wed" => Weekday::Wed,
+        "thu" | "thur" | "thurs" => Weekday::Thu,
+        "fri" => Weekday::Fri,
+        "sat" => Weekday::Sat,
+        "sun" => Weekday::Sun,
+        _ => return None,
+    };
+
+    Some(weekday)
+}
+
+fn month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+
+    // The first three letters tell months apart
+    let month = MONTHS
+        .iter()
+        .position(|month| word.len() >= 3 && month.starts_with(word))?;
+
+    Some(month as u32 + 1)
+}
+
+/// A day of the month: `1`, `1st`, `22nd`.
+fn ordinal(word: &str) -> Option<u32> {
+    let digits = ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix))
+        .unwrap_or(word);
+
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+/// A time of day, such as `5pm`, `5:30pm` or `17:30`. A bare hour only counts after `at`.
+fn clock(word: &str, at: bool) -> Option<NaiveTime> {
+    let (digits, meridiem) = match word.strip_suffix("am") {
+        Some(digits) => (digits, Some(false)),
+        None => match word.strip_suffix("pm") {
+            Some(digits) => (digits, Some(true)),
+            None => (word, None),
+        },
+    };
+    let (hour, minute) = match digits.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => {
+            (hour.parse::<u32>().ok()?, minute.parse().ok()?)
+        }
+        Some(_) => return None,
+        None if meridiem.is_some() || at => (digits.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+
+    let hour = match meridiem {
+        Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+        Some(_) => return None,
+        None => hour,
+    };
+
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn matches_month_day(date: NaiveDate, day: i8) -> bool {
+    if day > 0 {
+        return date.day() == day as u32;
+    }
+
+    // Counted from the end: the last day is the one before the 1st
+    date.succ_opt()
+        .is_some_and(|next| next.month() != date.month())
+}
+
+/// Whether a recurrence starting on `date` would have it as its first occurrence.
+fn matches_rule(rule: &RRule, date: NaiveDate) -> bool {
+    if rule.by_day.is_empty() && rule.by_month_day.is_empty() {
+        return true;
+    }
+
+    rule.by_day.iter().any(|day| day.weekday == date.weekday())
+        || rule
+            .by_month_day
+            .iter()
+            .any(|day| matches_month_day(date, *day))
+}
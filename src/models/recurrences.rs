@@ -0,0 +1,113 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, Postgres};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::rrule::RRule;
+
+/// A recurrence rule attached to a todo. Every occurrence generated from the rule points back to
+/// the series through `todos.series_id`.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Recurrence {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub rrule: String,
+    /// IANA timezone name, e.g. `Europe/Berlin`, the rule is evaluated in.
+    pub timezone: String,
+    pub dtstart: DateTime<FixedOffset>,
+    pub stopped_at: Option<DateTime<FixedOffset>>,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewRecurrence<'a> {
+    pub rrule: Cow<'a, str>,
+    pub timezone: Cow<'a, str>,
+    /// Defaults to the due date of the todo.
+    pub dtstart: Option<DateTime<FixedOffset>>,
+}
+
+impl Recurrence {
+    pub fn rule(&self) -> Result<RRule> {
+        Ok(self.rrule.parse()?)
+    }
+
+    pub fn tz(&self) -> Result<Tz> {
+        parse_timezone(&self.timezone)
+    }
+
+    /// The due date of the occurrence following the one due at `after`, if the series has one.
+    pub fn next_after(&self, after: DateTime<FixedOffset>) -> Result<Option<DateTime<Utc>>> {
+        let tz = self.tz()?;
+        let dtstart = self.dtstart.with_timezone(&tz);
+
+        Ok(self.rule()?.next_after(dtstart, after.with_timezone(&Utc)))
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn create<'e, E>(
+        db: E,
+        user_id: Uuid,
+        dto: &NewRecurrence<'_>,
+        dtstart: DateTime<FixedOffset>,
+    ) -> Result<Self>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let rule = dto.rrule.parse::<RRule>()?;
+        let tz = parse_timezone(&dto.timezone)?;
+
+        let recurrence = sqlx::query_as::<_, Self>(
+            "INSERT INTO recurrences (user_id, rrule, timezone, dtstart) VALUES ($1, $2, $3, $4) RETURNING *",
+        )
+        .bind(user_id)
+        .bind(rule.to_string())
+        .bind(tz.name())
+        .bind(dtstart)
+        .fetch_one(db)
+        .await?;
+
+        Ok(recurrence)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_id<'e, E>(db: E, id: Uuid) -> Result<Self>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let recurrence = sqlx::query_as::<_, Self>("SELECT * FROM recurrences WHERE id = $1")
+            .bind(id)
+            .fetch_optional(db)
+            .await?;
+
+        recurrence.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Stop generating occurrences. Todos already created are left untouched.
+    #[tracing::instrument(skip(db))]
+    pub async fn stop<'e, E>(db: E, id: Uuid) -> Result<()>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(
+            "UPDATE recurrences SET stopped_at = now() WHERE id = $1 AND stopped_at IS NULL",
+        )
+        .bind(id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    name.parse::<Tz>()
+        .map_err(|_| Error::BadRequest(format!("Unknown timezone `{name}`")).into())
+}
@@ -0,0 +1,309 @@
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, PgPool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    notifications::{Channel, Notification},
+};
+
+use super::todos::Todo;
+
+/// A reminder for a todo. Absolute reminders fire at `remind_at`, relative reminders fire
+/// `minutes_before` the todo is due. `fire_at` is kept in sync with the due date so that the
+/// scheduler only has to look at one indexed column.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Reminder {
+    pub id: Uuid,
+    pub todo_id: Uuid,
+    pub user_id: Uuid,
+    pub channel: Channel,
+    pub remind_at: Option<DateTime<FixedOffset>>,
+    pub minutes_before: Option<i32>,
+    pub fire_at: DateTime<FixedOffset>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub sent_at: Option<DateTime<FixedOffset>>,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewReminder {
+    pub remind_at: Option<DateTime<FixedOffset>>,
+    pub minutes_before: Option<i32>,
+    #[serde(default)]
+    pub channel: Channel,
+}
+
+/// A reminder claimed by the scheduler, joined with what is needed to notify its owner.
+#[derive(Debug, Clone, FromRow)]
+pub struct DueReminder {
+    pub id: Uuid,
+    pub channel: Channel,
+    /// Delivery attempts, this one included.
+    pub attempts: i32,
+    pub todo_id: Uuid,
+    pub title: String,
+    pub due_at: Option<DateTime<FixedOffset>>,
+    pub user_id: Uuid,
+    pub username: String,
+    pub email: String,
+}
+
+impl DueReminder {
+    pub fn notification(&self) -> Notification {
+        let body = match self.due_at {
+            Some(due_at) => format!("\"{}\" is due on {}", self.title, due_at.to_rfc2822()),
+            None => format!("Reminder for \"{}\"", self.title),
+        };
+
+        Notification {
+            user_id: self.user_id,
+            username: self.username.clone(),
+            email: self.email.clone(),
+            todo_id: Some(self.todo_id),
+            subject: format!("Reminder: {}", self.title),
+            body,
+        }
+    }
+}
+
+impl Reminder {
+    #[tracing::instrument(skip(db))]
+    pub async fn create(
+        db: &PgPool,
+        user_id: Uuid,
+        todo_id: Uuid,
+        dto: &NewReminder,
+    ) -> Result<Self> {
+        let todo = Todo::find_by_id(db, user_id, todo_id).await?;
+
+        let fire_at = match (dto.remind_at, dto.minutes_before) {
+            (Some(remind_at), None) => remind_at,
+            (None, Some(minutes)) if minutes >= 0 => {
+                let due_at = todo.due_at.ok_or_else(|| {
+                    Error::BadRequest("A relative reminder needs a todo with a due date".into())
+                })?;
+
+                due_at - Duration::minutes(i64::from(minutes))
+            }
+            (None, Some(_)) => {
+                return Err(Error::BadRequest("minutesBefore cannot be negative".into()).into())
+            }
+            _ => {
+                return Err(Error::BadRequest(
+                    "Provide exactly one of remindAt or minutesBefore".into(),
+                )
+                .into())
+            }
+        };
+
+        let reminder = sqlx::query_as::<_, Self>(
+            "INSERT INTO reminders (todo_id, user_id, channel, remind_at, minutes_before, fire_at) \
+             VALUES ($1, $2, $3, $4, $5, $6) RETURNING *",
+        )
+        .bind(todo.id)
+        .bind(user_id)
+        .bind(dto.channel)
+        .bind(dto.remind_at)
+        .bind(dto.minutes_before)
+        .bind(fire_at)
+        .fetch_one(db)
+        .await?;
+
+        Ok(reminder)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_todo<'e, E>(db: E, user_id: Uuid, todo_id: Uuid) -> Result<Vec<Self>>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let reminders = sqlx::query_as::<_, Self>(
+            "SELECT * FROM reminders WHERE todo_id = $1 AND user_id = $2 ORDER BY fire_at",
+        )
+        .bind(todo_id)
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(reminders)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn delete<'e, E>(db: E, user_id: Uuid, todo_id: Uuid, id: Uuid) -> Result<()>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let result =
+            sqlx::query("DELETE FROM reminders WHERE id = $1 AND todo_id = $2 AND user_id = $3")
+                .bind(id)
+                .bind(todo_id)
+                .bind(user_id)
+                .execute(db)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::EntityNotFound.into());
+        }
+
+        Ok(())
+    }
+
+    /// Move pending relative reminders after the due date of their todo changed. Once the todo
+    /// is no longer due, they are deleted, having nothing left to be relative to.
+    #[tracing::instrument(skip(db))]
+    pub async fn reschedule<'e, E>(
+        db: E,
+        todo_id: Uuid,
+        due_at: Option<DateTime<FixedOffset>>,
+    ) -> Result<()>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let query = match due_at {
+            Some(due_at) => sqlx::query(
+                "UPDATE reminders SET fire_at = $2 - make_interval(mins => minutes_before), \
+                 attempts = 0 WHERE todo_id = $1 AND minutes_before IS NOT NULL AND sent_at IS NULL",
+            )
+            .bind(todo_id)
+            .bind(due_at),
+            None => sqlx::query(
+                "DELETE FROM reminders \
+                 WHERE todo_id = $1 AND minutes_before IS NOT NULL AND sent_at IS NULL",
+            )
+            .bind(todo_id),
+        };
+        query.execute(db).await?;
+
+        Ok(())
+    }
+
+    /// Give the next occurrence of a recurring todo the relative reminders of the previous one.
+    #[tracing::instrument(skip(db))]
+    pub async fn copy_relative<'e, E>(
+        db: E,
+        from: Uuid,
+        to: Uuid,
+        due_at: DateTime<Utc>,
+    ) -> Result<()>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(
+            "INSERT INTO reminders (todo_id, user_id, channel, minutes_before, fire_at) \
+             SELECT $2, user_id, channel, minutes_before, $3 - make_interval(mins => minutes_before) \
+             FROM reminders WHERE todo_id = $1 AND minutes_before IS NOT NULL",
+        )
+        .bind(from)
+        .bind(to)
+        .bind(due_at)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Claim up to `limit` due reminders for `lease` seconds under `token`, counting the attempt.
+    /// The claim is its own statement, committed before anything is delivered, so that each
+    /// reminder is marked on its own afterwards. A reminder whose claim ran out without being
+    /// renewed or marked, say because its scheduler died, is due again.
+    #[tracing::instrument(skip(db))]
+    pub async fn claim_due<'e, E>(
+        db: E,
+        token: Uuid,
+        limit: i64,
+        max_attempts: i32,
+        lease: i64,
+    ) -> Result<Vec<DueReminder>>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let reminders = sqlx::query_as::<_, DueReminder>(
+            "WITH claimed AS ( \
+             UPDATE reminders SET claimed_at = now(), claim_token = $4, attempts = attempts + 1 \
+             WHERE id IN ( \
+             SELECT r.id FROM reminders r JOIN todos t ON t.id = r.todo_id \
+             WHERE r.sent_at IS NULL AND r.fire_at <= now() AND r.attempts < $2 \
+             AND (r.claimed_at IS NULL OR r.claimed_at <= now() - make_interval(secs => $3)) \
+             AND t.completed_at IS NULL AND t.deleted_at IS NULL \
+             ORDER BY r.fire_at LIMIT $1 \
+             FOR UPDATE OF r SKIP LOCKED) \
+             RETURNING id, channel, attempts, todo_id, user_id, fire_at) \
+             SELECT c.id, c.channel, c.attempts, t.id AS todo_id, t.title, t.due_at, \
+             u.id AS user_id, u.username, u.email \
+             FROM claimed c \
+             JOIN todos t ON t.id = c.todo_id \
+             JOIN users u ON u.id = c.user_id \
+             ORDER BY c.fire_at",
+        )
+        .bind(limit)
+        .bind(max_attempts)
+        .bind(lease as f64)
+        .bind(token)
+        .fetch_all(db)
+        .await?;
+
+        Ok(reminders)
+    }
+
+    /// Restart the lease of a reminder claimed under `token`, right before it is delivered.
+    /// Returns false when the claim is no longer held by `token`: it ran out and another
+    /// scheduler claimed the reminder, or the reminder was marked in the meantime.
+    pub async fn renew_claim<'e, E>(db: E, id: Uuid, token: Uuid) -> Result<bool>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let result = sqlx::query(
+            "UPDATE reminders SET claimed_at = now() \
+             WHERE id = $1 AND claim_token = $2 AND sent_at IS NULL",
+        )
+        .bind(id)
+        .bind(token)
+        .execute(db)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn mark_sent<'e, E>(db: E, id: Uuid) -> Result<()>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(
+            "UPDATE reminders SET sent_at = now(), claimed_at = NULL, claim_token = NULL, \
+             last_error = NULL \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_failed<'e, E>(
+        db: E,
+        id: Uuid,
+        error: &str,
+        retry_at: DateTime<Utc>,
+    ) -> Result<()>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(
+            "UPDATE reminders SET claimed_at = NULL, claim_token = NULL, last_error = $2, \
+             fire_at = $3 WHERE id = $1",
+        )
+        .bind(id)
+        .bind(error)
+        .bind(retry_at)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,518 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+
+use crate::error::Error;
+
+/// Upper bound on the number of periods walked while looking for an occurrence. Protects us from
+/// rules that can never match, e.g. `FREQ=MONTHLY;INTERVAL=12;BYMONTHDAY=30` starting in February.
+const MAX_PERIODS: u32 = 100_000;
+
+/// Largest `INTERVAL` accepted, already far more than any todo needs.
+const MAX_INTERVAL: u32 = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Daily => "DAILY",
+            Self::Weekly => "WEEKLY",
+            Self::Monthly => "MONTHLY",
+            Self::Yearly => "YEARLY",
+        }
+    }
+}
+
+/// A `BYDAY` entry such as `MO`, `2TU` or `-1FR`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeekdayNum {
+    pub ordinal: Option<i8>,
+    pub weekday: Weekday,
+}
+
+/// The `UNTIL` bound. A value ending in `Z` is an absolute instant, anything else is a local
+/// (floating) time interpreted in the series' timezone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Until {
+    Utc(DateTime<Utc>),
+    Local(NaiveDateTime),
+}
+
+/// The subset of RFC 5545 recurrence rules supported for todos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<Until>,
+    pub by_day: Vec<WeekdayNum>,
+    pub by_month_day: Vec<i8>,
+    pub week_start: Weekday,
+}
+
+impl RRule {
+    /// Returns the first occurrence strictly after `after`. `dtstart` is always the first
+    /// occurrence of the series.
+    pub fn next_after(&self, dtstart: DateTime<Tz>, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.occurrences(dtstart)
+            .find(|occurrence| *occurrence > after)
+    }
+
+    /// Iterates over every occurrence of the rule, in chronological order, starting at `dtstart`.
+    pub fn occurrences(&self, dtstart: DateTime<Tz>) -> Occurrences<'_> {
+        Occurrences {
+            rule: self,
+            tz: dtstart.timezone(),
+            start: dtstart.naive_local(),
+            period: 0,
+            pending: Vec::new(),
+            emitted: 0,
+            done: false,
+        }
+    }
+
+    /// Local dates produced by the rule in the `period`th period after `start`, sorted, or `None`
+    /// once the period lies beyond the dates chrono can represent.
+    fn period_dates(&self, start: NaiveDate, period: u32) -> Option<Vec<NaiveDate>> {
+        let step = i64::from(period) * i64::from(self.interval);
+
+        let mut dates = match self.freq {
+            Frequency::Daily => vec![start.checked_add_signed(Duration::try_days(step)?)?],
+            Frequency::Weekly => {
+                let offset = days_from(self.week_start, start.weekday());
+                let week = start
+                    .checked_sub_signed(Duration::days(offset))?
+                    .checked_add_signed(Duration::try_weeks(step)?)?;
+
+                if self.by_day.is_empty() {
+                    vec![week.checked_add_signed(Duration::days(offset))?]
+                } else {
+                    self.by_day
+                        .iter()
+                        .map(|day| {
+                            week.checked_add_signed(Duration::days(days_from(
+                                self.week_start,
+                                day.weekday,
+                            )))
+                        })
+                        .collect::<Option<_>>()?
+                }
+            }
+            Frequency::Monthly => {
+                let months = i64::from(start.year()) * 12 + i64::from(start.month0()) + step;
+                let year = i32::try_from(months / 12).ok()?;
+
+                self.month_dates(year, (months % 12) as u32 + 1, start.day())?
+            }
+            Frequency::Yearly => {
+                let year = start.year().checked_add(i32::try_from(step).ok()?)?;
+                if year > NaiveDate::MAX.year() {
+                    return None;
+                }
+
+                NaiveDate::from_ymd_opt(year, start.month(), start.day())
+                    .into_iter()
+                    .collect()
+            }
+        };
+
+        dates.sort();
+        dates.dedup();
+        Some(dates)
+    }
+
+    fn month_dates(&self, year: i32, month: u32, default_day: u32) -> Option<Vec<NaiveDate>> {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let length = days_in_month(year, month) as i64;
+
+        if !self.by_month_day.is_empty() {
+            // Days that do not exist in this month (e.g. 31 in April) are skipped, per RFC 5545.
+            return Some(
+                self.by_month_day
+                    .iter()
+                    .filter_map(|&day| {
+                        let day = i64::from(day);
+                        let index = if day > 0 { day - 1 } else { length + day };
+
+                        (0..length)
+                            .contains(&index)
+                            .then(|| first.checked_add_signed(Duration::days(index)))
+                            .flatten()
+                    })
+                    .collect(),
+            );
+        }
+
+        if !self.by_day.is_empty() {
+            return Some(
+                self.by_day
+                    .iter()
+                    .flat_map(|day| {
+                        let matches: Vec<NaiveDate> = first
+                            .checked_add_signed(Duration::days(days_from(
+                                first.weekday(),
+                                day.weekday,
+                            )))
+                            .into_iter()
+                            .flat_map(|first_match| {
+                                (0..5).filter_map(move |week| {
+                                    first_match.checked_add_signed(Duration::weeks(week))
+                                })
+                            })
+                            .filter(|date| date.month() == month)
+                            .collect();
+
+                        match day.ordinal {
+                            None => matches,
+                            Some(n) if n > 0 => {
+                                matches.get(n as usize - 1).copied().into_iter().collect()
+                            }
+                            Some(n) => matches
+                                .len()
+                                .checked_sub(n.unsigned_abs() as usize)
+                                .and_then(|index| matches.get(index).copied())
+                                .into_iter()
+                                .collect(),
+                        }
+                    })
+                    .collect(),
+            );
+        }
+
+        Some(
+            NaiveDate::from_ymd_opt(year, month, default_day)
+                .into_iter()
+                .collect(),
+        )
+    }
+}
+
+pub struct Occurrences<'a> {
+    rule: &'a RRule,
+    tz: Tz,
+    start: NaiveDateTime,
+    period: u32,
+    pending: Vec<NaiveDateTime>,
+    emitted: u32,
+    done: bool,
+}
+
+impl Occurrences<'_> {
+    fn next_local(&mut self) -> Option<NaiveDateTime> {
+        if self.emitted == 0 {
+            return Some(self.start);
+        }
+
+        while self.pending.is_empty() {
+            if self.period >= MAX_PERIODS {
+                return None;
+            }
+
+            let time = self.start.time();
+            self.pending = self
+                .rule
+                .period_dates(self.start.date(), self.period)?
+                .into_iter()
+                .map(|date| date.and_time(time))
+                .filter(|local| *local > self.start)
+                .rev()
+                .collect();
+            self.period += 1;
+        }
+
+        self.pending.pop()
+    }
+}
+
+impl Iterator for Occurrences<'_> {
+    type Item = DateTime<Utc>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if matches!(self.rule.count, Some(count) if self.emitted >= count) {
+            self.done = true;
+            return None;
+        }
+
+        let Some(local) = self.next_local() else {
+            self.done = true;
+            return None;
+        };
+        let occurrence = resolve_local(&self.tz, local);
+
+        let past_until = match self.rule.until {
+            Some(Until::Utc(until)) => occurrence > until,
+            Some(Until::Local(until)) => local > until,
+            None => false,
+        };
+        if past_until {
+            self.done = true;
+            return None;
+        }
+
+        self.emitted += 1;
+        Some(occurrence)
+    }
+}
+
+/// Maps a wall-clock time in `tz` to an instant, following RFC 5545: an ambiguous time (DST
+/// fall-back) resolves to its first occurrence, and a time inside a DST gap (spring-forward) is
+/// moved forward by the length of the gap.
+pub fn resolve_local(tz: &Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    if let Some(instant) = tz.from_local_datetime(&local).earliest() {
+        return instant.with_timezone(&Utc);
+    }
+
+    // Use the UTC offset that was in effect just before the gap.
+    let offset = tz
+        .offset_from_utc_datetime(&(local - Duration::days(1)))
+        .fix();
+
+    Utc.from_utc_datetime(&(local - Duration::seconds(i64::from(offset.local_minus_utc()))))
+}
+
+fn days_from(from: Weekday, to: Weekday) -> i64 {
+    i64::from((7 + to.num_days_from_monday() - from.num_days_from_monday()) % 7)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|next| next.pred_opt())
+        .map_or(31, |last| last.day())
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    match s {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn weekday_str(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn invalid(message: impl fmt::Display) -> Error {
+    Error::BadRequest(format!("Invalid RRULE: {message}"))
+}
+
+fn parse_until(value: &str) -> Result<Until, Error> {
+    let (value, utc) = match value.strip_suffix('Z') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+
+    let local = if value.len() == 8 {
+        // A bare date bounds the series inclusively, so the whole day is allowed.
+        NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map(|date| date.and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap_or_default()))
+    } else {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+    }
+    .map_err(|_| invalid(format!("UNTIL value `{value}` is not a date or date-time")))?;
+
+    if utc {
+        Ok(Until::Utc(Utc.from_utc_datetime(&local)))
+    } else {
+        Ok(Until::Local(local))
+    }
+}
+
+fn parse_by_day(value: &str) -> Result<WeekdayNum, Error> {
+    // Splitting off the weekday by bytes is only safe on ASCII, which every valid entry is.
+    if !value.is_ascii() {
+        return Err(invalid(format!("invalid BYDAY `{value}`")));
+    }
+
+    let split = value.len().saturating_sub(2);
+    let (ordinal, day) = value.split_at(split);
+
+    let weekday = parse_weekday(day).ok_or_else(|| invalid(format!("unknown weekday `{day}`")))?;
+    let ordinal = if ordinal.is_empty() {
+        None
+    } else {
+        match ordinal.parse::<i8>() {
+            Ok(n) if n != 0 && (-5..=5).contains(&n) => Some(n),
+            _ => return Err(invalid(format!("invalid BYDAY ordinal `{ordinal}`"))),
+        }
+    };
+
+    Ok(WeekdayNum { ordinal, weekday })
+}
+
+impl FromStr for RRule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+
+        let mut freq = None;
+        let mut interval = 1;
+        let mut count = None;
+        let mut until = None;
+        let mut by_day = Vec::new();
+        let mut by_month_day = Vec::new();
+        let mut week_start = Weekday::Mon;
+
+        for part in s.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("`{part}` is not a KEY=VALUE pair")))?;
+            let value = value.to_ascii_uppercase();
+
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        other => return Err(invalid(format!("unsupported FREQ `{other}`"))),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|n| (1..=MAX_INTERVAL).contains(n))
+                        .ok_or_else(|| {
+                            invalid(format!("INTERVAL must be between 1 and {MAX_INTERVAL}"))
+                        })?
+                }
+                "COUNT" => {
+                    count = Some(
+                        value
+                            .parse::<u32>()
+                            .ok()
+                            .filter(|n| *n > 0)
+                            .ok_or_else(|| invalid("COUNT must be a positive integer"))?,
+                    )
+                }
+                "UNTIL" => until = Some(parse_until(&value)?),
+                "BYDAY" => {
+                    by_day = value
+                        .split(',')
+                        .map(parse_by_day)
+                        .collect::<Result<Vec<_>, _>>()?
+                }
+                "BYMONTHDAY" => {
+                    by_month_day = value
+                        .split(',')
+                        .map(|day| match day.parse::<i8>() {
+                            Ok(n) if n != 0 && (-31..=31).contains(&n) => Ok(n),
+                            _ => Err(invalid(format!("invalid BYMONTHDAY `{day}`"))),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?
+                }
+                "WKST" => {
+                    week_start = parse_weekday(&value)
+                        .ok_or_else(|| invalid(format!("unknown weekday `{value}`")))?
+                }
+                other => return Err(invalid(format!("unsupported rule part `{other}`"))),
+            }
+        }
+
+        let freq = freq.ok_or_else(|| invalid("FREQ is required"))?;
+
+        if count.is_some() && until.is_some() {
+            return Err(invalid("COUNT and UNTIL cannot be combined"));
+        }
+        if !by_month_day.is_empty() && freq != Frequency::Monthly {
+            return Err(invalid("BYMONTHDAY is only supported with FREQ=MONTHLY"));
+        }
+        if !by_day.is_empty() && !matches!(freq, Frequency::Weekly | Frequency::Monthly) {
+            return Err(invalid(
+                "BYDAY is only supported with FREQ=WEEKLY or FREQ=MONTHLY",
+            ));
+        }
+        if freq == Frequency::Weekly && by_day.iter().any(|day| day.ordinal.is_some()) {
+            return Err(invalid("BYDAY ordinals are only allowed with FREQ=MONTHLY"));
+        }
+        if !by_day.is_empty() && !by_month_day.is_empty() {
+            return Err(invalid("BYDAY and BYMONTHDAY cannot be combined"));
+        }
+
+        Ok(Self {
+            freq,
+            interval,
+            count,
+            until,
+            by_day,
+            by_month_day,
+            week_start,
+        })
+    }
+}
+
+impl fmt::Display for RRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.freq.as_str())?;
+
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={count}")?;
+        }
+        match self.until {
+            Some(Until::Utc(until)) => write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ"))?,
+            Some(Until::Local(until)) => write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%S"))?,
+            None => (),
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self
+                .by_day
+                .iter()
+                .map(|day| match day.ordinal {
+                    Some(n) => format!("{n}{}", weekday_str(day.weekday)),
+                    None => weekday_str(day.weekday).to_string(),
+                })
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if !self.by_month_day.is_empty() {
+            let days: Vec<String> = self.by_month_day.iter().map(i8::to_string).collect();
+            write!(f, ";BYMONTHDAY={}", days.join(","))?;
+        }
+        if self.week_start != Weekday::Mon {
+            write!(f, ";WKST={}", weekday_str(self.week_start))?;
+        }
+
+        Ok(())
+    }
+}
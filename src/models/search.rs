@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::{
+    pagination::{CursorPage, Keyset, Pagination},
+    settings::Settings,
+    todos::{self, Todo},
+};
+
+// ts_headline marks matches with control characters, which are swapped for `<mark>` once the
+// rest of the text has been escaped
+const START: char = '\u{2}';
+const STOP: char = '\u{3}';
+
+const TITLE_OPTIONS: &str = "HighlightAll=true, StartSel=\u{2}, StopSel=\u{3}";
+const NOTES_OPTIONS: &str =
+    "MaxFragments=2, MaxWords=20, MinWords=5, FragmentDelimiter=\" … \", StartSel=\u{2}, StopSel=\u{3}";
+
+/// The order of search hits, the best matches first.
+const KEYSET: Keyset = Keyset {
+    scope: "search",
+    column: "-ts_rank_cd(todos.search, q.query)",
+    id: "todos.id",
+    kind: "real",
+};
+
+/// `?q=&prefix=` query parameters. With `prefix`, every word of `q` also matches the words it
+/// is the start of, for type-ahead.
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct SearchParams {
+    pub q: String,
+    #[serde(default)]
+    pub prefix: bool,
+}
+
+/// A todo matching a search. Highlights are HTML escaped, with the matches in `<mark>`.
+#[derive(Debug, Serialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub todo: Todo,
+    pub rank: f32,
+    pub title_highlight: String,
+    pub notes_highlight: Option<String>,
+}
+
+impl SearchHit {
+    /// A page of the user's todos whose titles, notes or tags match, best matches first. Without
+    /// `prefix`, `q` is read the way web search engines do: `"quoted phrases"`, `or` and `-word`.
+    ///
+    /// Only the user's own todos are searched. Nothing can be shared with other users yet; once
+    /// lists can be, their todos belong in here too.
+    #[tracing::instrument(skip(db))]
+    pub async fn search(
+        db: &PgPool,
+        user_id: Uuid,
+        params: &SearchParams,
+        pagination: &Pagination,
+    ) -> Result<CursorPage<Self>> {
+        let (function, q) = if params.prefix {
+            ("to_tsquery", prefix_query(&params.q))
+        } else {
+            ("websearch_to_tsquery", Some(params.q.trim().to_string()))
+        };
+        let q = q
+            .filter(|q| !q.is_empty())
+            .ok_or_else(|| Error::BadRequest("Nothing to search for".into()))?;
+
+        let language = Settings::find(db, user_id).await?.search_language;
+
+        let clause = pagination.clause(&KEYSET, 6)?;
+
+        let sql = format!(
+            "SELECT {}, ts_rank_cd(todos.search, q.query) AS rank, \
+             ts_headline($2::regconfig, todos.title, q.query, $4) AS title_highlight, \
+             CASE WHEN todos.notes IS NOT NULL \
+             THEN ts_headline($2::regconfig, todos.notes, q.query, $5) END AS notes_highlight \
+             FROM todos, (SELECT {function}($2::regconfig, $3) AS query) q \
+             WHERE todos.user_id = $1 AND todos.deleted_at IS NULL \
+             AND todos.search @@ q.query{clause}",
+            todos::COLUMNS
+        );
+        let query = sqlx::query_as::<_, Self>(&sql)
+            .bind(user_id)
+            .bind(&language)
+            .bind(&q)
+            .bind(TITLE_OPTIONS)
+            .bind(NOTES_OPTIONS);
+
+        let hits = pagination
+            .bind(query)
+            .fetch_all(db)
+            .await?
+            .into_iter()
+            .map(|hit| Self {
+                title_highlight: highlight(&hit.title_highlight),
+                notes_highlight: hit.notes_highlight.as_deref().map(highlight),
+                ..hit
+            })
+            .collect();
+
+        Ok(pagination.page(&KEYSET, hits, |hit| ((-hit.rank).to_string(), hit.todo.id)))
+    }
+}
+
+/// Turn free text into a `to_tsquery` query that matches every word as a prefix, or `None` when
+/// there are no words. Anything but letters and digits separates words, so the result never
+/// contains query operators from the input.
+pub fn prefix_query(q: &str) -> Option<String> {
+    let terms: Vec<String> = q
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("'{word}':*"))
+        .collect();
+
+    (!terms.is_empty()).then(|| terms.join(" & "))
+}
+
+/// Escape a ts_headline result for HTML and mark the matches with `<mark>`.
+pub fn highlight(headline: &str) -> String {
+    let mut html = String::with_capacity(headline.len());
+
+    for c in headline.chars() {
+        match c {
+            START => html.push_str("<mark>"),
+            STOP => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+
+    html
+}
@@ -0,0 +1,192 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{types::Json, Executor, FromRow, PgConnection, PgPool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::{recurrences::parse_timezone, urgency::Coefficients};
+
+const COLUMNS: &str = "search_language::text AS search_language, timezone, urgency, version";
+
+/// Preferences of a user. Users that never changed them get the defaults.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    /// Text search configuration, such as `english` or `simple`, used to index and search todos.
+    pub search_language: String,
+    /// IANA timezone, such as `Europe/Berlin`, dates written by the user are read in.
+    pub timezone: String,
+    /// The coefficients of the urgency score of todos.
+    pub urgency: Json<Coefficients>,
+    /// 0 until the settings are first changed.
+    pub version: i32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            search_language: "english".into(),
+            timezone: "UTC".into(),
+            urgency: Json(Coefficients::default()),
+            version: 0,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSettings<'a> {
+    pub search_language: Option<Cow<'a, str>>,
+    pub timezone: Option<Cow<'a, str>>,
+    /// Changes the given coefficients; `null` brings one back to its default.
+    pub urgency: Option<Map<String, Value>>,
+}
+
+impl Settings {
+    #[tracing::instrument(skip(db))]
+    pub async fn find<'e, E>(db: E, user_id: Uuid) -> Result<Self>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let settings = sqlx::query_as::<_, Self>(&format!(
+            "SELECT {COLUMNS} FROM user_settings WHERE user_id = $1"
+        ))
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(settings.unwrap_or_default())
+    }
+
+    /// Lock the settings of a user for the rest of the transaction, once they have been changed.
+    async fn lock(conn: &mut PgConnection, user_id: Uuid) -> Result<Self> {
+        let settings = sqlx::query_as::<_, Self>(&format!(
+            "SELECT {COLUMNS} FROM user_settings WHERE user_id = $1 FOR UPDATE"
+        ))
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await?;
+
+        Ok(settings.unwrap_or_default())
+    }
+
+    /// Change the settings of a user, provided they are still at the `expected` version when one
+    /// is given. A new search language reindexes all of their todos.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn update(
+        db: &PgPool,
+        user_id: Uuid,
+        dto: &UpdateSettings<'_>,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        if let Some(timezone) = &dto.timezone {
+            parse_timezone(timezone)?;
+        }
+
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        let urgency = dto
+            .urgency
+            .as_ref()
+            .map(|changes| coefficients(&current.urgency, changes))
+            .transpose()?;
+
+        if let Some(language) = &dto.search_language {
+            let exists: bool =
+                sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_ts_config WHERE cfgname = $1)")
+                    .bind(language)
+                    .fetch_one(&mut *txn)
+                    .await?;
+
+            if !exists {
+                return Err(
+                    Error::BadRequest(format!("Unknown search language `{language}`")).into(),
+                );
+            }
+        }
+
+        let settings = sqlx::query_as::<_, Self>(&format!(
+            "INSERT INTO user_settings (user_id, search_language, timezone, urgency) \
+             VALUES ($1, COALESCE($2, 'english')::regconfig, COALESCE($3, 'UTC'), \
+             COALESCE($4, '{{}}')) \
+             ON CONFLICT (user_id) DO UPDATE SET \
+             search_language = COALESCE($2::regconfig, user_settings.search_language), \
+             timezone = COALESCE($3, user_settings.timezone), \
+             urgency = COALESCE($4, user_settings.urgency), \
+             updated_at = now() \
+             WHERE $5::integer[] IS NULL OR user_settings.version = ANY($5) \
+             RETURNING {COLUMNS}"
+        ))
+        .bind(user_id)
+        .bind(&dto.search_language)
+        .bind(&dto.timezone)
+        .bind(urgency.map(Json))
+        .bind(expected)
+        .fetch_optional(&mut *txn)
+        .await?;
+
+        // Without a row to lock, the settings may have been changed for the first time since
+        let Some(settings) = settings else {
+            let current = Self::find(&mut *txn, user_id).await?;
+            return Err(super::stale(
+                &current,
+                current.version,
+                expected.unwrap_or_default(),
+            ));
+        };
+
+        if settings.search_language != current.search_language {
+            // Reindexing is no change of the user's to their todos, there is nothing to undo
+            sqlx::query("SELECT set_config('todos.undoing', 'on', true)")
+                .execute(&mut *txn)
+                .await?;
+
+            sqlx::query(
+                "UPDATE todos SET search_language = $2::regconfig \
+                 WHERE user_id = $1 AND search_language <> $2::regconfig",
+            )
+            .bind(user_id)
+            .bind(&settings.search_language)
+            .execute(&mut *txn)
+            .await?;
+        }
+
+        txn.commit().await?;
+
+        Ok(settings)
+    }
+}
+
+/// Apply changes to coefficients. Tags are matched as todos keep them, in lowercase without `#`.
+fn coefficients(current: &Coefficients, changes: &Map<String, Value>) -> Result<Coefficients> {
+    let mut merged: Map<String, Value> = serde_json::from_value(serde_json::to_value(current)?)?;
+
+    for (name, value) in changes {
+        match value {
+            Value::Null => merged.remove(name),
+            value => merged.insert(name.clone(), value.clone()),
+        };
+    }
+
+    let mut coefficients: Coefficients = serde_json::from_value(Value::Object(merged))
+        .map_err(|e| Error::BadRequest(format!("Invalid urgency coefficients: {e}")))?;
+    coefficients.tag = coefficients
+        .tag
+        .into_iter()
+        .map(|(tag, coefficient)| {
+            let tag = tag.trim();
+            (
+                tag.strip_prefix('#').unwrap_or(tag).to_lowercase(),
+                coefficient,
+            )
+        })
+        .collect();
+
+    Ok(coefficients)
+}
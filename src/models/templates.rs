@@ -0,0 +1,559 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::{
+    lists::{CreateList, List},
+    position,
+    quick::end_of_day,
+    recurrences::parse_timezone,
+    rrule::resolve_local,
+    settings::Settings,
+    todos::{CreateTodo, Priority, Todo},
+};
+
+/// Todos a template may create.
+const MAX_TODOS: usize = 500;
+
+/// Checklist items of a todo of a template.
+const MAX_CHECKLIST: usize = 100;
+
+/// A list, or some todos, to create again and again. Its text may contain placeholders such as
+/// `{{name}}`, filled in with the variables given when instantiating it.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub body: Json<TemplateBody>,
+    /// The placeholders of the body, in the order they first appear.
+    #[sqlx(skip)]
+    pub variables: Vec<String>,
+    pub version: i32,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateBody {
+    /// The name of the list to create with the todos. Without one, the todos go to the list
+    /// chosen when instantiating.
+    pub list: Option<String>,
+    #[serde(default)]
+    pub todos: Vec<TemplateTodo>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateTodo {
+    pub title: String,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub priority: Option<Priority>,
+    pub estimate_minutes: Option<i32>,
+    /// Days after the start date the todo is due, if it has a due date.
+    pub due_offset: Option<i32>,
+    /// The time of day it is due, in the user's timezone. The end of the day otherwise.
+    pub due_time: Option<NaiveTime>,
+    /// The subtasks of the todo. Todos do not nest, so these are the items of its checklist.
+    #[serde(default)]
+    pub checklist: Vec<String>,
+}
+
+/// What to make a template of.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(
+    tag = "from",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Source {
+    /// A list with its todos and their checklists.
+    List { list_id: Uuid },
+    /// A todo with its checklist.
+    Todo { todo_id: Uuid },
+    /// A body written by hand.
+    Body { body: TemplateBody },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTemplate<'a> {
+    pub name: Cow<'a, str>,
+    #[serde(flatten)]
+    pub source: Source,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTemplate<'a> {
+    pub name: Option<Cow<'a, str>>,
+    pub body: Option<TemplateBody>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Instantiate {
+    /// The day due offsets count from, today in the user's timezone by default.
+    pub start: Option<NaiveDate>,
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    /// Where the todos of a template without a list go, the Inbox by default.
+    pub list_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Instantiated {
+    pub list: Option<List>,
+    pub todos: Vec<Todo>,
+}
+
+impl TemplateBody {
+    fn validate(&self) -> Result<()> {
+        if self.list.is_none() && self.todos.is_empty() {
+            return Err(Error::BadRequest("A template needs a list or a todo".into()).into());
+        }
+        if self
+            .list
+            .as_ref()
+            .is_some_and(|list| list.trim().is_empty())
+        {
+            return Err(Error::BadRequest("The list of a template needs a name".into()).into());
+        }
+        if self.todos.len() > MAX_TODOS {
+            return Err(Error::BadRequest(format!(
+                "A template cannot have more than {MAX_TODOS} todos"
+            ))
+            .into());
+        }
+
+        for todo in &self.todos {
+            if todo.title.trim().is_empty() {
+                return Err(
+                    Error::BadRequest("The todos of a template need a title".into()).into(),
+                );
+            }
+            if todo.checklist.len() > MAX_CHECKLIST {
+                return Err(Error::BadRequest(format!(
+                    "A todo of a template cannot have more than {MAX_CHECKLIST} checklist items"
+                ))
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every text of the body, in order.
+    fn texts(&self) -> impl Iterator<Item = &str> {
+        self.list
+            .as_deref()
+            .into_iter()
+            .chain(self.todos.iter().flat_map(|todo| {
+                std::iter::once(todo.title.as_str())
+                    .chain(todo.notes.as_deref())
+                    .chain(todo.tags.iter().map(String::as_str))
+                    .chain(todo.checklist.iter().map(String::as_str))
+            }))
+    }
+
+    /// The body with its placeholders filled in.
+    fn substitute(&self, variables: &HashMap<String, String>) -> Result<Self> {
+        let fill = |text: &str| substitute(text, variables);
+
+        Ok(Self {
+            list: self.list.as_deref().map(fill).transpose()?,
+            todos: self
+                .todos
+                .iter()
+                .map(|todo| {
+                    Ok(TemplateTodo {
+                        title: fill(&todo.title)?,
+                        notes: todo.notes.as_deref().map(fill).transpose()?,
+                        tags: todo
+                            .tags
+                            .iter()
+                            .map(|tag| fill(tag))
+                            .collect::<Result<_>>()?,
+                        checklist: todo
+                            .checklist
+                            .iter()
+                            .map(|item| fill(item))
+                            .collect::<Result<_>>()?,
+                        ..todo.clone()
+                    })
+                })
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl Template {
+    fn with_variables(mut self) -> Self {
+        let mut variables: Vec<String> = Vec::new();
+        for name in self.body.texts().flat_map(placeholders) {
+            if !variables.contains(&name) {
+                variables.push(name);
+            }
+        }
+
+        self.variables = variables;
+        self
+    }
+
+    /// Save a template, made from a list or a todo of the user, or from a body.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn create(db: &PgPool, user_id: Uuid, dto: &CreateTemplate<'_>) -> Result<Self> {
+        if dto.name.trim().is_empty() {
+            return Err(Error::BadRequest("A template needs a name".into()).into());
+        }
+
+        let mut conn = db.acquire().await?;
+
+        let body = match &dto.source {
+            Source::List { list_id } => {
+                let list = List::find_by_id(&mut *conn, user_id, *list_id).await?;
+                let todos = Todo::find_all(&mut *conn, user_id, Some(Some(list.id)), None).await?;
+
+                TemplateBody {
+                    list: Some(list.name),
+                    todos: Self::capture(&mut conn, user_id, &todos).await?,
+                }
+            }
+            Source::Todo { todo_id } => {
+                let todo = Todo::find_by_id(&mut *conn, user_id, *todo_id).await?;
+
+                TemplateBody {
+                    list: None,
+                    todos: Self::capture(&mut conn, user_id, &[todo]).await?,
+                }
+            }
+            Source::Body { body } => body.clone(),
+        };
+        body.validate()?;
+
+        let template = sqlx::query_as::<_, Self>(
+            "INSERT INTO templates (user_id, name, body) VALUES ($1, $2, $3) RETURNING *",
+        )
+        .bind(user_id)
+        .bind(dto.name.trim())
+        .bind(Json(&body))
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok(template.with_variables())
+    }
+
+    /// The todos as they would be written in a template, with their checklists as their subtasks.
+    /// Due dates become days after the earliest of them.
+    async fn capture(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        todos: &[Todo],
+    ) -> Result<Vec<TemplateTodo>> {
+        let tz = parse_timezone(&Settings::find(&mut *conn, user_id).await?.timezone)?;
+
+        let ids: Vec<Uuid> = todos.iter().map(|todo| todo.id).collect();
+        let items: Vec<(Uuid, String)> = sqlx::query_as(
+            "SELECT todo_id, text FROM checklist_items WHERE todo_id = ANY($1) ORDER BY position",
+        )
+        .bind(&ids)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let due = |todo: &Todo| todo.due_at.map(|due| due.with_timezone(&tz).naive_local());
+        let start = todos.iter().filter_map(due).map(|due| due.date()).min();
+
+        Ok(todos
+            .iter()
+            .map(|todo| {
+                let due = due(todo);
+
+                TemplateTodo {
+                    title: todo.title.clone(),
+                    notes: todo.notes.clone(),
+                    tags: todo.tags.clone(),
+                    priority: todo.priority,
+                    estimate_minutes: todo.estimate_minutes,
+                    due_offset: due
+                        .zip(start)
+                        .map(|(due, start)| (due.date() - start).num_days() as i32),
+                    due_time: due
+                        .map(|due| due.time())
+                        .filter(|time| *time != end_of_day()),
+                    checklist: items
+                        .iter()
+                        .filter(|(todo_id, _)| *todo_id == todo.id)
+                        .map(|(_, text)| text.clone())
+                        .collect(),
+                }
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_all(db: &PgPool, user_id: Uuid) -> Result<Vec<Self>> {
+        let templates = sqlx::query_as::<_, Self>(
+            "SELECT * FROM templates WHERE user_id = $1 ORDER BY lower(name), id",
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(templates.into_iter().map(Self::with_variables).collect())
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_id(db: &PgPool, user_id: Uuid, id: Uuid) -> Result<Self> {
+        let template =
+            sqlx::query_as::<_, Self>("SELECT * FROM templates WHERE id = $1 AND user_id = $2")
+                .bind(id)
+                .bind(user_id)
+                .fetch_optional(db)
+                .await?;
+
+        template
+            .map(Self::with_variables)
+            .ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Lock one of the user's templates for the rest of the transaction.
+    async fn lock(conn: &mut PgConnection, user_id: Uuid, id: Uuid) -> Result<Self> {
+        let template = sqlx::query_as::<_, Self>(
+            "SELECT * FROM templates WHERE id = $1 AND user_id = $2 FOR UPDATE",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await?;
+
+        template
+            .map(Self::with_variables)
+            .ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn update(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        dto: &UpdateTemplate<'_>,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        if dto.name.as_ref().is_some_and(|name| name.trim().is_empty()) {
+            return Err(Error::BadRequest("A template needs a name".into()).into());
+        }
+        if let Some(body) = &dto.body {
+            body.validate()?;
+        }
+
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        let template = sqlx::query_as::<_, Self>(
+            "UPDATE templates SET name = COALESCE($2, name), body = COALESCE($3, body), \
+             updated_at = now() WHERE id = $1 RETURNING *",
+        )
+        .bind(current.id)
+        .bind(dto.name.as_deref().map(str::trim))
+        .bind(dto.body.as_ref().map(Json))
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(Self::with_variables(template))
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn delete(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        expected: Option<&[i32]>,
+    ) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        sqlx::query("DELETE FROM templates WHERE id = $1")
+            .bind(current.id)
+            .execute(&mut *txn)
+            .await?;
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Create the list and todos of a template in one transaction, its placeholders filled in
+    /// and its due dates counted from the start date.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn instantiate(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        dto: &Instantiate,
+    ) -> Result<Instantiated> {
+        let template = Self::find_by_id(db, user_id, id).await?;
+        let body = template.body.substitute(&dto.variables)?;
+
+        if body.list.is_some() && dto.list_id.is_some() {
+            return Err(Error::BadRequest("This template creates a list of its own".into()).into());
+        }
+
+        let mut txn = db.begin().await?;
+
+        let tz = parse_timezone(&Settings::find(&mut *txn, user_id).await?.timezone)?;
+        let start = dto
+            .start
+            .unwrap_or_else(|| Utc::now().with_timezone(&tz).date_naive());
+
+        let list = match &body.list {
+            Some(name) => Some(
+                List::insert(
+                    &mut txn,
+                    user_id,
+                    &CreateList {
+                        name: name.trim().into(),
+                    },
+                )
+                .await?,
+            ),
+            None => None,
+        };
+        let list_id = list.as_ref().map(|list| list.id).or(dto.list_id);
+
+        let mut ids = Vec::with_capacity(body.todos.len());
+        for todo in &body.todos {
+            let due_at = todo
+                .due_offset
+                .map(|offset| {
+                    let day = start
+                        .checked_add_signed(chrono::Duration::days(offset.into()))
+                        .ok_or_else(|| Error::BadRequest("Due date out of range".into()))?;
+                    let time = todo.due_time.unwrap_or_else(end_of_day);
+
+                    Ok::<_, Error>(resolve_local(&tz, day.and_time(time)).fixed_offset())
+                })
+                .transpose()?;
+
+            let created = Todo::insert(
+                &mut txn,
+                user_id,
+                &CreateTodo {
+                    title: todo.title.trim().into(),
+                    notes: todo.notes.as_deref().map(Into::into),
+                    due_at,
+                    start_at: None,
+                    list_id,
+                    recurrence: None,
+                    tags: todo.tags.iter().map(|tag| tag.as_str().into()).collect(),
+                    priority: todo.priority,
+                    estimate_minutes: todo.estimate_minutes,
+                    fields: Default::default(),
+                },
+            )
+            .await?;
+
+            for (text, position) in todo
+                .checklist
+                .iter()
+                .zip(position::spread(todo.checklist.len()))
+            {
+                sqlx::query(
+                    "INSERT INTO checklist_items (todo_id, text, position) VALUES ($1, $2, $3)",
+                )
+                .bind(created.id)
+                .bind(text)
+                .bind(position)
+                .execute(&mut *txn)
+                .await?;
+            }
+
+            ids.push(created.id);
+        }
+
+        // The todos of a list of their own are spread out rather than left with the long keys
+        // of one append after another
+        if list.is_some() {
+            position::rebalance(&mut txn, "todos", &ids).await?;
+        }
+
+        // Read back for the checklist counts
+        let mut todos = Vec::with_capacity(ids.len());
+        for id in ids {
+            todos.push(Todo::find_by_id(&mut *txn, user_id, id).await?);
+        }
+
+        txn.commit().await?;
+
+        Ok(Instantiated { list, todos })
+    }
+}
+
+/// Replace each `{{name}}` of `text`, `f` giving the value for a name. Anything else between
+/// braces is left as it is.
+fn replace(text: &str, mut f: impl FnMut(&str) -> Result<String>) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+
+        let name = after
+            .find("}}")
+            .map(|end| (end, after[..end].trim()))
+            .filter(|(_, name)| {
+                !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+            });
+
+        match name {
+            Some((end, name)) => {
+                out.push_str(&f(name)?);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+/// The names of the placeholders of `text`, in order.
+pub fn placeholders(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    // Never fails: every name is accepted
+    let _ = replace(text, |name| {
+        names.push(name.to_string());
+        Ok(String::new())
+    });
+
+    names
+}
+
+/// Fill in the placeholders of `text`. A placeholder without a value is an error.
+pub fn substitute(text: &str, variables: &HashMap<String, String>) -> Result<String> {
+    replace(text, |name| {
+        variables.get(name).cloned().ok_or_else(|| {
+            Error::BadRequest(format!("Missing a value for `{{{{{name}}}}}`")).into()
+        })
+    })
+}
@@ -0,0 +1,393 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::{
+    pagination::{CursorPage, Keyset, Pagination},
+    settings::Settings,
+    todos::Todo,
+};
+
+/// Days a report may cover.
+const MAX_REPORT_DAYS: i64 = 366;
+
+/// The order of listed entries, oldest first.
+const KEYSET: Keyset = Keyset {
+    scope: "time_entries",
+    column: "started_at",
+    id: "id",
+    kind: "timestamptz",
+};
+
+/// Time a user spent on a todo. An entry without an end is a running timer.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeEntry {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub todo_id: Uuid,
+    pub started_at: DateTime<FixedOffset>,
+    pub ended_at: Option<DateTime<FixedOffset>>,
+    pub note: Option<String>,
+    pub version: i32,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartTimer<'a> {
+    pub todo_id: Uuid,
+    pub note: Option<Cow<'a, str>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTimeEntry<'a> {
+    pub todo_id: Uuid,
+    pub started_at: DateTime<FixedOffset>,
+    pub ended_at: DateTime<FixedOffset>,
+    pub note: Option<Cow<'a, str>>,
+}
+
+/// Setting the end of a running entry stops its timer.
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTimeEntry<'a> {
+    pub started_at: Option<DateTime<FixedOffset>>,
+    pub ended_at: Option<DateTime<FixedOffset>>,
+    /// `null` removes the note.
+    #[serde(default, deserialize_with = "super::nullable")]
+    pub note: Option<Option<Cow<'a, str>>>,
+}
+
+/// `?todoId=&from=&to=`, entries of a todo or started within a period.
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryParams {
+    pub todo_id: Option<Uuid>,
+    pub from: Option<DateTime<FixedOffset>>,
+    pub to: Option<DateTime<FixedOffset>>,
+}
+
+/// `?from=&to=`, the first and last day of a report, in the user's timezone.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportParams {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+/// Tracked time of a period against the estimates of the todos it was spent on.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub timezone: String,
+    pub tracked_seconds: i64,
+    pub days: Vec<ReportRow>,
+    pub lists: Vec<ReportRow>,
+    pub tags: Vec<ReportRow>,
+}
+
+#[derive(Debug, Serialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportRow {
+    /// The day, the list id or the tag. `None` for the Inbox and for todos without tags.
+    pub key: Option<String>,
+    pub tracked_seconds: i64,
+    /// The estimates of the todos time was tracked on. Days have none.
+    pub estimate_seconds: Option<i64>,
+}
+
+/// Time tracked in each day of the report, `$2` to `$3` in timezone `$4`, split at midnight.
+/// Running timers count until now.
+const PIECES: &str = "WITH days AS ( \
+    SELECT d::date AS day, d::timestamp AT TIME ZONE $4 AS day_start, \
+    (d + interval '1 day')::timestamp AT TIME ZONE $4 AS day_end \
+    FROM generate_series($2::date, $3::date, interval '1 day') d), \
+    pieces AS ( \
+    SELECT e.todo_id, days.day, EXTRACT(EPOCH FROM \
+    LEAST(COALESCE(e.ended_at, now()), days.day_end) - GREATEST(e.started_at, days.day_start)) \
+    AS seconds \
+    FROM time_entries e JOIN days ON e.started_at < days.day_end \
+    AND COALESCE(e.ended_at, now()) > days.day_start \
+    JOIN todos t ON t.id = e.todo_id AND t.deleted_at IS NULL \
+    WHERE e.user_id = $1), \
+    per_todo AS (SELECT todo_id, sum(seconds) AS seconds FROM pieces GROUP BY todo_id) ";
+
+fn validate_range(
+    started_at: DateTime<FixedOffset>,
+    ended_at: Option<DateTime<FixedOffset>>,
+) -> Result<()> {
+    if ended_at.is_some_and(|ended_at| ended_at < started_at) {
+        return Err(Error::BadRequest("A time entry cannot end before it starts".into()).into());
+    }
+
+    Ok(())
+}
+
+/// Rows of a report, aggregated from its [`PIECES`] by `sql`.
+async fn report_rows(
+    db: &PgPool,
+    user_id: Uuid,
+    params: &ReportParams,
+    timezone: &str,
+    sql: &str,
+) -> Result<Vec<ReportRow>> {
+    let sql = format!("{PIECES}{sql}");
+
+    let rows = sqlx::query_as::<_, ReportRow>(&sql)
+        .bind(user_id)
+        .bind(params.from)
+        .bind(params.to)
+        .bind(timezone)
+        .fetch_all(db)
+        .await?;
+
+    Ok(rows)
+}
+
+impl TimeEntry {
+    /// The running timer of the user.
+    #[tracing::instrument(skip(db))]
+    pub async fn running(db: &PgPool, user_id: Uuid) -> Result<Self> {
+        let entry = sqlx::query_as::<_, Self>(
+            "SELECT * FROM time_entries WHERE user_id = $1 AND ended_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        entry.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Start a timer on a todo. A user can only have one running at a time.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn start(db: &PgPool, user_id: Uuid, dto: &StartTimer<'_>) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let todo = Todo::find_by_id(&mut *txn, user_id, dto.todo_id).await?;
+
+        let entry = sqlx::query_as::<_, Self>(
+            "INSERT INTO time_entries (user_id, todo_id, started_at, note) \
+             VALUES ($1, $2, now(), $3) RETURNING *",
+        )
+        .bind(user_id)
+        .bind(todo.id)
+        .bind(&dto.note)
+        .fetch_one(&mut *txn)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_unique_violation() => {
+                Error::Conflict("A timer is already running".into()).into()
+            }
+            e => crate::error::Report::from(e),
+        })?;
+
+        txn.commit().await?;
+
+        Ok(entry)
+    }
+
+    /// Stop the running timer of the user.
+    #[tracing::instrument(skip(db))]
+    pub async fn stop(db: &PgPool, user_id: Uuid) -> Result<Self> {
+        let entry = sqlx::query_as::<_, Self>(
+            "UPDATE time_entries SET ended_at = now(), updated_at = now() \
+             WHERE user_id = $1 AND ended_at IS NULL RETURNING *",
+        )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        entry.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Record time spent without a timer.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn create(db: &PgPool, user_id: Uuid, dto: &NewTimeEntry<'_>) -> Result<Self> {
+        validate_range(dto.started_at, Some(dto.ended_at))?;
+
+        let todo = Todo::find_by_id(db, user_id, dto.todo_id).await?;
+
+        let entry = sqlx::query_as::<_, Self>(
+            "INSERT INTO time_entries (user_id, todo_id, started_at, ended_at, note) \
+             VALUES ($1, $2, $3, $4, $5) RETURNING *",
+        )
+        .bind(user_id)
+        .bind(todo.id)
+        .bind(dto.started_at)
+        .bind(dto.ended_at)
+        .bind(&dto.note)
+        .fetch_one(db)
+        .await?;
+
+        Ok(entry)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_all(
+        db: &PgPool,
+        user_id: Uuid,
+        params: &EntryParams,
+        pagination: &Pagination,
+    ) -> Result<CursorPage<Self>> {
+        let sql = format!(
+            "SELECT * FROM time_entries WHERE user_id = $1 \
+             AND ($2::uuid IS NULL OR todo_id = $2) \
+             AND ($3::timestamptz IS NULL OR started_at >= $3) \
+             AND ($4::timestamptz IS NULL OR started_at < $4){}",
+            pagination.clause(&KEYSET, 5)?
+        );
+
+        let query = sqlx::query_as::<_, Self>(&sql)
+            .bind(user_id)
+            .bind(params.todo_id)
+            .bind(params.from)
+            .bind(params.to);
+        let entries = pagination.bind(query).fetch_all(db).await?;
+
+        Ok(pagination.page(&KEYSET, entries, |entry| {
+            (entry.started_at.to_rfc3339(), entry.id)
+        }))
+    }
+
+    /// Lock one of the user's entries for the rest of the transaction.
+    async fn lock(conn: &mut PgConnection, user_id: Uuid, id: Uuid) -> Result<Self> {
+        let entry = sqlx::query_as::<_, Self>(
+            "SELECT * FROM time_entries WHERE id = $1 AND user_id = $2 FOR UPDATE",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await?;
+
+        entry.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn update(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        dto: &UpdateTimeEntry<'_>,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let entry = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&entry, entry.version, expected)?;
+
+        validate_range(
+            dto.started_at.unwrap_or(entry.started_at),
+            dto.ended_at.or(entry.ended_at),
+        )?;
+
+        let entry = sqlx::query_as::<_, Self>(
+            "UPDATE time_entries SET started_at = COALESCE($2, started_at), \
+             ended_at = COALESCE($3, ended_at), note = CASE WHEN $4 THEN $5 ELSE note END, \
+             updated_at = now() WHERE id = $1 RETURNING *",
+        )
+        .bind(entry.id)
+        .bind(dto.started_at)
+        .bind(dto.ended_at)
+        .bind(dto.note.is_some())
+        .bind(dto.note.clone().flatten())
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(entry)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn delete(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        expected: Option<&[i32]>,
+    ) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        let entry = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&entry, entry.version, expected)?;
+
+        sqlx::query("DELETE FROM time_entries WHERE id = $1")
+            .bind(entry.id)
+            .execute(&mut *txn)
+            .await?;
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Tracked time from the first to the last day of the params, by day, list and tag.
+    #[tracing::instrument(skip(db))]
+    pub async fn report(db: &PgPool, user_id: Uuid, params: &ReportParams) -> Result<TimeReport> {
+        let days = (params.to - params.from).num_days() + 1;
+        if days < 1 {
+            return Err(Error::BadRequest("A report cannot end before it starts".into()).into());
+        }
+        if days > MAX_REPORT_DAYS {
+            return Err(Error::BadRequest(format!(
+                "A report cannot cover more than {MAX_REPORT_DAYS} days"
+            ))
+            .into());
+        }
+
+        let settings = Settings::find(db, user_id).await?;
+
+        let tz = &settings.timezone;
+        let days = report_rows(
+            db,
+            user_id,
+            params,
+            tz,
+            "SELECT day::text AS key, round(sum(seconds))::bigint AS tracked_seconds, \
+             NULL::bigint AS estimate_seconds FROM pieces GROUP BY day ORDER BY day",
+        )
+        .await?;
+        let lists = report_rows(
+            db,
+            user_id,
+            params,
+            tz,
+            "SELECT t.list_id::text AS key, round(sum(p.seconds))::bigint AS tracked_seconds, \
+             (sum(t.estimate_minutes) * 60)::bigint AS estimate_seconds \
+             FROM per_todo p JOIN todos t ON t.id = p.todo_id \
+             GROUP BY t.list_id ORDER BY tracked_seconds DESC, key",
+        )
+        .await?;
+        let tags = report_rows(
+            db,
+            user_id,
+            params,
+            tz,
+            "SELECT tag AS key, round(sum(p.seconds))::bigint AS tracked_seconds, \
+             (sum(t.estimate_minutes) * 60)::bigint AS estimate_seconds \
+             FROM per_todo p JOIN todos t ON t.id = p.todo_id \
+             LEFT JOIN LATERAL unnest(t.tags) tag ON true \
+             GROUP BY tag ORDER BY tracked_seconds DESC, key",
+        )
+        .await?;
+
+        Ok(TimeReport {
+            from: params.from,
+            to: params.to,
+            tracked_seconds: days.iter().map(|day| day.tracked_seconds).sum(),
+            timezone: settings.timezone,
+            days,
+            lists,
+            tags,
+        })
+    }
+}
@@ -0,0 +1,1234 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{types::Json, Acquire, Executor, FromRow, PgConnection, PgPool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    filter::Filter,
+    notifications::Notification,
+    storage::Storage,
+};
+
+use super::{
+    attachments::Attachment,
+    custom_fields::{self, CustomField},
+    lists::List,
+    notes,
+    pagination::{CursorPage, Keyset, Pagination},
+    position::{self, Placement},
+    recurrences::{parse_timezone, NewRecurrence, Recurrence},
+    reminders::Reminder,
+    rrule::resolve_local,
+    settings::Settings,
+};
+
+/// Columns selected for a [`Todo`]: the row itself plus the values derived from related rows.
+pub(crate) const COLUMNS: &str = "todos.*, \
+    link_todo_references(todos.notes_html, todos.user_id) AS linked_notes_html, \
+    EXISTS (SELECT 1 FROM todo_dependencies d JOIN todos p ON p.id = d.depends_on_id \
+    WHERE d.todo_id = todos.id AND p.completed_at IS NULL AND p.deleted_at IS NULL) AS blocked, \
+    (SELECT count(*) FILTER (WHERE c.checked) FROM checklist_items c WHERE c.todo_id = todos.id) \
+    AS checklist_checked, \
+    (SELECT count(*) FROM checklist_items c WHERE c.todo_id = todos.id) AS checklist_total";
+
+/// The order of listed todos.
+const KEYSET: Keyset = Keyset {
+    scope: "todos",
+    column: "todos.position",
+    id: "todos.id",
+    kind: "varchar",
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Todo {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub title: String,
+    /// CommonMark.
+    pub notes: Option<String>,
+    /// The notes rendered to sanitised HTML, with references named after their todos as they
+    /// are now.
+    #[sqlx(rename = "linked_notes_html")]
+    pub notes_html: Option<String>,
+    pub due_at: Option<DateTime<FixedOffset>>,
+    /// Until then the todo is snoozed, left out of active views.
+    pub start_at: Option<DateTime<FixedOffset>>,
+    /// Whether to notify the owner once the todo starts.
+    pub notify_on_start: bool,
+    pub completed_at: Option<DateTime<FixedOffset>>,
+    /// The recurrence this todo is an occurrence of.
+    pub series_id: Option<Uuid>,
+    /// `None` for todos in the Inbox.
+    pub list_id: Option<Uuid>,
+    pub position: String,
+    /// The board column of the list the todo is in.
+    pub status_id: Option<Uuid>,
+    /// Lowercase labels without the leading `#`.
+    pub tags: Vec<String>,
+    pub priority: Option<Priority>,
+    /// Expected effort, in minutes.
+    pub estimate_minutes: Option<i32>,
+    /// Values of the custom fields of the list, by key.
+    pub fields: Json<Map<String, Value>>,
+    /// Counts the changes to the todo.
+    pub version: i32,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+    /// When the todo was moved to the trash.
+    pub deleted_at: Option<DateTime<FixedOffset>>,
+    /// Whether any of the todos this one depends on is still open.
+    pub blocked: bool,
+    /// Number of checked checklist items.
+    pub checklist_checked: i64,
+    /// Number of checklist items.
+    pub checklist_total: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTodo<'a> {
+    pub title: Cow<'a, str>,
+    pub notes: Option<Cow<'a, str>>,
+    pub due_at: Option<DateTime<FixedOffset>>,
+    pub start_at: Option<DateTime<FixedOffset>>,
+    pub list_id: Option<Uuid>,
+    pub recurrence: Option<NewRecurrence<'a>>,
+    #[serde(default)]
+    pub tags: Vec<Cow<'a, str>>,
+    pub priority: Option<Priority>,
+    pub estimate_minutes: Option<i32>,
+    /// Values of custom fields of the list, by name or key.
+    #[serde(default)]
+    pub fields: Map<String, Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTodo<'a> {
+    pub title: Option<Cow<'a, str>>,
+    /// `null` clears the notes.
+    #[serde(default, deserialize_with = "super::nullable")]
+    pub notes: Option<Option<Cow<'a, str>>>,
+    /// `null` clears the due date, which a recurring todo cannot do without.
+    #[serde(default, deserialize_with = "super::nullable")]
+    pub due_at: Option<Option<DateTime<FixedOffset>>>,
+    /// `null` wakes the todo up.
+    #[serde(default, deserialize_with = "super::nullable")]
+    pub start_at: Option<Option<DateTime<FixedOffset>>>,
+    /// Replaces all the tags of the todo.
+    pub tags: Option<Vec<Cow<'a, str>>>,
+    /// `null` removes the priority.
+    #[serde(default, deserialize_with = "super::nullable")]
+    pub priority: Option<Option<Priority>>,
+    /// `null` removes the estimate.
+    #[serde(default, deserialize_with = "super::nullable")]
+    pub estimate_minutes: Option<Option<i32>>,
+    /// Sets the given custom fields, by name or key; `null` clears one.
+    pub fields: Option<Map<String, Value>>,
+}
+
+#[derive(
+    Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, sqlx::Type,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+/// What completing a recurring todo applies to.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompleteScope {
+    /// Complete this occurrence only; the next one is generated.
+    #[default]
+    Occurrence,
+    /// Complete this occurrence and end the series.
+    Series,
+}
+
+/// Where to move a todo: right after `after`, right before `before`, or last when neither is
+/// given. `listId` moves the todo to another list, `null` being the Inbox.
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveTodo {
+    pub after: Option<Uuid>,
+    pub before: Option<Uuid>,
+    #[serde(default, deserialize_with = "super::nullable")]
+    pub list_id: Option<Option<Uuid>>,
+}
+
+/// How long to snooze a todo for.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SnoozeUntil {
+    /// Three hours from now.
+    LaterToday,
+    /// Tomorrow morning.
+    Tomorrow,
+    /// Monday morning.
+    NextWeek,
+    /// The time given as `startAt`.
+    Custom,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnoozeTodo {
+    pub until: SnoozeUntil,
+    pub start_at: Option<DateTime<FixedOffset>>,
+    /// Notify the owner once the todo starts.
+    #[serde(default)]
+    pub notify: bool,
+}
+
+/// A snoozed todo whose start has come, claimed by the scheduler.
+#[derive(Debug, Clone, FromRow)]
+pub struct StartedTodo {
+    pub id: Uuid,
+    pub title: String,
+    pub notify_on_start: bool,
+    pub user_id: Uuid,
+    pub username: String,
+    pub email: String,
+}
+
+impl StartedTodo {
+    pub fn notification(&self) -> Notification {
+        Notification {
+            user_id: self.user_id,
+            username: self.username.clone(),
+            email: self.email.clone(),
+            todo_id: Some(self.id),
+            subject: format!("Back on your list: {}", self.title),
+            body: format!("\"{}\" is no longer snoozed", self.title),
+        }
+    }
+}
+
+/// The time of day snoozes to a later day end at.
+fn morning() -> NaiveTime {
+    NaiveTime::from_hms_opt(9, 0, 0).expect("valid time")
+}
+
+/// When a todo snoozed at `now` until `until` starts, in the timezone of `now`. `start_at` is
+/// the time of a custom snooze.
+pub fn snooze_start(
+    until: SnoozeUntil,
+    start_at: Option<DateTime<FixedOffset>>,
+    now: DateTime<Tz>,
+) -> Result<DateTime<Utc>> {
+    let morning = |days: i64| {
+        let local = (now.date_naive() + Duration::days(days)).and_time(morning());
+        resolve_local(&now.timezone(), local)
+    };
+
+    let start = match until {
+        SnoozeUntil::LaterToday => now.to_utc() + Duration::hours(3),
+        SnoozeUntil::Tomorrow => morning(1),
+        SnoozeUntil::NextWeek => morning(7 - i64::from(now.weekday().num_days_from_monday())),
+        SnoozeUntil::Custom => start_at
+            .ok_or_else(|| Error::BadRequest("A custom snooze needs a `startAt`".into()))?
+            .to_utc(),
+    };
+
+    if start <= now.to_utc() {
+        return Err(Error::BadRequest("A todo can only be snoozed until later".into()).into());
+    }
+
+    Ok(start)
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Completion {
+    pub todo: Todo,
+    /// The occurrence generated by completing a recurring todo.
+    pub next: Option<Todo>,
+}
+
+/// The most todos a single bulk request may touch.
+pub const BULK_LIMIT: usize = 200;
+
+/// An action applied to every todo of a bulk request.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(
+    tag = "action",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum BulkAction {
+    Complete,
+    /// Move to the end of a list, `null` being the Inbox.
+    Move {
+        list_id: Option<Uuid>,
+    },
+    AddTag {
+        tag: String,
+    },
+    RemoveTag {
+        tag: String,
+    },
+    /// Set or, with `null`, clear the due date.
+    SetDue {
+        due_at: Option<DateTime<FixedOffset>>,
+    },
+    /// Move to the trash.
+    Delete,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkTodos {
+    pub ids: Vec<Uuid>,
+    #[serde(flatten)]
+    pub action: BulkAction,
+    /// Apply the action to all of the todos or to none of them.
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum BulkStatus {
+    Applied,
+    Failed,
+    /// The action succeeded but was undone because another todo of an atomic request failed.
+    RolledBack,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BulkResult {
+    pub id: Uuid,
+    pub status: BulkStatus,
+    pub error: Option<String>,
+    /// The todo after the action, unless it was deleted.
+    pub todo: Option<Todo>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BulkOutcome {
+    /// Whether the changes were saved. Only an atomic request with a failure is not.
+    pub committed: bool,
+    pub results: Vec<BulkResult>,
+}
+
+impl Todo {
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn create(db: &PgPool, user_id: Uuid, dto: &CreateTodo<'_>) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let todo = Self::insert(&mut txn, user_id, dto).await?;
+
+        txn.commit().await?;
+
+        Ok(todo)
+    }
+
+    /// Add a todo at the end of its list, as part of a larger change.
+    pub(crate) async fn insert(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        dto: &CreateTodo<'_>,
+    ) -> Result<Self> {
+        validate_estimate(dto.estimate_minutes)?;
+
+        let (series_id, due_at) = match &dto.recurrence {
+            Some(recurrence) => {
+                let dtstart = recurrence
+                    .dtstart
+                    .or(dto.due_at)
+                    .ok_or_else(|| Error::BadRequest("A recurring todo needs a due date".into()))?;
+                let series = Recurrence::create(&mut *conn, user_id, recurrence, dtstart).await?;
+
+                (Some(series.id), Some(dto.due_at.unwrap_or(dtstart)))
+            }
+            None => (None, dto.due_at),
+        };
+
+        if let Some(list_id) = dto.list_id {
+            List::find_by_id(&mut *conn, user_id, list_id).await?;
+        }
+        let position = Self::next_position(&mut *conn, user_id, dto.list_id).await?;
+        let fields = CustomField::merge(&mut *conn, dto.list_id, &Map::new(), &dto.fields).await?;
+
+        let todo = sqlx::query_as::<_, Self>(&format!(
+            "INSERT INTO todos \
+             (user_id, title, notes, due_at, series_id, list_id, position, tags, priority, \
+             estimate_minutes, start_at, fields) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) RETURNING {COLUMNS}"
+        ))
+        .bind(user_id)
+        .bind(&dto.title)
+        .bind(&dto.notes)
+        .bind(due_at)
+        .bind(series_id)
+        .bind(dto.list_id)
+        .bind(position)
+        .bind(normalize_tags(&dto.tags)?)
+        .bind(dto.priority)
+        .bind(dto.estimate_minutes)
+        .bind(dto.start_at)
+        .bind(Json(fields))
+        .fetch_one(&mut *conn)
+        .await?;
+
+        if todo.notes.is_some() {
+            notes::save(&mut *conn, todo.id).await?;
+            return Self::find_by_id(&mut *conn, user_id, todo.id).await;
+        }
+
+        Ok(todo)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_id<'e, E>(db: E, user_id: Uuid, id: Uuid) -> Result<Self>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let todo = sqlx::query_as::<_, Self>(&format!(
+            "SELECT {COLUMNS} FROM todos WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+
+        todo.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// All todos of a user, or only those of one list when `list` is given (`Some(None)` being
+    /// the Inbox), in their manual order. `filter` narrows them down further.
+    #[tracing::instrument(skip(db))]
+    pub async fn find_all<'e, E>(
+        db: E,
+        user_id: Uuid,
+        list: Option<Option<Uuid>>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Self>>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let filter = filter.map(|filter| filter.compile(4, Utc::now()));
+        let condition = filter.as_ref().map_or("true", |filter| &filter.sql);
+
+        let sql = format!(
+            "SELECT {COLUMNS} FROM todos WHERE user_id = $1 AND deleted_at IS NULL \
+             AND ($2 = false OR list_id IS NOT DISTINCT FROM $3) AND {condition} \
+             ORDER BY position, id"
+        );
+
+        let query = sqlx::query_as::<_, Self>(&sql)
+            .bind(user_id)
+            .bind(list.is_some())
+            .bind(list.flatten());
+
+        let todos = match filter {
+            Some(filter) => filter.bind(query),
+            None => query,
+        }
+        .fetch_all(db)
+        .await?;
+
+        Ok(todos)
+    }
+
+    /// A page of the todos of [`Todo::find_all`], in the same order.
+    #[tracing::instrument(skip(db, filter))]
+    pub async fn paginate(
+        db: &PgPool,
+        user_id: Uuid,
+        list: Option<Option<Uuid>>,
+        filter: Option<&Filter>,
+        pagination: &Pagination,
+    ) -> Result<CursorPage<Self>> {
+        let filter = filter.map(|filter| filter.compile(4, Utc::now()));
+        let condition = filter.as_ref().map_or("true", |filter| &filter.sql);
+        let values = filter.as_ref().map_or(0, |filter| filter.values.len());
+        let clause = pagination.clause(&KEYSET, 4 + values)?;
+
+        let sql = format!(
+            "SELECT {COLUMNS} FROM todos WHERE user_id = $1 AND deleted_at IS NULL \
+             AND ($2 = false OR list_id IS NOT DISTINCT FROM $3) AND {condition}{clause}"
+        );
+
+        let query = sqlx::query_as::<_, Self>(&sql)
+            .bind(user_id)
+            .bind(list.is_some())
+            .bind(list.flatten());
+
+        let query = match filter {
+            Some(filter) => filter.bind(query),
+            None => query,
+        };
+        let todos = pagination.bind(query).fetch_all(db).await?;
+
+        Ok(pagination.page(&KEYSET, todos, |todo| (todo.position.clone(), todo.id)))
+    }
+
+    /// Make appends to and moves within a list, or the Inbox when `list_id` is `None`, wait for
+    /// each other until the end of the transaction, so that they neither pick the same key nor
+    /// race a rebalance.
+    async fn lock_siblings(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        list_id: Option<Uuid>,
+    ) -> Result<()> {
+        match list_id {
+            Some(list_id) => {
+                sqlx::query("SELECT 1 FROM lists WHERE id = $1 AND user_id = $2 FOR NO KEY UPDATE")
+                    .bind(list_id)
+                    .bind(user_id)
+                    .execute(conn)
+                    .await?;
+            }
+            // The Inbox has no row of its own, so the user stands in for it
+            None => {
+                sqlx::query("SELECT 1 FROM users WHERE id = $1 FOR NO KEY UPDATE")
+                    .bind(user_id)
+                    .execute(conn)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The position key of a todo appended to a list, or the Inbox when `list_id` is `None`. The
+    /// todos there are spread out again when the key would grow too long.
+    pub(crate) async fn next_position(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        list_id: Option<Uuid>,
+    ) -> Result<String> {
+        Self::lock_siblings(&mut *conn, user_id, list_id).await?;
+
+        let last: Option<String> = sqlx::query_scalar(
+            "SELECT position FROM todos WHERE user_id = $1 AND list_id IS NOT DISTINCT FROM $2 \
+             AND deleted_at IS NULL ORDER BY position DESC LIMIT 1",
+        )
+        .bind(user_id)
+        .bind(list_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        if let Some(key) = position::append(last.as_deref()) {
+            return Ok(key);
+        }
+
+        let ids: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM todos WHERE user_id = $1 AND list_id IS NOT DISTINCT FROM $2 \
+             AND deleted_at IS NULL ORDER BY position, id",
+        )
+        .bind(user_id)
+        .bind(list_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        position::rebalance(conn, "todos", &ids).await
+    }
+
+    /// Update a todo, provided it is still at the `expected` version when one is given.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn update(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        dto: &UpdateTodo<'_>,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        let tags = dto.tags.as_deref().map(normalize_tags).transpose()?;
+        validate_estimate(dto.estimate_minutes.flatten())?;
+
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        if let Some(due_at) = dto.due_at {
+            validate_due(current.series_id, due_at)?;
+        }
+
+        let fields = match &dto.fields {
+            Some(changes) => {
+                Some(CustomField::merge(&mut txn, current.list_id, &current.fields, changes).await?)
+            }
+            None => None,
+        };
+
+        let todo = sqlx::query_as::<_, Self>(&format!(
+            "UPDATE todos SET title = COALESCE($3, title), \
+             notes = CASE WHEN $4 THEN $5 ELSE notes END, \
+             notes_html = CASE WHEN $4 AND $5::text IS NULL THEN NULL ELSE notes_html END, \
+             due_at = CASE WHEN $6 THEN $7 ELSE due_at END, tags = COALESCE($8, tags), \
+             priority = CASE WHEN $9 THEN $10 ELSE priority END, \
+             estimate_minutes = CASE WHEN $11 THEN $12 ELSE estimate_minutes END, \
+             start_at = CASE WHEN $13 THEN $14 ELSE start_at END, \
+             notify_on_start = notify_on_start AND NOT $13, fields = COALESCE($15, fields), \
+             updated_at = now() \
+             WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL RETURNING {COLUMNS}"
+        ))
+        .bind(id)
+        .bind(user_id)
+        .bind(&dto.title)
+        .bind(dto.notes.is_some())
+        .bind(dto.notes.clone().flatten())
+        .bind(dto.due_at.is_some())
+        .bind(dto.due_at.flatten())
+        .bind(tags)
+        .bind(dto.priority.is_some())
+        .bind(dto.priority.flatten())
+        .bind(dto.estimate_minutes.is_some())
+        .bind(dto.estimate_minutes.flatten())
+        .bind(dto.start_at.is_some())
+        .bind(dto.start_at.flatten())
+        .bind(fields.map(Json))
+        .fetch_optional(&mut *txn)
+        .await?
+        .ok_or(Error::EntityNotFound)?;
+
+        if let Some(due_at) = dto.due_at {
+            Reminder::reschedule(&mut *txn, todo.id, due_at).await?;
+        }
+
+        let todo = if let Some(Some(_)) = dto.notes {
+            notes::save(&mut txn, todo.id).await?;
+            Self::find_by_id(&mut *txn, user_id, todo.id).await?
+        } else {
+            todo
+        };
+
+        txn.commit().await?;
+
+        Ok(todo)
+    }
+
+    /// Move a todo to the trash, provided it is still at the `expected` version when one is
+    /// given.
+    #[tracing::instrument(skip(db))]
+    pub async fn delete(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        expected: Option<&[i32]>,
+    ) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        sqlx::query("UPDATE todos SET deleted_at = now() WHERE id = $1")
+            .bind(current.id)
+            .execute(&mut *txn)
+            .await?;
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Take a todo out of the trash. It goes back to the end of its list, or of the Inbox when
+    /// the list is in the trash itself.
+    #[tracing::instrument(skip(db))]
+    pub async fn restore(db: &PgPool, user_id: Uuid, id: Uuid) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let todo = sqlx::query_as::<_, Self>(&format!(
+            "SELECT {COLUMNS} FROM todos \
+             WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL FOR UPDATE"
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *txn)
+        .await?
+        .ok_or(Error::EntityNotFound)?;
+
+        let list_id: Option<Uuid> = match todo.list_id {
+            Some(list_id) => {
+                sqlx::query_scalar("SELECT id FROM lists WHERE id = $1 AND deleted_at IS NULL")
+                    .bind(list_id)
+                    .fetch_optional(&mut *txn)
+                    .await?
+            }
+            None => None,
+        };
+        let position = Self::next_position(&mut txn, user_id, list_id).await?;
+
+        let todo = sqlx::query_as::<_, Self>(&format!(
+            "UPDATE todos SET deleted_at = NULL, list_id = $2, position = $3, updated_at = now(), \
+             status_id = CASE WHEN list_id IS DISTINCT FROM $2 THEN NULL ELSE status_id END, \
+             fields = {} WHERE id = $1 RETURNING {COLUMNS}",
+            custom_fields::KEPT_ON_MOVE
+        ))
+        .bind(todo.id)
+        .bind(list_id)
+        .bind(position)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(todo)
+    }
+
+    /// Permanently delete a todo from the trash.
+    #[tracing::instrument(skip(db, storage))]
+    pub async fn purge(db: &PgPool, storage: &dyn Storage, user_id: Uuid, id: Uuid) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        let checksums = Attachment::checksums(&mut txn, &[id]).await?;
+
+        let result = sqlx::query(
+            "DELETE FROM todos WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL",
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *txn)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::EntityNotFound.into());
+        }
+
+        let orphans = Attachment::release(&mut txn, &checksums).await?;
+
+        txn.commit().await?;
+
+        Attachment::delete_orphans(db, storage, &orphans).await;
+
+        Ok(())
+    }
+
+    /// Lock a todo for the rest of the transaction.
+    pub(crate) async fn lock(conn: &mut PgConnection, user_id: Uuid, id: Uuid) -> Result<Self> {
+        let todo = sqlx::query_as::<_, Self>(&format!(
+            "SELECT {COLUMNS} FROM todos WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL \
+             FOR UPDATE"
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await?;
+
+        todo.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Mark a todo as completed. Completing an occurrence of a recurring todo generates the next
+    /// occurrence in the same transaction, unless the series has been stopped or has ended.
+    #[tracing::instrument(skip(db))]
+    pub async fn complete(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        scope: CompleteScope,
+    ) -> Result<Completion> {
+        let mut txn = db.begin().await?;
+
+        // Lock the row so that concurrent completions cannot generate the next occurrence twice
+        let todo = Self::lock(&mut txn, user_id, id).await?;
+        let completion = Self::complete_locked(&mut txn, todo, scope).await?;
+
+        txn.commit().await?;
+
+        Ok(completion)
+    }
+
+    /// Complete a todo that the caller's transaction holds a lock on. On a board, the todo is
+    /// moved to the done column of its list.
+    pub(crate) async fn complete_locked(
+        conn: &mut PgConnection,
+        todo: Self,
+        scope: CompleteScope,
+    ) -> Result<Completion> {
+        if todo.completed_at.is_some() {
+            return Ok(Completion { todo, next: None });
+        }
+
+        let todo = sqlx::query_as::<_, Self>(&format!(
+            "UPDATE todos SET completed_at = now(), updated_at = now(), \
+             status_id = COALESCE((SELECT c.id FROM board_columns c \
+             WHERE c.list_id = todos.list_id AND c.is_done), status_id) \
+             WHERE id = $1 RETURNING {COLUMNS}"
+        ))
+        .bind(todo.id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let mut next = None;
+
+        if let Some(series_id) = todo.series_id {
+            let series = Recurrence::find_by_id(&mut *conn, series_id).await?;
+
+            match scope {
+                CompleteScope::Series => Recurrence::stop(&mut *conn, series_id).await?,
+                CompleteScope::Occurrence if series.stopped_at.is_none() => {
+                    let after = todo.due_at.unwrap_or(series.dtstart);
+
+                    if let Some(due_at) = series.next_after(after)? {
+                        let position =
+                            Self::next_position(&mut *conn, todo.user_id, todo.list_id).await?;
+
+                        let occurrence = sqlx::query_as::<_, Self>(&format!(
+                            "INSERT INTO todos \
+                             (user_id, title, notes, due_at, series_id, list_id, position, \
+                             tags, priority, estimate_minutes, fields) \
+                             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) \
+                             RETURNING {COLUMNS}"
+                        ))
+                        .bind(todo.user_id)
+                        .bind(&todo.title)
+                        .bind(todo.notes.as_deref().map(notes::uncheck))
+                        .bind(due_at)
+                        .bind(series_id)
+                        .bind(todo.list_id)
+                        .bind(position)
+                        .bind(&todo.tags)
+                        .bind(todo.priority)
+                        .bind(todo.estimate_minutes)
+                        .bind(&todo.fields)
+                        .fetch_one(&mut *conn)
+                        .await?;
+
+                        Reminder::copy_relative(&mut *conn, todo.id, occurrence.id, due_at).await?;
+
+                        // The next occurrence starts over with the checklist unchecked
+                        sqlx::query(
+                            "INSERT INTO checklist_items (todo_id, text, checked, position) \
+                             SELECT $2, text, false, position FROM checklist_items \
+                             WHERE todo_id = $1 ORDER BY position, id",
+                        )
+                        .bind(todo.id)
+                        .bind(occurrence.id)
+                        .execute(&mut *conn)
+                        .await?;
+
+                        let occurrence = match occurrence.notes {
+                            Some(_) => {
+                                notes::save(&mut *conn, occurrence.id).await?;
+                                Self::find_by_id(&mut *conn, todo.user_id, occurrence.id).await?
+                            }
+                            None => occurrence,
+                        };
+
+                        next = Some(occurrence);
+                    }
+                }
+                CompleteScope::Occurrence => (),
+            }
+        }
+
+        Ok(Completion { todo, next })
+    }
+
+    /// Stop a recurring todo from generating further occurrences, provided it is still at the
+    /// `expected` version when one is given. The todo itself stays open.
+    #[tracing::instrument(skip(db))]
+    pub async fn stop_recurrence(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let todo = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&todo, todo.version, expected)?;
+
+        let series_id = todo
+            .series_id
+            .ok_or_else(|| Error::BadRequest("Todo is not recurring".into()))?;
+
+        Recurrence::stop(&mut *txn, series_id).await?;
+
+        txn.commit().await?;
+
+        Ok(todo)
+    }
+
+    /// Leave a todo out of active views until later, in the timezone of the user, provided it is
+    /// still at the `expected` version when one is given.
+    #[tracing::instrument(skip(db))]
+    pub async fn snooze(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        dto: &SnoozeTodo,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        let settings = Settings::find(db, user_id).await?;
+        let tz = parse_timezone(&settings.timezone)?;
+        let start_at = snooze_start(dto.until, dto.start_at, Utc::now().with_timezone(&tz))?;
+
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        let todo = sqlx::query_as::<_, Self>(&format!(
+            "UPDATE todos SET start_at = $2, notify_on_start = $3, updated_at = now() \
+             WHERE id = $1 RETURNING {COLUMNS}"
+        ))
+        .bind(current.id)
+        .bind(start_at)
+        .bind(dto.notify)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(todo)
+    }
+
+    /// Bring a snoozed todo back into active views right away, provided it is still at the
+    /// `expected` version when one is given.
+    #[tracing::instrument(skip(db))]
+    pub async fn unsnooze(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        let todo = sqlx::query_as::<_, Self>(&format!(
+            "UPDATE todos SET start_at = NULL, notify_on_start = false, updated_at = now() \
+             WHERE id = $1 RETURNING {COLUMNS}"
+        ))
+        .bind(current.id)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(todo)
+    }
+
+    /// Lock a batch of snoozed todos whose start has come.
+    pub async fn claim_started(conn: &mut PgConnection, limit: i64) -> Result<Vec<StartedTodo>> {
+        let todos = sqlx::query_as::<_, StartedTodo>(
+            "SELECT t.id, t.title, t.notify_on_start, u.id AS user_id, u.username, u.email \
+             FROM todos t JOIN users u ON u.id = t.user_id \
+             WHERE t.start_at <= now() AND t.deleted_at IS NULL \
+             ORDER BY t.start_at \
+             LIMIT $1 \
+             FOR UPDATE OF t SKIP LOCKED",
+        )
+        .bind(limit)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(todos)
+    }
+
+    /// Clear the snooze of started todos, so that they no longer count as snoozed.
+    pub async fn mark_started(conn: &mut PgConnection, ids: &[Uuid]) -> Result<()> {
+        // Starting is no change of the user's, there is nothing for them to undo
+        sqlx::query("SELECT set_config('todos.undoing', 'on', true)")
+            .execute(&mut *conn)
+            .await?;
+
+        sqlx::query(
+            "UPDATE todos SET start_at = NULL, notify_on_start = false, updated_at = now() \
+             WHERE id = ANY($1)",
+        )
+        .bind(ids)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Move a todo within its list or into another one. Normally only the moved todo is written.
+    #[tracing::instrument(skip(db))]
+    pub async fn move_to(db: &PgPool, user_id: Uuid, id: Uuid, dto: &MoveTodo) -> Result<Self> {
+        let mut txn = db.begin().await?;
+
+        let todo = Self::lock(&mut txn, user_id, id).await?;
+
+        let list_id = dto.list_id.unwrap_or(todo.list_id);
+        if let Some(list_id) = list_id {
+            List::find_by_id(&mut *txn, user_id, list_id).await?;
+        }
+        Self::lock_siblings(&mut txn, user_id, list_id).await?;
+
+        let siblings: Vec<(Uuid, String)> = sqlx::query_as(
+            "SELECT id, position FROM todos \
+             WHERE user_id = $1 AND list_id IS NOT DISTINCT FROM $2 AND id <> $3 \
+             AND deleted_at IS NULL ORDER BY position, id",
+        )
+        .bind(user_id)
+        .bind(list_id)
+        .bind(id)
+        .fetch_all(&mut *txn)
+        .await?;
+
+        let key = match position::place(&siblings, id, dto.after, dto.before)? {
+            Placement::Single(key) => key,
+            Placement::Rebalance(keys) => {
+                let key = keys
+                    .iter()
+                    .find(|(sibling, _)| *sibling == id)
+                    .map(|(_, key)| key.clone())
+                    .unwrap_or_default();
+                let (ids, keys): (Vec<Uuid>, Vec<String>) = keys.into_iter().unzip();
+
+                sqlx::query(
+                    "UPDATE todos SET position = v.position \
+                     FROM UNNEST($1::uuid[], $2::text[]) AS v (id, position) \
+                     WHERE todos.id = v.id",
+                )
+                .bind(ids)
+                .bind(keys)
+                .execute(&mut *txn)
+                .await?;
+
+                key
+            }
+        };
+
+        let todo = sqlx::query_as::<_, Self>(&format!(
+            "UPDATE todos SET list_id = $2, position = $3, updated_at = now(), \
+             status_id = CASE WHEN list_id IS DISTINCT FROM $2 THEN NULL ELSE status_id END, \
+             fields = {} WHERE id = $1 RETURNING {COLUMNS}",
+            custom_fields::KEPT_ON_MOVE
+        ))
+        .bind(id)
+        .bind(list_id)
+        .bind(key)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(todo)
+    }
+
+    /// Apply one action to many todos in a single transaction. Each todo gets a savepoint of its
+    /// own, so that a todo that cannot be changed, for example because it does not exist, fails
+    /// alone; in an atomic request it rolls the whole transaction back instead.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn bulk(db: &PgPool, user_id: Uuid, dto: &BulkTodos) -> Result<BulkOutcome> {
+        let mut ids = dto.ids.clone();
+        let mut seen = std::collections::HashSet::new();
+        ids.retain(|id| seen.insert(*id));
+
+        if ids.is_empty() {
+            return Err(Error::BadRequest("No todos given".into()).into());
+        }
+        if ids.len() > BULK_LIMIT {
+            return Err(Error::BadRequest(format!("At most {BULK_LIMIT} todos at a time")).into());
+        }
+
+        let action = match &dto.action {
+            BulkAction::AddTag { tag } => BulkAction::AddTag {
+                tag: normalize_tag(tag)?,
+            },
+            BulkAction::RemoveTag { tag } => BulkAction::RemoveTag {
+                tag: normalize_tag(tag)?,
+            },
+            action => action.clone(),
+        };
+
+        let mut txn = db.begin().await?;
+
+        if let BulkAction::Move {
+            list_id: Some(list_id),
+        } = action
+        {
+            List::find_by_id(&mut *txn, user_id, list_id).await?;
+        }
+
+        let mut results = Vec::with_capacity(ids.len());
+
+        for id in ids {
+            let mut savepoint = txn.begin().await?;
+
+            match Self::apply(&mut savepoint, user_id, id, &action).await {
+                Ok(todo) => {
+                    savepoint.commit().await?;
+                    results.push(BulkResult {
+                        id,
+                        status: BulkStatus::Applied,
+                        error: None,
+                        todo,
+                    });
+                }
+                Err(report) => {
+                    // Anything but a problem with this one todo fails the whole request
+                    let error = match report.downcast_ref::<Error>() {
+                        Some(
+                            error @ (Error::EntityNotFound
+                            | Error::BadRequest(_)
+                            | Error::Conflict(_)),
+                        ) => error.to_string(),
+                        _ => return Err(report),
+                    };
+                    savepoint.rollback().await?;
+                    results.push(BulkResult {
+                        id,
+                        status: BulkStatus::Failed,
+                        error: Some(error),
+                        todo: None,
+                    });
+                }
+            }
+        }
+
+        let failed = results.iter().any(|r| r.status == BulkStatus::Failed);
+
+        if dto.atomic && failed {
+            txn.rollback().await?;
+
+            for result in &mut results {
+                if result.status == BulkStatus::Applied {
+                    result.status = BulkStatus::RolledBack;
+                    result.todo = None;
+                }
+            }
+
+            return Ok(BulkOutcome {
+                committed: false,
+                results,
+            });
+        }
+
+        txn.commit().await?;
+
+        Ok(BulkOutcome {
+            committed: true,
+            results,
+        })
+    }
+
+    /// Apply a bulk action to one todo. Returns the todo as changed, or `None` once deleted.
+    async fn apply(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        id: Uuid,
+        action: &BulkAction,
+    ) -> Result<Option<Self>> {
+        let todo = Self::lock(&mut *conn, user_id, id).await?;
+
+        let todo = match action {
+            BulkAction::Complete => {
+                Self::complete_locked(&mut *conn, todo, CompleteScope::Occurrence)
+                    .await?
+                    .todo
+            }
+            BulkAction::Move { list_id } if todo.list_id == *list_id => todo,
+            BulkAction::Move { list_id } => {
+                let position = Self::next_position(&mut *conn, user_id, *list_id).await?;
+
+                sqlx::query_as::<_, Self>(&format!(
+                    "UPDATE todos SET list_id = $2, position = $3, status_id = NULL, \
+                     fields = {}, updated_at = now() WHERE id = $1 RETURNING {COLUMNS}",
+                    custom_fields::KEPT_ON_MOVE
+                ))
+                .bind(todo.id)
+                .bind(list_id)
+                .bind(position)
+                .fetch_one(&mut *conn)
+                .await?
+            }
+            BulkAction::AddTag { tag } => {
+                sqlx::query_as::<_, Self>(&format!(
+                    "UPDATE todos SET updated_at = now(), tags = CASE WHEN $2 = ANY(tags) \
+                     THEN tags ELSE array_append(tags, $2) END WHERE id = $1 RETURNING {COLUMNS}"
+                ))
+                .bind(todo.id)
+                .bind(tag)
+                .fetch_one(&mut *conn)
+                .await?
+            }
+            BulkAction::RemoveTag { tag } => {
+                sqlx::query_as::<_, Self>(&format!(
+                    "UPDATE todos SET tags = array_remove(tags, $2), updated_at = now() \
+                     WHERE id = $1 RETURNING {COLUMNS}"
+                ))
+                .bind(todo.id)
+                .bind(tag)
+                .fetch_one(&mut *conn)
+                .await?
+            }
+            BulkAction::SetDue { due_at } => {
+                validate_due(todo.series_id, *due_at)?;
+
+                let todo = sqlx::query_as::<_, Self>(&format!(
+                    "UPDATE todos SET due_at = $2, updated_at = now() WHERE id = $1 \
+                     RETURNING {COLUMNS}"
+                ))
+                .bind(todo.id)
+                .bind(due_at)
+                .fetch_one(&mut *conn)
+                .await?;
+
+                Reminder::reschedule(&mut *conn, todo.id, *due_at).await?;
+
+                todo
+            }
+            BulkAction::Delete => {
+                sqlx::query("UPDATE todos SET deleted_at = now() WHERE id = $1")
+                    .bind(todo.id)
+                    .execute(&mut *conn)
+                    .await?;
+                return Ok(None);
+            }
+        };
+
+        Ok(Some(todo))
+    }
+}
+
+/// Check that a todo of the recurring series `series_id`, if any, may be given `due_at`. The
+/// occurrences of a series are generated from their due dates, so they cannot lose them.
+pub fn validate_due(series_id: Option<Uuid>, due_at: Option<DateTime<FixedOffset>>) -> Result<()> {
+    if series_id.is_some() && due_at.is_none() {
+        return Err(Error::BadRequest("A recurring todo needs a due date".into()).into());
+    }
+
+    Ok(())
+}
+
+fn validate_estimate(estimate_minutes: Option<i32>) -> Result<()> {
+    if estimate_minutes.is_some_and(|minutes| minutes < 0) {
+        return Err(Error::BadRequest("An estimate cannot be negative".into()).into());
+    }
+
+    Ok(())
+}
+
+/// Normalise a tag: trimmed, lowercase and without a leading `#`.
+pub fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim();
+    let tag = tag.strip_prefix('#').unwrap_or(tag).to_lowercase();
+
+    if tag.is_empty() || tag.chars().any(char::is_whitespace) {
+        return Err(Error::BadRequest(format!("Invalid tag `{tag}`")).into());
+    }
+
+    Ok(tag)
+}
+
+/// Normalise a set of tags, dropping duplicates.
+pub fn normalize_tags<T: AsRef<str>>(tags: &[T]) -> Result<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+
+    for tag in tags {
+        let tag = normalize_tag(tag.as_ref())?;
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+
+    Ok(normalized)
+}
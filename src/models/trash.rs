@@ -0,0 +1,110 @@
+use chrono::Duration;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{error::Result, storage::Storage};
+
+use super::{
+    attachments::Attachment,
+    lists::List,
+    todos::{self, Todo},
+};
+
+/// The deleted todos and lists of a user, most recently deleted first.
+#[derive(Debug, Serialize, Clone)]
+pub struct Trash {
+    pub todos: Vec<Todo>,
+    pub lists: Vec<List>,
+}
+
+impl Trash {
+    #[tracing::instrument(skip(db))]
+    pub async fn find(db: &PgPool, user_id: Uuid) -> Result<Self> {
+        let todos = sqlx::query_as::<_, Todo>(&format!(
+            "SELECT {} FROM todos WHERE user_id = $1 AND deleted_at IS NOT NULL \
+             ORDER BY deleted_at DESC, id",
+            todos::COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        let lists = sqlx::query_as::<_, List>(
+            "SELECT * FROM lists WHERE user_id = $1 AND deleted_at IS NOT NULL \
+             ORDER BY deleted_at DESC, id",
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(Self { todos, lists })
+    }
+
+    /// Permanently delete everything that has been in the trash for longer than `retention`,
+    /// returning the number of todos and lists removed. Each batch of at most `batch_size` todos
+    /// or lists is deleted in a transaction of its own, and their attachment contents once it has
+    /// been committed.
+    #[tracing::instrument(skip(db, storage))]
+    pub async fn purge_expired(
+        db: &PgPool,
+        storage: &dyn Storage,
+        retention: Duration,
+        batch_size: i64,
+    ) -> Result<u64> {
+        let cutoff = chrono::Utc::now() - retention;
+        let mut purged = 0;
+
+        // The todos of expired lists first, so that their attachments are released
+        loop {
+            let mut txn = db.begin().await?;
+
+            let todo_ids: Vec<Uuid> = sqlx::query_scalar(
+                "SELECT id FROM todos WHERE deleted_at < $1 \
+                 OR list_id IN (SELECT id FROM lists WHERE deleted_at < $1) \
+                 LIMIT $2 FOR UPDATE SKIP LOCKED",
+            )
+            .bind(cutoff)
+            .bind(batch_size)
+            .fetch_all(&mut *txn)
+            .await?;
+            if todo_ids.is_empty() {
+                break;
+            }
+            let checksums = Attachment::checksums(&mut txn, &todo_ids).await?;
+
+            purged += sqlx::query("DELETE FROM todos WHERE id = ANY($1)")
+                .bind(&todo_ids)
+                .execute(&mut *txn)
+                .await?
+                .rows_affected();
+
+            let orphans = Attachment::release(&mut txn, &checksums).await?;
+
+            txn.commit().await?;
+
+            Attachment::delete_orphans(db, storage, &orphans).await;
+        }
+
+        // Lists that still have todos, locked by someone else, are left for the next purge
+        loop {
+            let lists = sqlx::query(
+                "DELETE FROM lists WHERE id IN (SELECT l.id FROM lists l WHERE l.deleted_at < $1 \
+                 AND NOT EXISTS (SELECT 1 FROM todos t WHERE t.list_id = l.id) \
+                 LIMIT $2 FOR UPDATE SKIP LOCKED)",
+            )
+            .bind(cutoff)
+            .bind(batch_size)
+            .execute(db)
+            .await?
+            .rows_affected();
+            if lists == 0 {
+                break;
+            }
+
+            purged += lists;
+        }
+
+        Ok(purged)
+    }
+}
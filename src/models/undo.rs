@@ -0,0 +1,262 @@
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    config::undo::UndoConfig,
+    error::{Error, Result},
+};
+
+use super::{lists::List, todos};
+
+/// The columns an undo or a redo sets back, by table.
+const TODO_COLUMNS: &str = "title, notes, notes_html, due_at, start_at, notify_on_start, \
+    completed_at, series_id, list_id, position, status_id, tags, priority, estimate_minutes, \
+    fields, deleted_at";
+const LIST_COLUMNS: &str = "name, position, deleted_at";
+
+/// A transaction that changed todos or lists of a user, recorded by the database. Every change to
+/// them is one, from creating a todo to moving or deleting a list.
+#[derive(Debug, Serialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Operation {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    #[serde(skip)]
+    pub txid: i64,
+    pub undone_at: Option<DateTime<FixedOffset>>,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+/// A row an operation changed.
+#[derive(Debug, FromRow)]
+struct Change {
+    table_name: String,
+    row_id: Uuid,
+    /// `None` for a row the operation created.
+    before: Option<Value>,
+    after: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Undo,
+    Redo,
+}
+
+/// An undone or redone operation, with the todos and lists it changed as they are now.
+#[derive(Debug, Serialize, Clone)]
+pub struct Reverted {
+    pub operation: Operation,
+    pub todos: Vec<todos::Todo>,
+    pub lists: Vec<List>,
+}
+
+fn columns(table: &str) -> Result<&'static str> {
+    match table {
+        "todos" => Ok(TODO_COLUMNS),
+        "lists" => Ok(LIST_COLUMNS),
+        _ => Err(Error::BadRequest(format!("Cannot undo changes to `{table}`")).into()),
+    }
+}
+
+impl Operation {
+    /// Revert the last operation of the user that is not undone yet.
+    #[tracing::instrument(skip(db))]
+    pub async fn undo(db: &PgPool, user_id: Uuid, config: &UndoConfig) -> Result<Reverted> {
+        Self::revert(db, user_id, config, Direction::Undo).await
+    }
+
+    /// Apply the last undone operation of the user again. Any change made since the undo clears
+    /// what can be redone.
+    #[tracing::instrument(skip(db))]
+    pub async fn redo(db: &PgPool, user_id: Uuid, config: &UndoConfig) -> Result<Reverted> {
+        Self::revert(db, user_id, config, Direction::Redo).await
+    }
+
+    async fn revert(
+        db: &PgPool,
+        user_id: Uuid,
+        config: &UndoConfig,
+        direction: Direction,
+    ) -> Result<Reverted> {
+        let mut txn = db.begin().await?;
+
+        // Keeps the changes below from being recorded as an operation of their own
+        sqlx::query("SELECT set_config('todos.undoing', 'on', true)")
+            .execute(&mut *txn)
+            .await?;
+
+        Self::prune(&mut txn, user_id, config).await?;
+
+        let sql = match direction {
+            Direction::Undo => {
+                "SELECT * FROM undo_operations WHERE user_id = $1 AND undone_at IS NULL \
+                 ORDER BY created_at DESC, txid DESC LIMIT 1 FOR UPDATE"
+            }
+            Direction::Redo => {
+                "SELECT * FROM undo_operations WHERE user_id = $1 AND undone_at IS NOT NULL \
+                 ORDER BY undone_at DESC LIMIT 1 FOR UPDATE"
+            }
+        };
+        let operation = sqlx::query_as::<_, Self>(sql)
+            .bind(user_id)
+            .fetch_optional(&mut *txn)
+            .await?
+            .ok_or(Error::EntityNotFound)?;
+
+        let changes = sqlx::query_as::<_, Change>(
+            "SELECT table_name, row_id, before, after FROM undo_changes \
+             WHERE operation_id = $1 ORDER BY table_name, row_id",
+        )
+        .bind(operation.id)
+        .fetch_all(&mut *txn)
+        .await?;
+
+        // Every row must still be as the operation, or the last undo of it, left it
+        for change in &changes {
+            let expected = match direction {
+                Direction::Undo => Some(&change.after),
+                Direction::Redo => change.before.as_ref(),
+            };
+            let table = &change.table_name;
+            columns(table)?;
+
+            let unchanged: Option<bool> = sqlx::query_scalar(&format!(
+                "SELECT (to_jsonb(t) - 'updated_at' - 'version') \
+                 = ($2::jsonb - 'updated_at' - 'version') \
+                 FROM {table} t WHERE id = $1 FOR UPDATE"
+            ))
+            .bind(change.row_id)
+            .bind(expected)
+            .fetch_optional(&mut *txn)
+            .await?
+            .flatten();
+
+            if unchanged != Some(true) {
+                let kind = if table == "lists" { "list" } else { "todo" };
+                return Err(Error::Conflict(format!(
+                    "The {kind} `{}` has changed since",
+                    change.row_id
+                ))
+                .into());
+            }
+        }
+
+        for change in &changes {
+            let target = match direction {
+                Direction::Undo => change.before.as_ref(),
+                Direction::Redo => Some(&change.after),
+            };
+            let table = &change.table_name;
+            let columns = columns(table)?;
+
+            match target {
+                Some(target) => {
+                    sqlx::query(&format!(
+                        "UPDATE {table} SET ({columns}) = \
+                         (SELECT {columns} FROM jsonb_populate_record(NULL::{table}, $2)), \
+                         updated_at = now() WHERE id = $1"
+                    ))
+                    .bind(change.row_id)
+                    .bind(target)
+                    .execute(&mut *txn)
+                    .await?;
+                }
+                // Undoing the creation of a row moves it to the trash
+                None => {
+                    sqlx::query(&format!(
+                        "UPDATE {table} SET deleted_at = now(), updated_at = now() WHERE id = $1"
+                    ))
+                    .bind(change.row_id)
+                    .execute(&mut *txn)
+                    .await?;
+                }
+            }
+
+            // What the next undo or redo expects to find
+            let column = match direction {
+                Direction::Undo => "before",
+                Direction::Redo => "after",
+            };
+            sqlx::query(&format!(
+                "UPDATE undo_changes SET {column} = \
+                 (SELECT to_jsonb(t) FROM {table} t WHERE id = $3) \
+                 WHERE operation_id = $1 AND table_name = $2 AND row_id = $3"
+            ))
+            .bind(operation.id)
+            .bind(table)
+            .bind(change.row_id)
+            .execute(&mut *txn)
+            .await?;
+        }
+
+        let operation = sqlx::query_as::<_, Self>(
+            "UPDATE undo_operations SET undone_at = CASE WHEN $2 THEN now() END \
+             WHERE id = $1 RETURNING *",
+        )
+        .bind(operation.id)
+        .bind(direction == Direction::Undo)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        let ids = |table: &str| -> Vec<Uuid> {
+            changes
+                .iter()
+                .filter(|change| change.table_name == table)
+                .map(|change| change.row_id)
+                .collect()
+        };
+        let todos = sqlx::query_as::<_, todos::Todo>(&format!(
+            "SELECT {} FROM todos WHERE id = ANY($1) ORDER BY position, id",
+            todos::COLUMNS
+        ))
+        .bind(ids("todos"))
+        .fetch_all(&mut *txn)
+        .await?;
+        let lists = sqlx::query_as::<_, List>(
+            "SELECT * FROM lists WHERE id = ANY($1) ORDER BY position, id",
+        )
+        .bind(ids("lists"))
+        .fetch_all(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(Reverted {
+            operation,
+            todos,
+            lists,
+        })
+    }
+
+    /// Forget the operations of a user that are out of the window or beyond the depth.
+    async fn prune(conn: &mut PgConnection, user_id: Uuid, config: &UndoConfig) -> Result<()> {
+        sqlx::query(
+            "DELETE FROM undo_operations WHERE user_id = $1 AND (created_at < $2 \
+             OR id NOT IN (SELECT id FROM undo_operations WHERE user_id = $1 \
+             ORDER BY created_at DESC, txid DESC LIMIT $3))",
+        )
+        .bind(user_id)
+        .bind(Utc::now() - Duration::seconds(config.window))
+        .bind(config.depth)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete the operations of all users older than `window`. Returns how many were deleted.
+    #[tracing::instrument(skip(db))]
+    pub async fn purge_expired(db: &PgPool, window: Duration) -> Result<u64> {
+        let purged = sqlx::query("DELETE FROM undo_operations WHERE created_at < $1")
+            .bind(Utc::now() - window)
+            .execute(db)
+            .await?
+            .rows_affected();
+
+        Ok(purged)
+    }
+}
@@ -0,0 +1,225 @@
+//! The urgency of a todo ranks what to do next, the way Taskwarrior does it: each factor, such as
+//! how close the todo is to being due, is a value between 0 and 1 weighed by a coefficient the
+//! user may change. The score is the sum of the weighed factors.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    filter::Filter,
+};
+
+use super::{
+    pagination::{CursorPage, Keyset, Pagination},
+    settings::Settings,
+    todos::{Priority, Todo},
+};
+
+/// Todos this many days old or older count as fully aged.
+const MAX_AGE_DAYS: f64 = 365.0;
+
+/// The order of todos listed by urgency, the most urgent first.
+const KEYSET: Keyset = Keyset {
+    scope: "todos_urgency",
+    column: "urgency",
+    id: "todos.id",
+    kind: "float8",
+};
+
+/// The weights of the factors of the score. Those left out of a user's settings are the
+/// defaults of Taskwarrior.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct Coefficients {
+    /// Being due, from two weeks before until a week after.
+    pub due: f64,
+    /// Age, up to a year.
+    pub age: f64,
+    pub priority_high: f64,
+    pub priority_medium: f64,
+    pub priority_low: f64,
+    /// Having tags: more tags count a little more.
+    pub tags: f64,
+    /// Depending on open todos.
+    pub blocked: f64,
+    /// Extra weight of todos with one of these tags, like `next`.
+    pub tag: BTreeMap<String, f64>,
+}
+
+impl Default for Coefficients {
+    fn default() -> Self {
+        Self {
+            due: 12.0,
+            age: 2.0,
+            priority_high: 6.0,
+            priority_medium: 3.9,
+            priority_low: 1.8,
+            tags: 1.0,
+            blocked: -5.0,
+            tag: BTreeMap::new(),
+        }
+    }
+}
+
+/// A factor of a score.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Term {
+    /// `due`, `age`, `priority`, `tags`, `blocked` or `tag:<tag>`.
+    pub factor: String,
+    /// How much the factor applies, from 0 to 1.
+    pub value: f64,
+    pub coefficient: f64,
+    /// `value` times `coefficient`.
+    pub score: f64,
+}
+
+/// The score of a todo with the factors it is made of, leaving out those that do not apply.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Urgency {
+    pub score: f64,
+    pub terms: Vec<Term>,
+}
+
+/// A todo with its score, as todos are listed by urgency.
+#[derive(Debug, Serialize, Clone)]
+pub struct Ranked {
+    #[serde(flatten)]
+    pub todo: Todo,
+    pub urgency: f64,
+}
+
+/// The urgency of a todo at `now`. Completed todos have none.
+pub fn score(todo: &Todo, coefficients: &Coefficients, now: DateTime<Utc>) -> Urgency {
+    let mut terms = Vec::new();
+    let mut add = |factor: &str, value: f64, coefficient: f64| {
+        if value != 0.0 && coefficient != 0.0 {
+            terms.push(Term {
+                factor: factor.into(),
+                value,
+                coefficient,
+                score: value * coefficient,
+            });
+        }
+    };
+
+    if todo.completed_at.is_none() {
+        if let Some(due_at) = todo.due_at {
+            let overdue = (now - due_at.to_utc()).num_seconds() as f64 / 86_400.0;
+            let value = if overdue >= 7.0 {
+                1.0
+            } else if overdue >= -14.0 {
+                (overdue + 14.0) * 0.8 / 21.0 + 0.2
+            } else {
+                0.2
+            };
+            add("due", value, coefficients.due);
+        }
+
+        let age = (now - todo.created_at.to_utc()).num_seconds() as f64 / 86_400.0;
+        add(
+            "age",
+            (age / MAX_AGE_DAYS).clamp(0.0, 1.0),
+            coefficients.age,
+        );
+
+        if let Some(priority) = todo.priority {
+            let coefficient = match priority {
+                Priority::High => coefficients.priority_high,
+                Priority::Medium => coefficients.priority_medium,
+                Priority::Low => coefficients.priority_low,
+            };
+            add("priority", 1.0, coefficient);
+        }
+
+        let tags = match todo.tags.len() {
+            0 => 0.0,
+            1 => 0.8,
+            2 => 0.9,
+            _ => 1.0,
+        };
+        add("tags", tags, coefficients.tags);
+
+        for tag in &todo.tags {
+            if let Some(&coefficient) = coefficients.tag.get(tag) {
+                add(&format!("tag:{tag}"), 1.0, coefficient);
+            }
+        }
+
+        if todo.blocked {
+            add("blocked", 1.0, coefficients.blocked);
+        }
+    }
+
+    Urgency {
+        score: terms.iter().fold(0.0, |score, term| score + term.score),
+        terms,
+    }
+}
+
+impl Urgency {
+    /// The urgency of a todo of the user, with the user's coefficients.
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_todo(db: &PgPool, user_id: Uuid, todo_id: Uuid) -> Result<Self> {
+        let todo = Todo::find_by_id(db, user_id, todo_id).await?;
+        let settings = Settings::find(db, user_id).await?;
+
+        Ok(score(&todo, &settings.urgency, Utc::now()))
+    }
+}
+
+impl Ranked {
+    /// A page of the todos of [`Todo::find_all`], the most urgent first. Scores change over
+    /// time, so they are computed for all of the todos each time; a page starts after the score
+    /// its cursor was made with.
+    #[tracing::instrument(skip(db, filter))]
+    pub async fn paginate(
+        db: &PgPool,
+        user_id: Uuid,
+        list: Option<Option<Uuid>>,
+        filter: Option<&Filter>,
+        pagination: &Pagination,
+    ) -> Result<CursorPage<Self>> {
+        let after = pagination
+            .cursor
+            .as_ref()
+            .map(|cursor| cursor.key.parse::<f64>())
+            .transpose()
+            .map_err(|_| Error::BadRequest("Invalid cursor".into()))?;
+
+        let settings = Settings::find(db, user_id).await?;
+        let now = Utc::now();
+
+        let mut ranked: Vec<Self> = Todo::find_all(db, user_id, list, filter)
+            .await?
+            .into_iter()
+            .map(|todo| Self {
+                urgency: score(&todo, &settings.urgency, now).score,
+                todo,
+            })
+            .collect();
+        ranked.sort_by(|a, b| {
+            b.urgency
+                .total_cmp(&a.urgency)
+                .then(a.todo.id.cmp(&b.todo.id))
+        });
+
+        pagination.slice(
+            &KEYSET,
+            ranked,
+            |ranked| (ranked.urgency.to_string(), ranked.todo.id),
+            |ranked, cursor| {
+                after
+                    .unwrap_or_default()
+                    .total_cmp(&ranked.urgency)
+                    .then(ranked.todo.id.cmp(&cursor.id))
+            },
+        )
+    }
+}
@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 
 use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
 use chrono::{DateTime, FixedOffset};
@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgRow, Decode, Executor, FromRow, PgPool, Postgres, Row};
 use uuid::Uuid;
 
-use crate::error::{Error, Result};
+use crate::error::{AuthError, Error, Result};
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -31,6 +31,21 @@ impl<'a> RegisterUser<'a> {
     }
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LoginUser<'a> {
+    email: Cow<'a, str>,
+    password: Cow<'a, str>,
+}
+
+impl<'a> LoginUser<'a> {
+    pub fn new(email: &'a str, password: &'a str) -> Self {
+        Self {
+            email: Cow::Borrowed(email),
+            password: Cow::Borrowed(password),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FilteredUser {
     pub id: Uuid,
@@ -79,12 +94,12 @@ impl User {
             let email_exists: &str = row.try_get("email").unwrap_or_default();
             let username_taken: &str = row.try_get("username").unwrap_or_default();
 
-            if email_exists == &dto.email {
+            if email_exists == dto.email {
                 return Err(
                     Error::EntityAlreadyExists("User with email already exists".into()).into(),
                 );
             }
-            if username_taken == &dto.username {
+            if username_taken == dto.username {
                 return Err(Error::EntityAlreadyExists("Username already taken".into()).into());
             }
         }
@@ -103,6 +118,24 @@ impl User {
         Ok(user)
     }
 
+    #[tracing::instrument(skip_all)]
+    pub async fn login(db: &PgPool, dto: &LoginUser<'_>) -> Result<Self> {
+        // Do not reveal whether it was the e-mail or the password that was wrong
+        let user = sqlx::query_as::<_, Self>("SELECT * FROM users WHERE email = $1")
+            .bind(&dto.email)
+            .fetch_optional(db)
+            .await?
+            .ok_or(AuthError::WrongCredentials)?;
+
+        let hash = PasswordHash::new(&user.password).map_err(Error::from)?;
+
+        Argon2::default()
+            .verify_password(dto.password.as_bytes(), &hash)
+            .map_err(|_| AuthError::WrongCredentials)?;
+
+        Ok(user)
+    }
+
     pub fn hash_password(password: &str) -> Result<String> {
         let argon = Argon2::default();
         let salt = SaltString::generate(&mut OsRng);
@@ -0,0 +1,381 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{types::Json, Executor, FromRow, PgConnection, PgPool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    filter::Filter,
+};
+
+use super::{
+    custom_fields,
+    recurrences::parse_timezone,
+    settings::Settings,
+    todos::{self, Todo},
+    urgency,
+};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum Sort {
+    /// The manual order: the Inbox, then each list in order.
+    #[default]
+    Position,
+    Due,
+    Created,
+    Updated,
+    Title,
+    /// By the value of the custom field `sortField`. Todos without one come last.
+    Field,
+    /// The most urgent first, or last when descending.
+    Urgency,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum GroupBy {
+    List,
+    /// A todo with several tags is in the group of each of them.
+    Tag,
+    /// The day the todo is due, in the user's timezone.
+    Due,
+}
+
+/// A saved filter with how to present its todos, also known as a smart list.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedView {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// In the query language of [`Filter`], empty for all todos.
+    pub filter: String,
+    pub sort: Sort,
+    /// The key of the custom field a [`Sort::Field`] view sorts by.
+    pub sort_field: Option<String>,
+    pub descending: bool,
+    pub group_by: Option<GroupBy>,
+    /// Options for clients, kept as they are given.
+    pub display: Json<Value>,
+    pub version: i32,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateView<'a> {
+    pub name: Cow<'a, str>,
+    #[serde(default)]
+    pub filter: Cow<'a, str>,
+    #[serde(default)]
+    pub sort: Sort,
+    pub sort_field: Option<Cow<'a, str>>,
+    #[serde(default)]
+    pub descending: bool,
+    pub group_by: Option<GroupBy>,
+    pub display: Option<Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateView<'a> {
+    pub name: Option<Cow<'a, str>>,
+    pub filter: Option<Cow<'a, str>>,
+    pub sort: Option<Sort>,
+    #[serde(default, deserialize_with = "super::nullable")]
+    pub sort_field: Option<Option<Cow<'a, str>>>,
+    pub descending: Option<bool>,
+    /// `null` stops grouping.
+    #[serde(default, deserialize_with = "super::nullable")]
+    pub group_by: Option<Option<GroupBy>>,
+    pub display: Option<Value>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Group {
+    /// The list id, tag or day of the group; `None` for todos without one.
+    pub key: Option<String>,
+    pub todos: Vec<Todo>,
+}
+
+/// The todos of a view. Without grouping they are all in a single group.
+#[derive(Debug, Serialize, Clone)]
+pub struct ViewTodos {
+    pub view: SavedView,
+    pub groups: Vec<Group>,
+}
+
+impl Sort {
+    /// The ORDER BY of the sort, with the key of a sort field bound to `$2`.
+    fn order_by(self, descending: bool) -> String {
+        let direction = if descending { "DESC" } else { "ASC" };
+
+        match self {
+            Self::Position => format!(
+                "(SELECT l.position FROM lists l WHERE l.id = todos.list_id) {direction} \
+                 NULLS FIRST, todos.position {direction}, todos.id"
+            ),
+            Self::Due => format!("todos.due_at {direction} NULLS LAST, todos.id"),
+            Self::Created => format!("todos.created_at {direction}, todos.id"),
+            Self::Updated => format!("todos.updated_at {direction}, todos.id"),
+            Self::Title => format!("lower(todos.title) {direction}, todos.id"),
+            Self::Field => format!("todos.fields -> $2 {direction} NULLS LAST, todos.id"),
+            // Sorted once scored, see [`SavedView::todos`]
+            Self::Urgency => "todos.id".into(),
+        }
+    }
+}
+
+fn validate(name: Option<&str>, filter: Option<&str>, display: Option<&Value>) -> Result<()> {
+    if name.is_some_and(|name| name.trim().is_empty()) {
+        return Err(Error::BadRequest("A view needs a name".into()).into());
+    }
+    if let Some(filter) = filter.filter(|filter| !filter.trim().is_empty()) {
+        Filter::parse(filter).map_err(Error::from)?;
+    }
+    if display.is_some_and(|display| !display.is_object()) {
+        return Err(Error::BadRequest("Display options must be an object".into()).into());
+    }
+
+    Ok(())
+}
+
+/// The key of the field to sort by, which a [`Sort::Field`] view needs.
+fn sort_field(sort: Sort, field: Option<&str>) -> Result<Option<String>> {
+    let field = field.map(custom_fields::field_key).transpose()?;
+
+    if sort == Sort::Field && field.is_none() {
+        return Err(Error::BadRequest("Sorting by a field needs a `sortField`".into()).into());
+    }
+
+    Ok(field)
+}
+
+impl SavedView {
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn create(db: &PgPool, user_id: Uuid, dto: &CreateView<'_>) -> Result<Self> {
+        validate(Some(&dto.name), Some(&dto.filter), dto.display.as_ref())?;
+        let field = sort_field(dto.sort, dto.sort_field.as_deref())?;
+
+        let view = sqlx::query_as::<_, Self>(
+            "INSERT INTO saved_views \
+             (user_id, name, filter, sort, descending, group_by, display, sort_field) \
+             VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, '{}'), $8) RETURNING *",
+        )
+        .bind(user_id)
+        .bind(dto.name.trim())
+        .bind(dto.filter.trim())
+        .bind(dto.sort)
+        .bind(dto.descending)
+        .bind(dto.group_by)
+        .bind(dto.display.as_ref().map(Json))
+        .bind(field)
+        .fetch_one(db)
+        .await?;
+
+        Ok(view)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_all(db: &PgPool, user_id: Uuid) -> Result<Vec<Self>> {
+        let views = sqlx::query_as::<_, Self>(
+            "SELECT * FROM saved_views WHERE user_id = $1 ORDER BY lower(name), id",
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(views)
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_id<'e, E>(db: E, user_id: Uuid, id: Uuid) -> Result<Self>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let view =
+            sqlx::query_as::<_, Self>("SELECT * FROM saved_views WHERE user_id = $1 AND id = $2")
+                .bind(user_id)
+                .bind(id)
+                .fetch_optional(db)
+                .await?;
+
+        view.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Lock one of the user's views for the rest of the transaction.
+    async fn lock(conn: &mut PgConnection, user_id: Uuid, id: Uuid) -> Result<Self> {
+        let view = sqlx::query_as::<_, Self>(
+            "SELECT * FROM saved_views WHERE id = $1 AND user_id = $2 FOR UPDATE",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await?;
+
+        view.ok_or_else(|| Error::EntityNotFound.into())
+    }
+
+    /// Change a view, provided it is still at the `expected` version when one is given.
+    #[tracing::instrument(skip(db, dto))]
+    pub async fn update(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        dto: &UpdateView<'_>,
+        expected: Option<&[i32]>,
+    ) -> Result<Self> {
+        validate(
+            dto.name.as_deref(),
+            dto.filter.as_deref(),
+            dto.display.as_ref(),
+        )?;
+
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        let field = match &dto.sort_field {
+            Some(field) => field.as_deref(),
+            None => current.sort_field.as_deref(),
+        };
+        let field = sort_field(dto.sort.unwrap_or(current.sort), field)?;
+
+        let view = sqlx::query_as::<_, Self>(
+            "UPDATE saved_views SET name = COALESCE($2, name), filter = COALESCE($3, filter), \
+             sort = COALESCE($4, sort), descending = COALESCE($5, descending), \
+             group_by = CASE WHEN $6 THEN $7 ELSE group_by END, \
+             display = COALESCE($8, display), sort_field = $9, updated_at = now() \
+             WHERE id = $1 RETURNING *",
+        )
+        .bind(current.id)
+        .bind(dto.name.as_deref().map(str::trim))
+        .bind(dto.filter.as_deref().map(str::trim))
+        .bind(dto.sort)
+        .bind(dto.descending)
+        .bind(dto.group_by.is_some())
+        .bind(dto.group_by.flatten())
+        .bind(dto.display.as_ref().map(Json))
+        .bind(field)
+        .fetch_one(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(view)
+    }
+
+    /// Delete a view, provided it is still at the `expected` version when one is given.
+    #[tracing::instrument(skip(db))]
+    pub async fn delete(
+        db: &PgPool,
+        user_id: Uuid,
+        id: Uuid,
+        expected: Option<&[i32]>,
+    ) -> Result<()> {
+        let mut txn = db.begin().await?;
+
+        let current = Self::lock(&mut txn, user_id, id).await?;
+        super::check_version(&current, current.version, expected)?;
+
+        sqlx::query("DELETE FROM saved_views WHERE id = $1")
+            .bind(current.id)
+            .execute(&mut *txn)
+            .await?;
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Run a view on the user's todos. Snoozed todos are left out unless the filter asks for
+    /// them.
+    #[tracing::instrument(skip(db))]
+    pub async fn todos(db: &PgPool, user_id: Uuid, id: Uuid) -> Result<ViewTodos> {
+        let view = Self::find_by_id(db, user_id, id).await?;
+
+        let filter = match view.filter.trim() {
+            "" => None,
+            filter => Some(Filter::parse(filter).map_err(Error::from)?),
+        };
+        let filter = Filter::active(filter).compile(3, Utc::now());
+
+        let sql = format!(
+            "SELECT {} FROM todos WHERE user_id = $1 AND deleted_at IS NULL AND {} ORDER BY {}",
+            todos::COLUMNS,
+            filter.sql,
+            view.sort.order_by(view.descending)
+        );
+
+        let query = sqlx::query_as::<_, Todo>(&sql)
+            .bind(user_id)
+            .bind(&view.sort_field);
+        let mut todos = filter.bind(query).fetch_all(db).await?;
+
+        let settings = Settings::find(db, user_id).await?;
+
+        if view.sort == Sort::Urgency {
+            let now = Utc::now();
+
+            let mut ranked: Vec<(f64, Todo)> = todos
+                .into_iter()
+                .map(|todo| (urgency::score(&todo, &settings.urgency, now).score, todo))
+                .collect();
+            ranked.sort_by(|(a, _), (b, _)| match view.descending {
+                true => a.total_cmp(b),
+                false => b.total_cmp(a),
+            });
+            todos = ranked.into_iter().map(|(_, todo)| todo).collect();
+        }
+
+        let groups = group(todos, view.group_by, parse_timezone(&settings.timezone)?);
+
+        Ok(ViewTodos { view, groups })
+    }
+}
+
+/// Split sorted todos into groups, which come in the order of their first todo. Due dates are
+/// the days in `tz`.
+pub fn group(todos: Vec<Todo>, group_by: Option<GroupBy>, tz: Tz) -> Vec<Group> {
+    let Some(group_by) = group_by else {
+        return vec![Group { key: None, todos }];
+    };
+
+    let mut groups: Vec<Group> = Vec::new();
+    let mut add =
+        |key: Option<String>, todo: &Todo| match groups.iter_mut().find(|group| group.key == key) {
+            Some(group) => group.todos.push(todo.clone()),
+            None => groups.push(Group {
+                key,
+                todos: vec![todo.clone()],
+            }),
+        };
+
+    for todo in &todos {
+        match group_by {
+            GroupBy::List => add(todo.list_id.map(|id| id.to_string()), todo),
+            GroupBy::Tag if todo.tags.is_empty() => add(None, todo),
+            GroupBy::Tag => {
+                for tag in &todo.tags {
+                    add(Some(tag.clone()), todo);
+                }
+            }
+            GroupBy::Due => add(
+                todo.due_at
+                    .map(|due| due.with_timezone(&tz).date_naive().to_string()),
+                todo,
+            ),
+        }
+    }
+
+    groups
+}
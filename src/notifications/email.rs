@@ -0,0 +1,63 @@
+use async_trait::async_trait;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::{
+    config::notifications::EmailConfig,
+    error::{Error, Result},
+};
+
+use super::{Notification, Notifier};
+
+/// Sends notifications to the user's e-mail address over SMTP.
+pub struct EmailNotifier {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl EmailNotifier {
+    pub fn new(cfg: &EmailConfig) -> Result<Self> {
+        let from = cfg
+            .from
+            .parse::<Mailbox>()
+            .map_err(|e| Error::ConfigFile(format!("Invalid e-mail sender: {e}")))?;
+
+        let mut builder = if cfg.tls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&cfg.host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&cfg.host)
+        }
+        .port(cfg.port);
+
+        if let (Some(username), Some(password)) = (&cfg.username, &cfg.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            from,
+            transport: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn notify(&self, notification: &Notification) -> Result<()> {
+        let to = Mailbox::new(
+            Some(notification.username.clone()),
+            notification.email.parse()?,
+        );
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&notification.subject)
+            .body(notification.body.clone())?;
+
+        self.transport.send(message).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+
+use crate::error::Result;
+
+use super::{Notification, Notifier};
+
+/// Writes notifications to the application log. Always enabled.
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn notify(&self, notification: &Notification) -> Result<()> {
+        tracing::info!(
+            user_id = %notification.user_id,
+            todo_id = ?notification.todo_id,
+            subject = %notification.subject,
+            "{}",
+            notification.body
+        );
+
+        Ok(())
+    }
+}
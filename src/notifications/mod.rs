@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    config::notifications::NotificationsConfig,
+    error::{Error, Result},
+};
+
+pub mod email;
+pub mod log;
+pub mod webhook;
+
+/// A message for a single user, delivered through one of the notification channels.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    pub user_id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub todo_id: Option<Uuid>,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, notification: &Notification) -> Result<()>;
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum Channel {
+    #[default]
+    Log,
+    Email,
+    Webhook,
+}
+
+impl Channel {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Log => "log",
+            Self::Email => "email",
+            Self::Webhook => "webhook",
+        }
+    }
+}
+
+/// The configured notification channels.
+#[derive(Clone)]
+pub struct Notifiers {
+    log: Arc<dyn Notifier>,
+    email: Option<Arc<dyn Notifier>>,
+    webhook: Option<Arc<dyn Notifier>>,
+}
+
+impl Notifiers {
+    pub fn new(cfg: &NotificationsConfig) -> Result<Self> {
+        let email = match &cfg.email {
+            Some(email) => Some(Arc::new(email::EmailNotifier::new(email)?) as Arc<dyn Notifier>),
+            None => None,
+        };
+        let webhook = match &cfg.webhook {
+            Some(webhook) => {
+                Some(Arc::new(webhook::WebhookNotifier::new(webhook)?) as Arc<dyn Notifier>)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            log: Arc::new(log::LogNotifier),
+            email,
+            webhook,
+        })
+    }
+
+    /// The notifier for `channel`, failing when the channel has not been configured.
+    pub fn channel(&self, channel: Channel) -> Result<&dyn Notifier> {
+        let notifier = match channel {
+            Channel::Log => Some(self.log.as_ref()),
+            Channel::Email => self.email.as_deref(),
+            Channel::Webhook => self.webhook.as_deref(),
+        };
+
+        notifier.ok_or_else(|| {
+            Error::BadRequest(format!(
+                "Notification channel `{}` is not configured",
+                channel.as_str()
+            ))
+            .into()
+        })
+    }
+
+    /// The channel for notifications nobody picked a channel for: email when it is configured,
+    /// the log otherwise.
+    pub fn preferred(&self) -> Channel {
+        if self.email.is_some() {
+            Channel::Email
+        } else {
+            Channel::Log
+        }
+    }
+
+    pub async fn notify(&self, channel: Channel, notification: &Notification) -> Result<()> {
+        self.channel(channel)?.notify(notification).await
+    }
+}
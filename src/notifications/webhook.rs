@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::{config::notifications::WebhookConfig, error::Result};
+
+use super::{Notification, Notifier};
+
+/// Posts notifications as JSON to a configured URL.
+pub struct WebhookNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(cfg: &WebhookConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(cfg.timeout))
+            .build()?;
+
+        Ok(Self {
+            url: cfg.url.clone(),
+            client,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, notification: &Notification) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .json(notification)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use axum::body::Body;
+use tokio::fs::{self, File};
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::Storage;
+
+/// Stores blobs as files below `root`.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        // Keys are generated by us, but never let one escape the root
+        if key.is_empty() || key.split('/').any(|part| matches!(part, "" | "." | "..")) {
+            return Err(Error::BadRequest(format!("Invalid storage key `{key}`")).into());
+        }
+
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, path: &Path, _: &str, _: &str) -> Result<()> {
+        let target = self.path(key)?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // Copy next to the target first, so readers never see a partial file
+        let partial = target.with_extension(format!("partial-{}", Uuid::new_v4()));
+        fs::copy(path, &partial).await?;
+        fs::rename(&partial, &target).await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Body> {
+        let file = match File::open(self.path(key)?).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::EntityNotFound.into())
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Body::from_stream(ReaderStream::new(file)))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
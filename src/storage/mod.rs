@@ -0,0 +1,105 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use axum::body::Body;
+use sha2::{Digest, Sha256};
+use tokio::{fs::File, io::AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::{
+    config::storage::{BackendConfig, StorageConfig},
+    error::{Error, Result},
+};
+
+pub mod local;
+pub mod s3;
+
+/// A blob store for attachment contents.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Store the file at `path` under `key`. `checksum` is the hex SHA-256 of its contents.
+    async fn put(&self, key: &str, path: &Path, content_type: &str, checksum: &str) -> Result<()>;
+
+    /// Stream the contents stored under `key`.
+    async fn get(&self, key: &str) -> Result<Body>;
+
+    /// Remove `key`. Missing keys are not an error.
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+pub fn from_config(cfg: &StorageConfig) -> Result<Arc<dyn Storage>> {
+    let storage: Arc<dyn Storage> = match &cfg.backend {
+        BackendConfig::Local { root } => Arc::new(local::LocalStorage::new(root)),
+        BackendConfig::S3(s3) => Arc::new(s3::S3Storage::new(s3)?),
+    };
+
+    Ok(storage)
+}
+
+/// An upload being written to a temporary file, so that its size and checksum are known before
+/// it reaches the storage backend. The file is removed on drop.
+pub struct Upload {
+    path: PathBuf,
+    file: File,
+    hasher: Sha256,
+    size: u64,
+    max_size: u64,
+}
+
+impl Upload {
+    pub async fn new(max_size: u64) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("todos-upload-{}", Uuid::new_v4()));
+        let file = File::create(&path).await?;
+
+        Ok(Self {
+            path,
+            file,
+            hasher: Sha256::new(),
+            size: 0,
+            max_size,
+        })
+    }
+
+    pub async fn write(&mut self, chunk: &[u8]) -> Result<()> {
+        self.size += chunk.len() as u64;
+        if self.size > self.max_size {
+            return Err(Error::PayloadTooLarge(format!(
+                "Attachments cannot be larger than {} bytes",
+                self.max_size
+            ))
+            .into());
+        }
+
+        self.hasher.update(chunk);
+        self.file.write_all(chunk).await?;
+
+        Ok(())
+    }
+
+    /// Flush the contents to disk, returning the hex SHA-256 of the upload.
+    pub async fn finish(&mut self) -> Result<String> {
+        self.file.flush().await?;
+        self.file.sync_all().await?;
+
+        Ok(hex::encode(self.hasher.clone().finalize()))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl Drop for Upload {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            tracing::warn!("Failed to remove upload {}: {e}", self.path.display());
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use async_trait::async_trait;
+use axum::body::Body;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{header, Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use tokio::fs::File;
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    config::storage::S3Config,
+    error::{Error, Result},
+};
+
+use super::Storage;
+
+/// SHA-256 of an empty payload.
+const EMPTY_PAYLOAD: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// Stores blobs in a bucket of an S3 compatible object store.
+pub struct S3Storage {
+    cfg: S3Config,
+    endpoint: Url,
+    client: reqwest::Client,
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode a key the way S3 expects in a path, keeping the `/` separators.
+fn encode_key(key: &str) -> String {
+    key.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// The `Authorization` header for a request without query string, signed with AWS Signature
+/// Version 4. `headers` are the headers to sign, with lowercase names; they must include `host`,
+/// `x-amz-date` and `x-amz-content-sha256`.
+pub fn authorization(
+    cfg: &S3Config,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    payload_hash: &str,
+    at: DateTime<Utc>,
+) -> String {
+    let mut headers = headers.to_vec();
+    headers.sort();
+
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+
+    let canonical_request =
+        format!("{method}\n{path}\n\n{canonical_headers}\n{signed_headers}\n{payload_hash}");
+
+    let date = at.format("%Y%m%d").to_string();
+    let scope = format!("{date}/{}/s3/aws4_request", cfg.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{scope}\n{}",
+        at.format("%Y%m%dT%H%M%SZ"),
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = [date.as_str(), &cfg.region, "s3", "aws4_request"]
+        .iter()
+        .fold(
+            format!("AWS4{}", cfg.secret_key).into_bytes(),
+            |key, part| hmac(&key, part),
+        );
+    let signature = hex::encode(hmac(&key, &string_to_sign));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope},SignedHeaders={signed_headers},Signature={signature}",
+        cfg.access_key
+    )
+}
+
+impl S3Storage {
+    pub fn new(cfg: &S3Config) -> Result<Self> {
+        let endpoint = Url::parse(&cfg.endpoint)
+            .map_err(|e| Error::ConfigFile(format!("Invalid S3 endpoint: {e}")))?;
+
+        Ok(Self {
+            cfg: cfg.clone(),
+            endpoint,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    fn request(
+        &self,
+        method: Method,
+        key: &str,
+        payload_hash: &str,
+    ) -> Result<reqwest::RequestBuilder> {
+        let path = format!(
+            "{}/{}/{}",
+            self.endpoint.path().trim_end_matches('/'),
+            encode_key(&self.cfg.bucket),
+            encode_key(key)
+        );
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(Error::ConfigFile("S3 endpoint has no host".into()).into()),
+        };
+
+        let now = Utc::now();
+        let date = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+        let authorization = authorization(
+            &self.cfg,
+            method.as_str(),
+            url.path(),
+            &[
+                ("host", &host),
+                ("x-amz-content-sha256", payload_hash),
+                ("x-amz-date", &date),
+            ],
+            payload_hash,
+            now,
+        );
+
+        Ok(self
+            .client
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", date)
+            .header(header::AUTHORIZATION, authorization))
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(
+        &self,
+        key: &str,
+        path: &std::path::Path,
+        content_type: &str,
+        checksum: &str,
+    ) -> Result<()> {
+        let file = File::open(path).await?;
+        let size = file.metadata().await?.len();
+
+        self.request(Method::PUT, key, checksum)?
+            .header(header::CONTENT_TYPE, content_type)
+            .header(header::CONTENT_LENGTH, size)
+            .body(reqwest::Body::wrap_stream(ReaderStream::new(file)))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Body> {
+        let response = self
+            .request(Method::GET, key, EMPTY_PAYLOAD)?
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(Error::EntityNotFound.into());
+        }
+
+        Ok(Body::from_stream(
+            response.error_for_status()?.bytes_stream(),
+        ))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let response = self
+            .request(Method::DELETE, key, EMPTY_PAYLOAD)?
+            .send()
+            .await?;
+
+        if response.status() != StatusCode::NOT_FOUND {
+            response.error_for_status()?;
+        }
+
+        Ok(())
+    }
+}
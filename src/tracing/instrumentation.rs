@@ -69,7 +69,7 @@ impl InstrumentationConfig {
                 }
                 // if --directive is specified, don't set a default
                 if self.directives.is_empty() {
-                    EnvFilter::try_new(&format!(
+                    EnvFilter::try_new(format!(
                         "{}={}",
                         env!("CARGO_PKG_NAME").replace('-', "_"),
                         self.log_level()
@@ -0,0 +1,20 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{config::state::AppContext, models::history::Revision};
+
+/// Purge expired revisions of todos until the process exits.
+pub async fn run(ctx: Arc<AppContext>) {
+    let history = &ctx.config.history;
+    let mut interval = tokio::time::interval(Duration::from_secs(history.interval));
+    let retention = chrono::Duration::days(history.retention);
+
+    loop {
+        interval.tick().await;
+
+        match Revision::purge_expired(&ctx.db, retention).await {
+            Ok(0) => (),
+            Ok(purged) => tracing::info!("Purged {purged} revisions"),
+            Err(e) => tracing::error!("Revision purge failed: {e:?}"),
+        }
+    }
+}
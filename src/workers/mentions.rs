@@ -0,0 +1,70 @@
+use std::{sync::Arc, time::Duration};
+
+use uuid::Uuid;
+
+use crate::{config::state::AppContext, error::Result, models::comments::Comment};
+
+/// Notify mentioned users until the process exits.
+pub async fn run(ctx: Arc<AppContext>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(ctx.config.scheduler.interval));
+
+    loop {
+        interval.tick().await;
+
+        match tick(&ctx).await {
+            Ok(0) => (),
+            Ok(sent) => tracing::debug!("Processed {sent} mentions"),
+            Err(e) => tracing::error!("Mention notifications failed: {e:?}"),
+        }
+    }
+}
+
+/// Claim one batch of pending mentions and notify the mentioned users. As with reminders, each
+/// claim is renewed right before its notification and each mention is marked on its own, so a
+/// failure only ever leaves the mention at hand to be retried once its claim runs out. Failed
+/// notifications are retried on the next ticks, up to the configured number of attempts.
+#[tracing::instrument(skip_all)]
+pub async fn tick(ctx: &AppContext) -> Result<usize> {
+    let scheduler = &ctx.config.scheduler;
+    let channel = ctx.notifiers.preferred();
+    let token = Uuid::new_v4();
+
+    let mentions = Comment::claim_mentions(
+        &ctx.db,
+        token,
+        scheduler.batch_size,
+        scheduler.max_attempts,
+        scheduler.lease,
+    )
+    .await?;
+
+    for mention in &mentions {
+        match Comment::renew_mention(&ctx.db, mention.comment_id, mention.user_id, token).await {
+            Ok(true) => (),
+            Ok(false) => continue,
+            Err(e) => {
+                tracing::error!(comment = %mention.comment_id, "Failed to renew mention claim: {e:?}");
+                continue;
+            }
+        }
+
+        let marked = match ctx.notifiers.notify(channel, &mention.notification()).await {
+            Ok(()) => Comment::mark_notified(&ctx.db, mention.comment_id, mention.user_id).await,
+            Err(e) => {
+                tracing::warn!(
+                    comment = %mention.comment_id,
+                    user = %mention.user_id,
+                    "Failed to notify mentioned user: {e:?}"
+                );
+
+                Comment::mark_failed(&ctx.db, mention.comment_id, mention.user_id).await
+            }
+        };
+
+        if let Err(e) = marked {
+            tracing::error!(comment = %mention.comment_id, "Failed to mark mention: {e:?}");
+        }
+    }
+
+    Ok(mentions.len())
+}
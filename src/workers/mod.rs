@@ -0,0 +1,22 @@
+use std::sync::Arc;
+
+use crate::config::state::AppContext;
+
+pub mod history;
+pub mod mentions;
+pub mod notes;
+pub mod reminders;
+pub mod snoozes;
+pub mod trash;
+pub mod undo;
+
+/// Start the background jobs that run inside the server process.
+pub fn spawn(ctx: Arc<AppContext>) {
+    tokio::spawn(reminders::run(ctx.clone()));
+    tokio::spawn(mentions::run(ctx.clone()));
+    tokio::spawn(notes::run(ctx.clone()));
+    tokio::spawn(snoozes::run(ctx.clone()));
+    tokio::spawn(trash::run(ctx.clone()));
+    tokio::spawn(history::run(ctx.clone()));
+    tokio::spawn(undo::run(ctx));
+}
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use crate::{config::state::AppContext, error::Result, models::notes};
+
+/// Render the notes written before notes were rendered, once at startup. Their task lists are
+/// taken into the checklists, so that later checklist changes keep them.
+pub async fn run(ctx: Arc<AppContext>) {
+    let mut rendered = 0;
+
+    loop {
+        match tick(&ctx).await {
+            Ok(0) => break,
+            Ok(count) => rendered += count,
+            Err(e) => {
+                tracing::error!("Rendering notes failed: {e:?}");
+                break;
+            }
+        }
+    }
+
+    if rendered > 0 {
+        tracing::info!("Rendered the notes of {rendered} todos");
+    }
+}
+
+/// Save the notes of one batch of todos.
+#[tracing::instrument(skip_all)]
+pub async fn tick(ctx: &AppContext) -> Result<usize> {
+    let mut txn = ctx.db.begin().await?;
+
+    let rendered = notes::save_unrendered(&mut txn, ctx.config.scheduler.batch_size).await?;
+
+    txn.commit().await?;
+
+    Ok(rendered)
+}
@@ -0,0 +1,123 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    config::{scheduler::SchedulerConfig, state::AppContext},
+    error::Result,
+    models::reminders::{DueReminder, Reminder},
+    notifications::Notifiers,
+};
+
+/// Poll for due reminders until the process exits.
+pub async fn run(ctx: Arc<AppContext>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(ctx.config.scheduler.interval));
+
+    loop {
+        interval.tick().await;
+
+        match tick(&ctx).await {
+            Ok(0) => (),
+            Ok(sent) => tracing::debug!("Processed {sent} reminders"),
+            Err(e) => tracing::error!("Reminder scheduler failed: {e:?}"),
+        }
+    }
+}
+
+/// Where reminders are claimed and their deliveries recorded, each in a statement of its own.
+#[async_trait]
+pub trait Outbox: Send + Sync {
+    async fn claim(&self, scheduler: &SchedulerConfig, token: Uuid) -> Result<Vec<DueReminder>>;
+
+    /// Restart the lease of one claimed reminder, or return false if `token` lost its claim.
+    async fn renew(&self, id: Uuid, token: Uuid) -> Result<bool>;
+
+    async fn mark_sent(&self, id: Uuid) -> Result<()>;
+
+    async fn mark_failed(&self, id: Uuid, error: &str, retry_at: DateTime<Utc>) -> Result<()>;
+}
+
+#[async_trait]
+impl Outbox for PgPool {
+    async fn claim(&self, scheduler: &SchedulerConfig, token: Uuid) -> Result<Vec<DueReminder>> {
+        Reminder::claim_due(
+            self,
+            token,
+            scheduler.batch_size,
+            scheduler.max_attempts,
+            scheduler.lease,
+        )
+        .await
+    }
+
+    async fn renew(&self, id: Uuid, token: Uuid) -> Result<bool> {
+        Reminder::renew_claim(self, id, token).await
+    }
+
+    async fn mark_sent(&self, id: Uuid) -> Result<()> {
+        Reminder::mark_sent(self, id).await
+    }
+
+    async fn mark_failed(&self, id: Uuid, error: &str, retry_at: DateTime<Utc>) -> Result<()> {
+        Reminder::mark_failed(self, id, error, retry_at).await
+    }
+}
+
+/// Claim one batch of due reminders and deliver them.
+#[tracing::instrument(skip_all)]
+pub async fn tick(ctx: &AppContext) -> Result<usize> {
+    deliver(&ctx.db, &ctx.notifiers, &ctx.config.scheduler).await
+}
+
+/// Claim one batch of due reminders from `outbox` and deliver them one by one. The claim keeps
+/// other instances away from the batch, and each reminder is marked as soon as it is delivered,
+/// so a failure only ever leaves the reminder at hand to be retried once its claim runs out.
+/// Failed deliveries are retried with an exponential backoff.
+///
+/// A slow batch can take longer than the lease, so the claim of each reminder is renewed right
+/// before it is sent. A reminder another instance claimed in the meantime is left to it.
+pub async fn deliver(
+    outbox: &dyn Outbox,
+    notifiers: &Notifiers,
+    scheduler: &SchedulerConfig,
+) -> Result<usize> {
+    let token = Uuid::new_v4();
+    let reminders = outbox.claim(scheduler, token).await?;
+
+    for reminder in &reminders {
+        match outbox.renew(reminder.id, token).await {
+            Ok(true) => (),
+            Ok(false) => {
+                tracing::debug!(reminder = %reminder.id, "Reminder was claimed by another scheduler");
+                continue;
+            }
+            Err(e) => {
+                tracing::error!(reminder = %reminder.id, "Failed to renew reminder claim: {e:?}");
+                continue;
+            }
+        }
+
+        let notification = reminder.notification();
+
+        let marked = match notifiers.notify(reminder.channel, &notification).await {
+            Ok(()) => outbox.mark_sent(reminder.id).await,
+            Err(e) => {
+                tracing::warn!(reminder = %reminder.id, "Failed to deliver reminder: {e:?}");
+
+                let backoff = chrono::Duration::minutes(1 << (reminder.attempts - 1).clamp(0, 10));
+                outbox
+                    .mark_failed(reminder.id, &e.to_string(), Utc::now() + backoff)
+                    .await
+            }
+        };
+
+        if let Err(e) = marked {
+            tracing::error!(reminder = %reminder.id, "Failed to mark reminder: {e:?}");
+        }
+    }
+
+    Ok(reminders.len())
+}
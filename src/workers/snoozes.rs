@@ -0,0 +1,42 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{config::state::AppContext, error::Result, models::todos::Todo};
+
+/// Bring snoozed todos back once they start, until the process exits.
+pub async fn run(ctx: Arc<AppContext>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(ctx.config.scheduler.interval));
+
+    loop {
+        interval.tick().await;
+
+        match tick(&ctx).await {
+            Ok(0) => (),
+            Ok(started) => tracing::debug!("Started {started} snoozed todos"),
+            Err(e) => tracing::error!("Starting snoozed todos failed: {e:?}"),
+        }
+    }
+}
+
+/// Claim one batch of snoozed todos whose start has come, clear their snoozes and then notify
+/// the owners who asked for it. The snoozes are committed first, so a failed commit notifies no
+/// one twice. A notification that fails is not retried; the todo is back in view anyway.
+#[tracing::instrument(skip_all)]
+pub async fn tick(ctx: &AppContext) -> Result<usize> {
+    let channel = ctx.notifiers.preferred();
+    let mut txn = ctx.db.begin().await?;
+
+    let todos = Todo::claim_started(&mut txn, ctx.config.scheduler.batch_size).await?;
+
+    let ids: Vec<_> = todos.iter().map(|todo| todo.id).collect();
+    Todo::mark_started(&mut txn, &ids).await?;
+
+    txn.commit().await?;
+
+    for todo in todos.iter().filter(|todo| todo.notify_on_start) {
+        if let Err(e) = ctx.notifiers.notify(channel, &todo.notification()).await {
+            tracing::warn!(todo = %todo.id, "Failed to notify about a started todo: {e:?}");
+        }
+    }
+
+    Ok(todos.len())
+}
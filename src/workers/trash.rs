@@ -0,0 +1,21 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{config::state::AppContext, models::trash::Trash};
+
+/// Purge expired items from the trash until the process exits.
+pub async fn run(ctx: Arc<AppContext>) {
+    let trash = &ctx.config.trash;
+    let mut interval = tokio::time::interval(Duration::from_secs(trash.interval));
+    let retention = chrono::Duration::days(trash.retention);
+
+    loop {
+        interval.tick().await;
+
+        match Trash::purge_expired(&ctx.db, ctx.storage.as_ref(), retention, trash.batch_size).await
+        {
+            Ok(0) => (),
+            Ok(purged) => tracing::info!("Purged {purged} items from the trash"),
+            Err(e) => tracing::error!("Trash purge failed: {e:?}"),
+        }
+    }
+}
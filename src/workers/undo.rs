@@ -0,0 +1,20 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{config::state::AppContext, models::undo::Operation};
+
+/// Purge operations that can no longer be undone until the process exits.
+pub async fn run(ctx: Arc<AppContext>) {
+    let undo = &ctx.config.undo;
+    let mut interval = tokio::time::interval(Duration::from_secs(undo.interval));
+    let window = chrono::Duration::seconds(undo.window);
+
+    loop {
+        interval.tick().await;
+
+        match Operation::purge_expired(&ctx.db, window).await {
+            Ok(0) => (),
+            Ok(purged) => tracing::info!("Purged {purged} undo operations"),
+            Err(e) => tracing::error!("Undo purge failed: {e:?}"),
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use chrono::{TimeZone, Utc};
+use serde_json::json;
+use todos::filter::{
+    Comparison, Condition, DateField, DateValue, FieldValue, Filter, State, Unit, Value,
+};
+use todos::models::todos::Priority;
+
+fn condition(condition: Condition) -> Box<Filter> {
+    Box::new(Filter::Condition(condition))
+}
+
+#[test]
+fn test_parse_precedence() {
+    let filter = Filter::parse("tag:work AND (due<2d OR is:blocked) AND NOT list:someday").unwrap();
+
+    assert_eq!(
+        filter,
+        Filter::And(
+            Box::new(Filter::And(
+                condition(Condition::Tag("work".into())),
+                Box::new(Filter::Or(
+                    condition(Condition::Date(
+                        DateField::Due,
+                        Comparison::Lt,
+                        Some(DateValue::Relative(2, Unit::Days))
+                    )),
+                    condition(Condition::Is(State::Blocked)),
+                )),
+            )),
+            Box::new(Filter::Not(condition(Condition::List(Some(
+                "someday".into()
+            ))))),
+        )
+    );
+
+    // AND binds tighter than OR, and is implied between conditions
+    assert_eq!(
+        Filter::parse("milk or tag:home list:inbox").unwrap(),
+        Filter::Or(
+            condition(Condition::Title("milk".into())),
+            Box::new(Filter::And(
+                condition(Condition::Tag("home".into())),
+                condition(Condition::List(None)),
+            )),
+        )
+    );
+}
+
+#[test]
+fn test_parse_values() {
+    assert_eq!(
+        Filter::parse("title:\"say \\\"hi\\\"\" list:\"inbox\" due:none").unwrap(),
+        Filter::And(
+            Box::new(Filter::And(
+                condition(Condition::Title("say \"hi\"".into())),
+                condition(Condition::List(Some("inbox".into()))),
+            )),
+            condition(Condition::Date(DateField::Due, Comparison::Eq, None)),
+        )
+    );
+    assert_eq!(
+        Filter::parse("completed>=2025-05-01T10:00:00+02:00").unwrap(),
+        *condition(Condition::Date(
+            DateField::Completed,
+            Comparison::Ge,
+            Some(DateValue::Instant(
+                "2025-05-01T10:00:00+02:00".parse().unwrap()
+            ))
+        ))
+    );
+    assert_eq!(
+        Filter::parse("tag!=work").unwrap(),
+        Filter::Not(condition(Condition::Tag("work".into())))
+    );
+}
+
+#[test]
+fn test_parse_errors_have_positions() {
+    let error = |input: &str| {
+        let error = Filter::parse(input).unwrap_err();
+        (error.message, error.position)
+    };
+
+    assert_eq!(error("").1, 0);
+    assert_eq!(error("(tag:a").1, 6);
+    assert_eq!(error("tag:a)").1, 5);
+    assert_eq!(error("prio>=3"), ("Unknown field `prio`".into(), 0));
+    assert_eq!(error("tag:a AND due<soon").1, 14);
+    assert_eq!(error("tag<a").1, 0);
+    assert_eq!(error("due<none").1, 4);
+    assert_eq!(error("is:lost").1, 3);
+    assert_eq!(error("title:\"open").1, 6);
+    assert_eq!(error("tag:a OR").1, 8);
+    assert_eq!(error("! tag:a").1, 0);
+    assert_eq!(error(&"(".repeat(100)).1, 32);
+}
+
+#[test]
+fn test_compile() {
+    let now = Utc.with_ymd_and_hms(2025, 5, 1, 12, 0, 0).unwrap();
+    let compiled = Filter::parse("tag:#Work due:today NOT notes:50%")
+        .unwrap()
+        .compile(4, now);
+
+    assert_eq!(
+        compiled.sql,
+        "((($4 = ANY(todos.tags)) AND (COALESCE(todos.due_at >= $5 AND todos.due_at < $6, false))) \
+         AND (NOT (COALESCE(todos.notes ILIKE $7, false))))"
+    );
+    assert_eq!(
+        compiled.values,
+        vec![
+            Value::Text("work".into()),
+            Value::Time(Utc.with_ymd_and_hms(2025, 5, 1, 0, 0, 0).unwrap()),
+            Value::Time(Utc.with_ymd_and_hms(2025, 5, 2, 0, 0, 0).unwrap()),
+            Value::Text("%50\\%%".into()),
+        ]
+    );
+}
+
+#[test]
+fn test_active_leaves_out_snoozed_todos() {
+    let snoozed = || condition(Condition::Is(State::Snoozed));
+
+    assert_eq!(Filter::active(None), Filter::Not(snoozed()));
+    assert_eq!(
+        Filter::active(Some(Filter::parse("tag:work").unwrap())),
+        Filter::And(
+            condition(Condition::Tag("work".into())),
+            Box::new(Filter::Not(snoozed()))
+        )
+    );
+    assert_eq!(
+        Filter::active(Some(Filter::parse("tag:work is:snoozed").unwrap())),
+        Filter::parse("tag:work is:snoozed").unwrap()
+    );
+}
+
+#[test]
+fn test_custom_fields() {
+    let field = |value| condition(Condition::Field("points".into(), Comparison::Eq, value));
+
+    assert_eq!(
+        Filter::parse("field.Points:3").unwrap(),
+        *field(Some(FieldValue::Number(3.0)))
+    );
+    assert_eq!(
+        Filter::parse("field.points:\"3\"").unwrap(),
+        *field(Some(FieldValue::Text("3".into())))
+    );
+    assert_eq!(
+        Filter::parse("field.points!=none").unwrap(),
+        Filter::Not(field(None))
+    );
+    assert!(Filter::parse("field.points<none").is_err());
+    assert!(Filter::parse("field.:3").is_err());
+    assert_eq!(
+        Filter::parse("field.points:\"true\"").unwrap().to_string(),
+        "field.points:\"true\""
+    );
+
+    let now = Utc.with_ymd_and_hms(2025, 5, 1, 12, 0, 0).unwrap();
+    let compiled = Filter::parse("field.points:3 field.points>=1")
+        .unwrap()
+        .compile(2, now);
+
+    assert_eq!(
+        compiled.sql,
+        "(((todos.fields @> $2 OR todos.fields @> $3)) AND \
+         (COALESCE(jsonb_typeof(todos.fields -> $4) = jsonb_typeof($5) \
+         AND todos.fields -> $6 >= $7, false)))"
+    );
+    assert_eq!(
+        compiled.values,
+        vec![
+            Value::Json(json!({ "points": 3.0 })),
+            Value::Json(json!({ "points": "3" })),
+            Value::Text("points".into()),
+            Value::Json(json!(1.0)),
+            Value::Text("points".into()),
+            Value::Json(json!(1.0)),
+        ]
+    );
+}
+
+#[test]
+fn test_priority() {
+    let filter =
+        Filter::parse("tag:work AND (due<2d OR priority>=3) AND NOT list:someday").unwrap();
+
+    assert_eq!(
+        filter,
+        Filter::And(
+            Box::new(Filter::And(
+                condition(Condition::Tag("work".into())),
+                Box::new(Filter::Or(
+                    condition(Condition::Date(
+                        DateField::Due,
+                        Comparison::Lt,
+                        Some(DateValue::Relative(2, Unit::Days))
+                    )),
+                    condition(Condition::Priority(Comparison::Ge, Priority::High)),
+                )),
+            )),
+            Box::new(Filter::Not(condition(Condition::List(Some(
+                "someday".into()
+            ))))),
+        )
+    );
+    assert_eq!(
+        Filter::parse("priority:Medium").unwrap(),
+        *condition(Condition::Priority(Comparison::Eq, Priority::Medium))
+    );
+    assert_eq!(
+        Filter::parse("priority<=2").unwrap().to_string(),
+        "priority<=medium"
+    );
+    assert!(Filter::parse("priority>4").is_err());
+
+    let compiled = Filter::parse("priority>low")
+        .unwrap()
+        .compile(1, Utc::now());
+    assert_eq!(
+        compiled.sql,
+        "(COALESCE(array_position($1, todos.priority) > $2, false))"
+    );
+    assert_eq!(
+        compiled.values,
+        vec![
+            Value::Priorities(vec![Priority::Low, Priority::Medium, Priority::High]),
+            Value::Int(1),
+        ]
+    );
+}
@@ -0,0 +1,218 @@
+//! Whatever the input, a filter compiles to SQL made only of the compiler's own text: user input
+//! reaches the database as bound values and nothing else.
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use proptest::prelude::*;
+use todos::{
+    filter::{Comparison, Condition, DateField, DateValue, FieldValue, Filter, State, Unit},
+    models::todos::Priority,
+};
+
+fn date_value() -> impl Strategy<Value = DateValue> {
+    prop_oneof![
+        (
+            -100_000i64..=100_000,
+            prop_oneof![Just(Unit::Hours), Just(Unit::Days), Just(Unit::Weeks)]
+        )
+            .prop_map(|(amount, unit)| DateValue::Relative(amount, unit)),
+        (-1i64..=1).prop_map(DateValue::Today),
+        (1i32..=9999, 1u32..=12, 1u32..=28)
+            .prop_map(|(y, m, d)| DateValue::Day(NaiveDate::from_ymd_opt(y, m, d).unwrap())),
+        (0i64..253_402_300_799, -14 * 60i32..=14 * 60).prop_map(|(secs, minutes)| {
+            let offset = chrono::FixedOffset::east_opt(minutes * 60).unwrap();
+            DateValue::Instant(offset.timestamp_opt(secs, 0).unwrap())
+        }),
+    ]
+}
+
+fn comparison() -> impl Strategy<Value = Comparison> {
+    prop_oneof![
+        Just(Comparison::Eq),
+        Just(Comparison::Lt),
+        Just(Comparison::Le),
+        Just(Comparison::Gt),
+        Just(Comparison::Ge)
+    ]
+}
+
+fn field_value() -> impl Strategy<Value = FieldValue> {
+    prop_oneof![
+        any::<String>().prop_map(FieldValue::Text),
+        any::<f64>()
+            .prop_filter("finite", |number| number.is_finite())
+            .prop_map(FieldValue::Number),
+        any::<bool>().prop_map(FieldValue::Bool),
+    ]
+}
+
+fn condition() -> impl Strategy<Value = Condition> {
+    prop_oneof![
+        any::<String>().prop_map(Condition::Tag),
+        proptest::option::of(any::<String>()).prop_map(Condition::List),
+        any::<String>().prop_map(Condition::Title),
+        any::<String>().prop_map(Condition::Notes),
+        (
+            prop_oneof![
+                Just(DateField::Due),
+                Just(DateField::Start),
+                Just(DateField::Created),
+                Just(DateField::Updated),
+                Just(DateField::Completed)
+            ],
+            comparison(),
+            proptest::option::of(date_value()),
+        )
+            .prop_map(|(field, comparison, value)| match value {
+                None => Condition::Date(field, Comparison::Eq, None),
+                value => Condition::Date(field, comparison, value),
+            }),
+        (
+            "[a-z0-9_]{1,12}",
+            comparison(),
+            proptest::option::of(field_value())
+        )
+            .prop_map(|(key, comparison, value)| match value {
+                None => Condition::Field(key, Comparison::Eq, None),
+                value => Condition::Field(key, comparison, value),
+            }),
+        (
+            comparison(),
+            prop_oneof![
+                Just(Priority::Low),
+                Just(Priority::Medium),
+                Just(Priority::High)
+            ]
+        )
+            .prop_map(|(comparison, priority)| Condition::Priority(comparison, priority)),
+        prop_oneof![
+            Just(State::Done),
+            Just(State::Open),
+            Just(State::Blocked),
+            Just(State::Recurring),
+            Just(State::Snoozed)
+        ]
+        .prop_map(Condition::Is),
+    ]
+}
+
+fn filter() -> impl Strategy<Value = Filter> {
+    condition()
+        .prop_map(Filter::Condition)
+        .prop_recursive(6, 32, 2, |inner| {
+            prop_oneof![
+                (inner.clone(), inner.clone())
+                    .prop_map(|(l, r)| Filter::And(Box::new(l), Box::new(r))),
+                (inner.clone(), inner.clone())
+                    .prop_map(|(l, r)| Filter::Or(Box::new(l), Box::new(r))),
+                inner.prop_map(|f| Filter::Not(Box::new(f))),
+            ]
+        })
+}
+
+/// Input built from the pieces of the language, to get past the lexer more often than random
+/// text does.
+fn source() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        Just("tag".to_string()),
+        Just("list".to_string()),
+        Just("due".to_string()),
+        Just("priority".to_string()),
+        Just("is".to_string()),
+        Just("field.points".to_string()),
+        Just("none".to_string()),
+        Just(":".to_string()),
+        Just("<=".to_string()),
+        Just("!=".to_string()),
+        Just("(".to_string()),
+        Just(")".to_string()),
+        Just("AND".to_string()),
+        Just("or".to_string()),
+        Just("NOT".to_string()),
+        Just("\"".to_string()),
+        Just("'; DROP TABLE todos; --".to_string()),
+        Just("$1".to_string()),
+        any::<String>(),
+    ];
+
+    prop::collection::vec(piece, 0..16).prop_map(|pieces| pieces.join(" "))
+}
+
+/// The same filter with every text value replaced.
+fn blank(filter: &Filter) -> Filter {
+    match filter {
+        Filter::And(l, r) => Filter::And(Box::new(blank(l)), Box::new(blank(r))),
+        Filter::Or(l, r) => Filter::Or(Box::new(blank(l)), Box::new(blank(r))),
+        Filter::Not(f) => Filter::Not(Box::new(blank(f))),
+        Filter::Condition(condition) => Filter::Condition(match condition {
+            Condition::Tag(_) => Condition::Tag("x".into()),
+            Condition::List(Some(_)) => Condition::List(Some("x".into())),
+            Condition::Title(_) => Condition::Title("x".into()),
+            Condition::Notes(_) => Condition::Notes("x".into()),
+            Condition::Field(_, comparison, value) => Condition::Field(
+                "x".into(),
+                *comparison,
+                value.as_ref().map(|value| match value {
+                    FieldValue::Text(_) => FieldValue::Text("x".into()),
+                    FieldValue::Number(_) => FieldValue::Number(0.0),
+                    FieldValue::Bool(_) => FieldValue::Bool(false),
+                }),
+            ),
+            Condition::Priority(comparison, _) => Condition::Priority(*comparison, Priority::Low),
+            condition => condition.clone(),
+        }),
+    }
+}
+
+/// The SQL is plain text with numbered placeholders, one for each value and in order.
+fn assert_safe(sql: &str, values: usize, first: usize) {
+    for forbidden in ["'", "\"", ";", "--", "/*", "\\"] {
+        assert!(!sql.contains(forbidden), "`{forbidden}` in {sql}");
+    }
+
+    let placeholders: Vec<usize> = sql
+        .split('$')
+        .skip(1)
+        .map(|rest| {
+            let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse().unwrap()
+        })
+        .collect();
+    let expected: Vec<usize> = (first..first + values).collect();
+
+    assert_eq!(placeholders, expected);
+}
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 5, 1, 12, 0, 0).unwrap()
+}
+
+proptest! {
+    #[test]
+    fn test_arbitrary_input_compiles_to_safe_sql(input in source()) {
+        if let Ok(filter) = Filter::parse(&input) {
+            let compiled = filter.compile(4, now());
+            assert_safe(&compiled.sql, compiled.values.len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_random_text_never_panics(input in "\\PC*") {
+        if let Ok(filter) = Filter::parse(&input) {
+            let compiled = filter.compile(2, now());
+            assert_safe(&compiled.sql, compiled.values.len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_sql_does_not_depend_on_values(filter in filter()) {
+        let compiled = filter.compile(1, now());
+
+        assert_safe(&compiled.sql, compiled.values.len(), 1);
+        prop_assert_eq!(compiled.sql, blank(&filter).compile(1, now()).sql);
+    }
+
+    #[test]
+    fn test_display_parses_back(filter in filter()) {
+        prop_assert_eq!(Filter::parse(&filter.to_string()), Ok(filter));
+    }
+}
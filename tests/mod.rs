@@ -1 +1,4 @@
+mod filter;
 mod models;
+mod storage;
+mod workers;
@@ -0,0 +1,36 @@
+use chrono::{Duration, Utc};
+use todos::models::attachments::{sign, verify, Signature};
+use uuid::Uuid;
+
+#[test]
+fn test_signed_url_verifies() {
+    let id = Uuid::new_v4();
+    let expires = (Utc::now() + Duration::minutes(5)).timestamp();
+    let signature = Signature {
+        expires,
+        signature: sign("secret", id, expires),
+    };
+
+    assert!(verify("secret", id, &signature, Utc::now()).is_ok());
+    assert!(verify("other", id, &signature, Utc::now()).is_err());
+    assert!(verify("secret", Uuid::new_v4(), &signature, Utc::now()).is_err());
+}
+
+#[test]
+fn test_signed_url_expires() {
+    let id = Uuid::new_v4();
+    let expires = Utc::now().timestamp();
+    let signature = Signature {
+        expires,
+        signature: sign("secret", id, expires),
+    };
+
+    assert!(verify("secret", id, &signature, Utc::now() + Duration::seconds(1)).is_err());
+
+    // Moving the expiry invalidates the signature
+    let extended = Signature {
+        expires: expires + 3600,
+        ..signature
+    };
+    assert!(verify("secret", id, &extended, Utc::now()).is_err());
+}
@@ -0,0 +1,9 @@
+use todos::models::checklists::convertible_title;
+
+#[test]
+fn test_convertible_title() {
+    assert_eq!(convertible_title("Buy milk").unwrap(), "Buy milk");
+    assert!(convertible_title(&"é".repeat(255)).is_ok());
+
+    assert!(convertible_title(&"a".repeat(300)).is_err());
+}
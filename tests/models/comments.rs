@@ -0,0 +1,46 @@
+use serde_json::json;
+use sqlx::PgPool;
+use todos::models::{
+    comments::{parse_mentions, Comment, NewComment},
+    todos::{CreateTodo, Todo},
+};
+
+use super::register;
+
+#[test]
+fn test_parse_mentions() {
+    assert_eq!(
+        parse_mentions("@alice can you check this with @Bob_2?"),
+        vec!["alice", "bob_2"]
+    );
+}
+
+#[test]
+fn test_parse_mentions_ignores_email_addresses() {
+    assert_eq!(
+        parse_mentions("mail alice@example.com or ping @carol."),
+        vec!["carol"]
+    );
+}
+
+#[test]
+fn test_parse_mentions_deduplicates() {
+    assert_eq!(parse_mentions("@dave @DAVE (@dave)"), vec!["dave"]);
+    assert!(parse_mentions("@ and @@").is_empty());
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "needs a Postgres database in DATABASE_URL"]
+async fn test_comment_stores_mentions(db: PgPool) {
+    let alice = register(&db, "alice").await;
+    register(&db, "bob").await;
+    let dto: CreateTodo = serde_json::from_value(json!({ "title": "Review" })).unwrap();
+    let todo = Todo::create(&db, alice.id, &dto).await.unwrap();
+
+    let dto = NewComment {
+        body: "@bob can you check this? cc @alice @nobody".into(),
+    };
+    let comment = Comment::create(&db, alice.id, todo.id, &dto).await.unwrap();
+
+    assert_eq!(comment.mentions, vec!["bob"]);
+}
@@ -0,0 +1,60 @@
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use todos::models::custom_fields::{field_key, merge_values, CustomField, FieldKind};
+use uuid::Uuid;
+
+fn field(name: &str, kind: FieldKind, options: &[&str]) -> CustomField {
+    CustomField {
+        id: Uuid::new_v4(),
+        list_id: Uuid::nil(),
+        name: name.into(),
+        key: field_key(name).unwrap(),
+        kind,
+        options: options.iter().map(|option| option.to_string()).collect(),
+        version: 1,
+        created_at: Utc::now().fixed_offset(),
+        updated_at: Utc::now().fixed_offset(),
+    }
+}
+
+fn values(value: Value) -> Map<String, Value> {
+    value.as_object().unwrap().clone()
+}
+
+#[test]
+fn test_field_key() {
+    assert_eq!(field_key("Story points").unwrap(), "story_points");
+    assert_eq!(field_key("  Due (client) ").unwrap(), "due_client");
+    assert_eq!(field_key("Größe").unwrap(), "größe");
+    assert!(field_key("--").is_err());
+    assert!(field_key(&"x".repeat(101)).is_err());
+}
+
+#[test]
+fn test_merge_values() {
+    let fields = [
+        field("Points", FieldKind::Number, &[]),
+        field("Ship date", FieldKind::Date, &[]),
+        field("Size", FieldKind::Select, &["S", "M", "L"]),
+        field("Billable", FieldKind::Checkbox, &[]),
+    ];
+    let current = values(json!({ "points": 3, "size": "S" }));
+
+    assert_eq!(
+        merge_values(
+            &fields,
+            &current,
+            &values(json!({ "Ship date": "2025-5-1", "size": null, "billable": true }))
+        )
+        .unwrap(),
+        values(json!({ "points": 3, "ship_date": "2025-05-01", "billable": true }))
+    );
+
+    let invalid = |changes: Value| merge_values(&fields, &current, &values(changes)).is_err();
+
+    assert!(invalid(json!({ "points": "3" })));
+    assert!(invalid(json!({ "ship_date": "tomorrow" })));
+    assert!(invalid(json!({ "size": "XL" })));
+    assert!(invalid(json!({ "billable": 1 })));
+    assert!(invalid(json!({ "customer": "Acme" })));
+}
@@ -0,0 +1,24 @@
+use todos::models::dependencies::find_path;
+use uuid::Uuid;
+
+#[test]
+fn test_find_path_follows_dependencies() {
+    let (a, b, c, d) = (
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+    );
+    let edges = vec![(a, b), (b, c), (a, d)];
+
+    assert_eq!(find_path(&edges, a, c), Some(vec![a, b, c]));
+    assert_eq!(find_path(&edges, c, a), None);
+    assert_eq!(find_path(&edges, d, c), None);
+}
+
+#[test]
+fn test_find_path_detects_self_reference() {
+    let a = Uuid::new_v4();
+
+    assert_eq!(find_path(&[], a, a), Some(vec![a]));
+}
@@ -0,0 +1,91 @@
+use serde_json::json;
+use sqlx::PgPool;
+use todos::{
+    error::Error,
+    models::{
+        boards::{BoardColumn, CreateColumn, SetStatus},
+        duplicates::{DuplicateList, DuplicateTodo},
+        lists::{CreateList, List},
+        todos::{CreateTodo, Todo},
+    },
+};
+
+use super::register;
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "needs a Postgres database in DATABASE_URL"]
+async fn test_duplicate_respects_wip_limits(db: PgPool) {
+    let user = register(&db, "alice").await;
+    let list = List::create(
+        &db,
+        user.id,
+        &CreateList {
+            name: "Sprint".into(),
+        },
+    )
+    .await
+    .unwrap();
+    let column = CreateColumn {
+        name: "Doing".into(),
+        wip_limit: Some(1),
+        is_done: false,
+    };
+    let column = BoardColumn::create(&db, user.id, list.id, &column)
+        .await
+        .unwrap();
+    let dto: CreateTodo =
+        serde_json::from_value(json!({ "title": "Ship", "listId": list.id })).unwrap();
+    let todo = Todo::create(&db, user.id, &dto).await.unwrap();
+    let status = SetStatus {
+        column_id: Some(column.id),
+    };
+    BoardColumn::set_status(&db, user.id, todo.id, &status, None)
+        .await
+        .unwrap();
+
+    let err = Todo::duplicate(&db, user.id, todo.id, &DuplicateTodo::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(err.downcast_ref(), Some(Error::Conflict(_))));
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "needs a Postgres database in DATABASE_URL"]
+async fn test_duplicate_rejects_blank_titles_and_names(db: PgPool) {
+    let user = register(&db, "alice").await;
+    let list = List::create(
+        &db,
+        user.id,
+        &CreateList {
+            name: "Sprint".into(),
+        },
+    )
+    .await
+    .unwrap();
+    let dto: CreateTodo =
+        serde_json::from_value(json!({ "title": "Ship", "listId": list.id })).unwrap();
+    let todo = Todo::create(&db, user.id, &dto).await.unwrap();
+
+    let dto = DuplicateTodo {
+        title: Some("  ".into()),
+        ..Default::default()
+    };
+    let err = Todo::duplicate(&db, user.id, todo.id, &dto)
+        .await
+        .unwrap_err();
+    assert!(matches!(err.downcast_ref(), Some(Error::BadRequest(_))));
+
+    let dto = DuplicateList {
+        name: Some("  ".into()),
+        ..Default::default()
+    };
+    let err = List::duplicate(&db, user.id, list.id, &dto)
+        .await
+        .unwrap_err();
+    assert!(matches!(err.downcast_ref(), Some(Error::BadRequest(_))));
+
+    let copy = Todo::duplicate(&db, user.id, todo.id, &DuplicateTodo::default())
+        .await
+        .unwrap();
+    assert_eq!(copy.title, "Ship");
+}
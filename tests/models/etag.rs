@@ -0,0 +1,18 @@
+use todos::models::if_match_versions;
+
+#[test]
+fn test_if_match_versions() {
+    assert_eq!(if_match_versions("\"3\""), Some(vec![3]));
+    assert_eq!(if_match_versions("\"3\", \"4\""), Some(vec![3, 4]));
+    assert_eq!(if_match_versions(" * "), None);
+}
+
+#[test]
+fn test_if_match_versions_leaves_out_tags_that_cannot_match() {
+    assert_eq!(
+        if_match_versions("W/\"3\", \"a,b\", 4, \"5\""),
+        Some(vec![5])
+    );
+    assert_eq!(if_match_versions("\"3"), Some(vec![]));
+    assert_eq!(if_match_versions(""), Some(vec![]));
+}
@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use serde_json::{json, Value};
+use sqlx::types::Json;
+use todos::models::history::{value_at, Change, Revision};
+use uuid::Uuid;
+
+fn revision(revision: i32, changes: &[(&str, Value, Value)]) -> Revision {
+    Revision {
+        id: Uuid::new_v4(),
+        todo_id: Uuid::nil(),
+        actor_id: None,
+        revision,
+        changes: Json(HashMap::from_iter(changes.iter().map(
+            |(field, old, new)| {
+                (
+                    field.to_string(),
+                    Change {
+                        old: old.clone(),
+                        new: new.clone(),
+                    },
+                )
+            },
+        ))),
+        created_at: Utc::now().fixed_offset(),
+    }
+}
+
+#[test]
+fn test_value_at() {
+    let revisions = [
+        revision(1, &[("title", json!("Draft"), json!("Plan"))]),
+        revision(2, &[("notes", Value::Null, json!("Flights"))]),
+        revision(3, &[("title", json!("Plan"), json!("Trip"))]),
+    ];
+
+    assert_eq!(value_at(&revisions, 2, "title"), Some(&json!("Plan")));
+    assert_eq!(value_at(&revisions, 1, "notes"), Some(&Value::Null));
+    assert_eq!(value_at(&revisions, 3, "notes"), Some(&json!("Flights")));
+    assert_eq!(value_at(&revisions, 3, "tags"), None);
+}
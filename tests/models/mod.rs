@@ -1 +1,36 @@
+use ::todos::models::users::{RegisterUser, User};
+use sqlx::PgPool;
+
+mod attachments;
+mod checklists;
+mod comments;
+mod custom_fields;
+mod dependencies;
+mod duplicates;
+mod etag;
+mod history;
+mod notes;
+mod pagination;
+mod position;
+mod quick;
+mod rrule;
+mod search;
+mod settings;
+mod templates;
+mod todos;
+mod trash;
+mod urgency;
 mod user;
+mod views;
+
+/// Register a user named `username`, for tests that run against a database.
+async fn register(db: &PgPool, username: &str) -> User {
+    let email = format!("{username}@example.com");
+
+    User::register(
+        db,
+        &RegisterUser::new(username, &email, "password", "password"),
+    )
+    .await
+    .unwrap()
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+use sqlx::PgPool;
+use todos::models::{
+    checklists::ChecklistItem,
+    notes::{check, prune, references, remap, render, save_unrendered, tasks, uncheck},
+    todos::{CreateTodo, Todo},
+};
+use uuid::Uuid;
+
+use super::register;
+
+#[test]
+fn test_tasks() {
+    let notes =
+        "Before leaving:\n\n- [ ] Pack `charger`\n- [x] Water\n  the plants\n- Not a task\n";
+
+    let tasks: Vec<_> = tasks(notes)
+        .into_iter()
+        .map(|task| (task.text, task.checked))
+        .collect();
+    assert_eq!(
+        tasks,
+        [
+            ("Pack charger".to_string(), false),
+            ("Water the plants".to_string(), true)
+        ]
+    );
+}
+
+#[test]
+fn test_check() {
+    let checklist = HashMap::from([("Milk".to_string(), true), ("Eggs".to_string(), false)]);
+
+    assert_eq!(
+        check("- [ ] Milk\n- [x] Eggs\n- [ ] Bread\n", &checklist),
+        "- [x] Milk\n- [ ] Eggs\n- [ ] Bread\n"
+    );
+}
+
+#[test]
+fn test_uncheck() {
+    assert_eq!(
+        uncheck("Trip:\n\n- [x] Pack\n- [ ] Water\n  - [X] the plants\n"),
+        "Trip:\n\n- [ ] Pack\n- [ ] Water\n  - [ ] the plants\n"
+    );
+}
+
+#[test]
+fn test_prune_round_trip() {
+    let checklist = HashMap::from([("Milk".to_string(), false), ("Eggs".to_string(), true)]);
+    let cases = [
+        (
+            "- [ ] Milk\n- [ ] Bread\n- [x] Eggs\n",
+            "- [ ] Milk\n- [x] Eggs\n",
+        ),
+        (
+            "Shopping:\n\n- [ ] Milk\n- [x] Bread",
+            "Shopping:\n\n- [ ] Milk\n",
+        ),
+        ("- [ ] Bread\n  and butter\n- [x] Eggs\n", "- [x] Eggs\n"),
+        ("- [ ] Bread\n  - [ ] Milk\n", "  - [ ] Milk\n"),
+        ("- [ ] \n- [ ] Milk\n", "- [ ] \n- [ ] Milk\n"),
+    ];
+
+    for (notes, pruned) in cases {
+        let notes = prune(notes, &checklist);
+        assert_eq!(notes, pruned);
+
+        // What is left of the task list follows the checklist
+        assert!(tasks(&check(&notes, &checklist)).iter().all(|task| {
+            task.text.is_empty() || checklist.get(&task.text) == Some(&task.checked)
+        }));
+    }
+}
+
+#[test]
+fn test_render() {
+    let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+    let notes = format!(
+        "See #todo-{first} and #todo-{second}, not `#todo-{first}`\n\n\
+         - [x] Done\n\n<script>alert(1)</script>\n\n[click](javascript:alert(1))"
+    );
+
+    assert_eq!(references(&notes), [first, second]);
+
+    // References are named after their todos once read
+    let html = render(&notes);
+    for id in [first, second] {
+        assert!(html.contains(&format!(
+            "<a href=\"/todos/{id}\" rel=\"noopener noreferrer\">#todo-{id}</a>"
+        )));
+    }
+    assert!(html.contains(&format!("<code>#todo-{first}</code>")));
+    assert!(html.contains("<input"));
+    assert!(html.contains("type=\"checkbox\""));
+    assert!(!html.contains("script"));
+    assert!(!html.contains("javascript"));
+}
+
+#[test]
+fn test_remap() {
+    let (original, copy, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let notes = format!("After #todo-{original} and #todo-{other}, not #todo-123");
+
+    assert_eq!(
+        remap(&notes, &HashMap::from([(original, copy)])),
+        format!("After #todo-{copy} and #todo-{other}, not #todo-123")
+    );
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "needs a Postgres database in DATABASE_URL"]
+async fn test_save_unrendered(db: PgPool) {
+    let user = register(&db, "alice").await;
+    let dto: CreateTodo = serde_json::from_value(json!({ "title": "Trip" })).unwrap();
+    let todo = Todo::create(&db, user.id, &dto).await.unwrap();
+    // Notes as they were written before they were rendered
+    sqlx::query("UPDATE todos SET notes = '- [x] **Pack**' WHERE id = $1")
+        .bind(todo.id)
+        .execute(&db)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM undo_operations")
+        .execute(&db)
+        .await
+        .unwrap();
+
+    let mut txn = db.begin().await.unwrap();
+    assert_eq!(save_unrendered(&mut txn, 10).await.unwrap(), 1);
+    assert_eq!(save_unrendered(&mut txn, 10).await.unwrap(), 0);
+    txn.commit().await.unwrap();
+
+    let todo = Todo::find_by_id(&db, user.id, todo.id).await.unwrap();
+    assert!(todo.notes_html.unwrap().contains("<strong>Pack</strong>"));
+
+    let items = ChecklistItem::find_by_todo(&db, user.id, todo.id)
+        .await
+        .unwrap();
+    let items: Vec<_> = items
+        .iter()
+        .map(|item| (item.text.as_str(), item.checked))
+        .collect();
+    assert_eq!(items, [("Pack", true)]);
+
+    let operations: i64 = sqlx::query_scalar("SELECT count(*) FROM undo_operations")
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(operations, 0);
+}
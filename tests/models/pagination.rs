@@ -0,0 +1,35 @@
+use todos::models::pagination::Cursor;
+use uuid::Uuid;
+
+fn cursor() -> Cursor {
+    Cursor {
+        scope: "todos".into(),
+        key: "a0".into(),
+        id: Uuid::new_v4(),
+        before: false,
+    }
+}
+
+#[test]
+fn test_cursor_round_trip() {
+    let cursor = cursor();
+    let encoded = cursor.encode("secret");
+
+    assert_eq!(Cursor::decode(&encoded, "secret").unwrap(), cursor);
+}
+
+#[test]
+fn test_cursor_rejects_tampering() {
+    let encoded = cursor().encode("secret");
+    let (_, signature) = encoded.split_once('.').unwrap();
+    let forged = Cursor {
+        before: true,
+        ..cursor()
+    }
+    .encode("secret");
+    let (payload, _) = forged.split_once('.').unwrap();
+
+    assert!(Cursor::decode(&encoded, "other").is_err());
+    assert!(Cursor::decode(&format!("{payload}.{signature}"), "secret").is_err());
+    assert!(Cursor::decode("garbage", "secret").is_err());
+}
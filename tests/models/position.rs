@@ -0,0 +1,133 @@
+use todos::models::position::{append, between, place, spread, Placement, MAX_LEN};
+use uuid::Uuid;
+
+#[test]
+fn test_between_sorts_strictly_between_bounds() {
+    let cases = [
+        (None, None),
+        (Some("V"), None),
+        (None, Some("V")),
+        (Some("V"), Some("W")),
+        (Some("z"), None),
+        (Some("A"), Some("A1")),
+        (Some("Az"), Some("B")),
+    ];
+
+    for (lower, upper) in cases {
+        let key = between(lower, upper).unwrap();
+
+        assert!(
+            lower.is_none_or(|lower| lower < key.as_str()),
+            "{lower:?} < {key}"
+        );
+        assert!(
+            upper.is_none_or(|upper| key.as_str() < upper),
+            "{key} < {upper:?}"
+        );
+        assert!(!key.ends_with('0'));
+    }
+}
+
+#[test]
+fn test_between_rejects_unordered_or_invalid_keys() {
+    assert!(between(Some("W"), Some("V")).is_err());
+    assert!(between(Some("V"), Some("V")).is_err());
+    assert!(between(Some("V0"), None).is_err());
+    assert!(between(Some("V-"), None).is_err());
+}
+
+#[test]
+fn test_appends_stay_short() {
+    let mut keys: Vec<String> = Vec::new();
+    let mut rebalances = 0;
+
+    for _ in 0..1000 {
+        match append(keys.last().map(String::as_str)) {
+            Some(key) => keys.push(key),
+            None => {
+                // What `rebalance` writes: the siblings spread out, with the appended key last
+                keys = spread(keys.len() + 1);
+                rebalances += 1;
+            }
+        }
+
+        assert!(keys.iter().all(|key| key.len() <= MAX_LEN));
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    assert_eq!(keys.len(), 1000);
+    assert!(rebalances > 0);
+    assert_eq!(append(Some("V0")), None);
+}
+
+#[test]
+fn test_spread_is_ordered_and_short() {
+    for count in [1, 2, 61, 62, 1000] {
+        let keys = spread(count);
+
+        assert_eq!(keys.len(), count);
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(keys.iter().all(|key| !key.ends_with('0') && key.len() <= 3));
+    }
+}
+
+#[test]
+fn test_place_between_neighbours() {
+    let (a, b, moved) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let siblings = vec![(a, "A".to_string()), (b, "C".to_string())];
+
+    assert_eq!(
+        place(&siblings, moved, Some(a), None).unwrap(),
+        Placement::Single("B".into())
+    );
+    assert_eq!(
+        place(&siblings, moved, Some(a), Some(b)).unwrap(),
+        Placement::Single("B".into())
+    );
+    assert!(matches!(
+        place(&siblings, moved, None, Some(a)).unwrap(),
+        Placement::Single(key) if key.as_str() < "A"
+    ));
+    assert!(matches!(
+        place(&siblings, moved, None, None).unwrap(),
+        Placement::Single(key) if key.as_str() > "C"
+    ));
+}
+
+#[test]
+fn test_place_rejects_bad_neighbours() {
+    let (a, b, c, moved) = (
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+    );
+    let siblings = vec![
+        (a, "A".to_string()),
+        (b, "B".to_string()),
+        (c, "C".to_string()),
+    ];
+
+    assert!(place(&siblings, moved, Some(a), Some(c)).is_err());
+    assert!(place(&siblings, moved, Some(Uuid::new_v4()), None).is_err());
+}
+
+#[test]
+fn test_place_rebalances_ties_and_long_keys() {
+    let (a, b, moved) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    let tied = vec![(a, "V".to_string()), (b, "V".to_string())];
+    let Placement::Rebalance(keys) = place(&tied, moved, Some(a), None).unwrap() else {
+        panic!("expected a rebalance");
+    };
+    let order: Vec<Uuid> = keys.iter().map(|(id, _)| *id).collect();
+    assert_eq!(order, vec![a, moved, b]);
+    assert!(keys.windows(2).all(|pair| pair[0].1 < pair[1].1));
+
+    let long = "V".repeat(MAX_LEN);
+    let crowded = vec![(a, long.clone()), (b, format!("{long}1"))];
+    assert!(matches!(
+        place(&crowded, moved, Some(a), Some(b)).unwrap(),
+        Placement::Rebalance(_)
+    ));
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, TimeZone};
+use chrono_tz::{Europe::Berlin, Tz};
+use todos::models::{
+    quick::{parse, Parsed},
+    todos::Priority,
+};
+
+/// Wednesday, 10:00 in Berlin.
+fn now() -> DateTime<Tz> {
+    Berlin.with_ymd_and_hms(2025, 5, 14, 10, 0, 0).unwrap()
+}
+
+fn due(parsed: &Parsed) -> String {
+    parsed
+        .due_at
+        .map(|due| due.format("%Y-%m-%d %H:%M %:z").to_string())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_parse_recurrence_tags_and_priority() {
+    let parsed = parse("Pay rent every month on the 1st #finance !high", now());
+
+    assert_eq!(parsed.title, "Pay rent");
+    assert_eq!(parsed.rrule.as_deref(), Some("FREQ=MONTHLY;BYMONTHDAY=1"));
+    assert_eq!(due(&parsed), "2025-06-01 23:59 +02:00");
+    assert_eq!(parsed.tags, ["finance"]);
+    assert_eq!(parsed.priority, Some(Priority::High));
+}
+
+#[test]
+fn test_parse_dates_and_times() {
+    let cases = [
+        (
+            "Call mom tomorrow at 5pm",
+            "Call mom",
+            "2025-05-15 17:00 +02:00",
+        ),
+        ("Standup at 9:30", "Standup", "2025-05-15 09:30 +02:00"),
+        ("Report friday", "Report", "2025-05-16 23:59 +02:00"),
+        ("Review on wed", "Review", "2025-05-21 23:59 +02:00"),
+        ("Taxes jan 31st", "Taxes", "2026-01-31 23:59 +01:00"),
+        ("Trip 1 june 2026 noon", "Trip", "2026-06-01 12:00 +02:00"),
+        (
+            "Check oven in 90 minutes",
+            "Check oven",
+            "2025-05-14 11:30 +02:00",
+        ),
+        ("Renew in 2 weeks", "Renew", "2025-05-28 23:59 +02:00"),
+    ];
+
+    for (text, title, expected) in cases {
+        let parsed = parse(text, now());
+        assert_eq!(parsed.title, title, "{text}");
+        assert_eq!(due(&parsed), expected, "{text}");
+    }
+}
+
+#[test]
+fn test_parse_weekly_recurrence_starts_on_first_occurrence() {
+    let parsed = parse("Gym every monday and thursday at 7am @health", now());
+
+    assert_eq!(parsed.title, "Gym");
+    assert_eq!(parsed.rrule.as_deref(), Some("FREQ=WEEKLY;BYDAY=MO,TH"));
+    assert_eq!(due(&parsed), "2025-05-15 07:00 +02:00");
+    assert_eq!(parsed.list.as_deref(), Some("health"));
+}
+
+#[test]
+fn test_parse_keeps_other_words_in_title() {
+    let parsed = parse("Watch the sun set at home \"tomorrow\" #", now());
+
+    assert_eq!(parsed.title, "Watch the sun set at home tomorrow #");
+    assert_eq!(parsed.due_at, None);
+    assert_eq!(parsed.rrule, None);
+    assert!(parsed.tags.is_empty());
+}
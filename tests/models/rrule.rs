@@ -0,0 +1,200 @@
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use chrono_tz::{America::New_York, Europe::Berlin, Tz};
+use todos::models::rrule::RRule;
+
+fn local(tz: Tz, s: &str) -> DateTime<Tz> {
+    let naive = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+    tz.from_local_datetime(&naive).earliest().unwrap()
+}
+
+fn dates(rule: &str, dtstart: DateTime<Tz>, take: usize) -> Vec<NaiveDate> {
+    let rule: RRule = rule.parse().unwrap();
+
+    rule.occurrences(dtstart)
+        .take(take)
+        .map(|occurrence| occurrence.with_timezone(&dtstart.timezone()).date_naive())
+        .collect()
+}
+
+fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[test]
+fn test_daily_with_interval() {
+    let start = local(Berlin, "2025-03-01 09:00");
+
+    assert_eq!(
+        dates("FREQ=DAILY;INTERVAL=2", start, 3),
+        vec![ymd(2025, 3, 1), ymd(2025, 3, 3), ymd(2025, 3, 5)]
+    );
+}
+
+#[test]
+fn test_weekly_by_weekday() {
+    // 2025-03-03 is a Monday
+    let start = local(Berlin, "2025-03-03 18:00");
+
+    assert_eq!(
+        dates("RRULE:FREQ=WEEKLY;BYDAY=MO,TH", start, 4),
+        vec![
+            ymd(2025, 3, 3),
+            ymd(2025, 3, 6),
+            ymd(2025, 3, 10),
+            ymd(2025, 3, 13)
+        ]
+    );
+}
+
+#[test]
+fn test_monthly_by_month_day_skips_short_months() {
+    let start = local(Berlin, "2025-01-31 08:00");
+
+    assert_eq!(
+        dates("FREQ=MONTHLY;BYMONTHDAY=31", start, 3),
+        vec![ymd(2025, 1, 31), ymd(2025, 3, 31), ymd(2025, 5, 31)]
+    );
+    assert_eq!(
+        dates("FREQ=MONTHLY;BYMONTHDAY=-1", start, 3),
+        vec![ymd(2025, 1, 31), ymd(2025, 2, 28), ymd(2025, 3, 31)]
+    );
+}
+
+#[test]
+fn test_monthly_by_nth_weekday() {
+    let start = local(New_York, "2025-01-14 10:00");
+
+    assert_eq!(
+        dates("FREQ=MONTHLY;BYDAY=2TU", start, 3),
+        vec![ymd(2025, 1, 14), ymd(2025, 2, 11), ymd(2025, 3, 11)]
+    );
+    assert_eq!(
+        dates("FREQ=MONTHLY;BYDAY=-1FR", start, 3),
+        vec![ymd(2025, 1, 14), ymd(2025, 1, 31), ymd(2025, 2, 28)]
+    );
+}
+
+#[test]
+fn test_count_includes_dtstart() {
+    let start = local(Berlin, "2025-03-01 09:00");
+
+    assert_eq!(dates("FREQ=DAILY;COUNT=3", start, 10).len(), 3);
+}
+
+#[test]
+fn test_until_is_inclusive() {
+    let start = local(Berlin, "2025-03-01 09:00");
+
+    assert_eq!(
+        dates("FREQ=WEEKLY;UNTIL=20250315", start, 10),
+        vec![ymd(2025, 3, 1), ymd(2025, 3, 8), ymd(2025, 3, 15)]
+    );
+    assert_eq!(
+        dates("FREQ=WEEKLY;UNTIL=20250315T075959Z", start, 10),
+        vec![ymd(2025, 3, 1), ymd(2025, 3, 8)]
+    );
+}
+
+#[test]
+fn test_wall_clock_time_is_kept_across_dst() {
+    let rule: RRule = "FREQ=DAILY".parse().unwrap();
+    // DST starts in New York on 2025-03-09
+    let start = local(New_York, "2025-03-08 09:00");
+    let occurrences: Vec<DateTime<Utc>> = rule.occurrences(start).take(2).collect();
+
+    assert_eq!(
+        occurrences[0],
+        Utc.with_ymd_and_hms(2025, 3, 8, 14, 0, 0).unwrap()
+    );
+    assert_eq!(
+        occurrences[1],
+        Utc.with_ymd_and_hms(2025, 3, 9, 13, 0, 0).unwrap()
+    );
+}
+
+#[test]
+fn test_nonexistent_local_time_moves_forward() {
+    let rule: RRule = "FREQ=DAILY".parse().unwrap();
+    let start = local(New_York, "2025-03-08 02:30");
+    let next = rule
+        .next_after(start, start.with_timezone(&Utc))
+        .unwrap()
+        .with_timezone(&New_York);
+
+    assert_eq!(
+        next.format("%Y-%m-%d %H:%M").to_string(),
+        "2025-03-09 03:30"
+    );
+}
+
+#[test]
+fn test_ambiguous_local_time_uses_first_instance() {
+    let rule: RRule = "FREQ=DAILY".parse().unwrap();
+    // DST ends in New York on 2025-11-02, 01:30 happens twice
+    let start = local(New_York, "2025-11-01 01:30");
+    let next = rule.next_after(start, start.with_timezone(&Utc)).unwrap();
+
+    assert_eq!(next, Utc.with_ymd_and_hms(2025, 11, 2, 5, 30, 0).unwrap());
+}
+
+#[test]
+fn test_next_after_skips_past_occurrences() {
+    let rule: RRule = "FREQ=WEEKLY".parse().unwrap();
+    let start = local(Berlin, "2025-03-03 09:00");
+    let after = local(Berlin, "2025-03-12 12:00").with_timezone(&Utc);
+
+    let next = rule
+        .next_after(start, after)
+        .unwrap()
+        .with_timezone(&Berlin);
+
+    assert_eq!(next.date_naive(), ymd(2025, 3, 17));
+}
+
+#[test]
+fn test_parse_round_trip() {
+    let rule: RRule = "freq=monthly;interval=2;byday=-1fr;count=4"
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        rule.to_string(),
+        "FREQ=MONTHLY;INTERVAL=2;COUNT=4;BYDAY=-1FR"
+    );
+}
+
+#[test]
+fn test_parse_rejects_invalid_rules() {
+    for rule in [
+        "INTERVAL=2",
+        "FREQ=HOURLY",
+        "FREQ=DAILY;COUNT=2;UNTIL=20250101",
+        "FREQ=WEEKLY;BYDAY=2MO",
+        "FREQ=WEEKLY;BYMONTHDAY=3",
+        "FREQ=MONTHLY;BYDAY=XX",
+        "FREQ=WEEKLY;BYDAY=éX",
+        "FREQ=WEEKLY;BYDAY=MOé",
+        "FREQ=DAILY;INTERVAL=0",
+        "FREQ=WEEKLY;INTERVAL=20000000",
+        "FREQ=DAILY;BYSETPOS=1",
+    ] {
+        assert!(rule.parse::<RRule>().is_err(), "{rule} should be rejected");
+    }
+}
+
+#[test]
+fn test_series_ends_at_the_last_representable_date() {
+    let start = local(Berlin, "2025-03-03 09:00");
+
+    for rule in [
+        "FREQ=DAILY;INTERVAL=1000",
+        "FREQ=WEEKLY;INTERVAL=1000;BYDAY=MO,SU",
+        "FREQ=MONTHLY;INTERVAL=1000;BYDAY=-1SU",
+        "FREQ=YEARLY;INTERVAL=1000",
+    ] {
+        let rule: RRule = rule.parse().unwrap();
+
+        let last = rule.occurrences(start).last().unwrap();
+        assert!(last.year() > 200_000, "{rule} ended in {}", last.year());
+    }
+}
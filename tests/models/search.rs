@@ -0,0 +1,27 @@
+use todos::models::search::{highlight, prefix_query};
+
+#[test]
+fn test_prefix_query() {
+    assert_eq!(prefix_query("gro").as_deref(), Some("'gro':*"));
+    assert_eq!(
+        prefix_query("  buy  mil").as_deref(),
+        Some("'buy':* & 'mil':*")
+    );
+    // Query operators and quotes only separate words
+    assert_eq!(
+        prefix_query("a'b & !c:*").as_deref(),
+        Some("'a':* & 'b':* & 'c':*")
+    );
+    assert_eq!(prefix_query("caf\u{e9}").as_deref(), Some("'caf\u{e9}':*"));
+
+    assert_eq!(prefix_query(""), None);
+    assert_eq!(prefix_query(" & | "), None);
+}
+
+#[test]
+fn test_highlight_escapes_html() {
+    assert_eq!(
+        highlight("<b>\u{2}milk\u{3}</b> & eggs"),
+        "&lt;b&gt;<mark>milk</mark>&lt;/b&gt; &amp; eggs"
+    );
+}
@@ -0,0 +1,44 @@
+use serde_json::json;
+use sqlx::PgPool;
+use todos::models::{
+    settings::{Settings, UpdateSettings},
+    todos::{CreateTodo, Todo},
+};
+
+use super::register;
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "needs a Postgres database in DATABASE_URL"]
+async fn test_new_search_language_leaves_nothing_to_undo(db: PgPool) {
+    let user = register(&db, "alice").await;
+    let dto: CreateTodo = serde_json::from_value(json!({ "title": "Buy milk" })).unwrap();
+    let todo = Todo::create(&db, user.id, &dto).await.unwrap();
+
+    let operations = || async {
+        sqlx::query_scalar::<_, i64>("SELECT count(*) FROM undo_operations")
+            .fetch_one(&db)
+            .await
+            .unwrap()
+    };
+    let before = operations().await;
+
+    let dto = UpdateSettings {
+        search_language: Some("simple".into()),
+        ..Default::default()
+    };
+    Settings::update(&db, user.id, &dto, None).await.unwrap();
+
+    assert_eq!(operations().await, before);
+
+    let language: String =
+        sqlx::query_scalar("SELECT search_language::text FROM todos WHERE user_id = $1")
+            .bind(user.id)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+    assert_eq!(language, "simple");
+
+    // Nor is there anything new for clients to see
+    let reindexed = Todo::find_by_id(&db, user.id, todo.id).await.unwrap();
+    assert_eq!(reindexed.version, todo.version);
+}
@@ -0,0 +1,23 @@
+use std::collections::HashMap;
+
+use todos::models::templates::{placeholders, substitute};
+
+#[test]
+fn test_placeholders() {
+    assert_eq!(
+        placeholders("Welcome {{name}} to {{ team }}, {{name}}"),
+        ["name", "team", "name"]
+    );
+    assert!(placeholders("{{}} {{two words}} {{open").is_empty());
+}
+
+#[test]
+fn test_substitute() {
+    let variables = HashMap::from([("name".to_string(), "Ada".to_string())]);
+
+    assert_eq!(
+        substitute("Laptop for {{ name }} {{not closed", &variables).unwrap(),
+        "Laptop for Ada {{not closed"
+    );
+    assert!(substitute("Desk for {{team}}", &variables).is_err());
+}
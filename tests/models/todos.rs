@@ -0,0 +1,187 @@
+use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::json;
+use sqlx::PgPool;
+use todos::models::{
+    checklists::ChecklistItem,
+    lists::{CreateList, List},
+    todos::{
+        normalize_tag, normalize_tags, snooze_start, validate_due, CompleteScope, CreateTodo,
+        SnoozeUntil, Todo, UpdateTodo,
+    },
+};
+use uuid::Uuid;
+
+use super::register;
+
+#[test]
+fn test_normalize_tag() {
+    assert_eq!(normalize_tag(" #Work ").unwrap(), "work");
+    assert_eq!(normalize_tag("errands").unwrap(), "errands");
+
+    assert!(normalize_tag("").is_err());
+    assert!(normalize_tag("#").is_err());
+    assert!(normalize_tag("two words").is_err());
+}
+
+#[test]
+fn test_normalize_tags_drops_duplicates() {
+    assert_eq!(
+        normalize_tags(&["Work", "#work", "home"]).unwrap(),
+        vec!["work", "home"]
+    );
+}
+
+#[test]
+fn test_recurring_todos_keep_their_due_date() {
+    let due_at = Utc
+        .with_ymd_and_hms(2025, 7, 16, 9, 0, 0)
+        .unwrap()
+        .fixed_offset();
+    let series = Some(Uuid::new_v4());
+
+    assert!(validate_due(series, Some(due_at)).is_ok());
+    assert!(validate_due(None, None).is_ok());
+    assert!(validate_due(series, None).is_err());
+}
+
+#[test]
+fn test_snooze_start() {
+    let tz: Tz = "Europe/Berlin".parse().unwrap();
+    // A Wednesday
+    let now = tz.with_ymd_and_hms(2025, 7, 16, 22, 30, 0).unwrap();
+    let start = |until| snooze_start(until, None, now).unwrap();
+
+    assert_eq!(
+        start(SnoozeUntil::LaterToday),
+        Utc.with_ymd_and_hms(2025, 7, 16, 23, 30, 0).unwrap()
+    );
+    assert_eq!(
+        start(SnoozeUntil::Tomorrow),
+        Utc.with_ymd_and_hms(2025, 7, 17, 7, 0, 0).unwrap()
+    );
+    assert_eq!(
+        start(SnoozeUntil::NextWeek),
+        Utc.with_ymd_and_hms(2025, 7, 21, 7, 0, 0).unwrap()
+    );
+
+    assert!(snooze_start(SnoozeUntil::Custom, None, now).is_err());
+    assert!(snooze_start(SnoozeUntil::Custom, Some(now.fixed_offset()), now).is_err());
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "needs a Postgres database in DATABASE_URL"]
+async fn test_next_occurrence_starts_over_with_the_checklist(db: PgPool) {
+    let user = register(&db, "alice").await;
+    let dto: CreateTodo = serde_json::from_value(json!({
+        "title": "Water the plants",
+        "notes": "- [x] Balcony\n- [ ] Kitchen\n",
+        "dueAt": "2025-07-16T09:00:00Z",
+        "recurrence": { "rrule": "FREQ=WEEKLY", "timezone": "UTC" },
+    }))
+    .unwrap();
+    let todo = Todo::create(&db, user.id, &dto).await.unwrap();
+
+    let completion = Todo::complete(&db, user.id, todo.id, CompleteScope::Occurrence)
+        .await
+        .unwrap();
+    let next = completion.next.unwrap();
+    assert_eq!(
+        next.notes.as_deref(),
+        Some("- [ ] Balcony\n- [ ] Kitchen\n")
+    );
+
+    let items = ChecklistItem::find_by_todo(&db, user.id, next.id)
+        .await
+        .unwrap();
+    let texts: Vec<_> = items
+        .iter()
+        .map(|item| (item.text.as_str(), item.checked))
+        .collect();
+    assert_eq!(texts, [("Balcony", false), ("Kitchen", false)]);
+
+    // Changing the checklist keeps the task list of the notes
+    ChecklistItem::toggle(&db, user.id, next.id, items[0].id)
+        .await
+        .unwrap();
+    let next = Todo::find_by_id(&db, user.id, next.id).await.unwrap();
+    assert_eq!(
+        next.notes.as_deref(),
+        Some("- [x] Balcony\n- [ ] Kitchen\n")
+    );
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "needs a Postgres database in DATABASE_URL"]
+async fn test_unchanged_todo_keeps_its_version(db: PgPool) {
+    let user = register(&db, "alice").await;
+    let dto: CreateTodo = serde_json::from_value(json!({
+        "title": "Pack",
+        "notes": "- [ ] Charger",
+    }))
+    .unwrap();
+    let todo = Todo::create(&db, user.id, &dto).await.unwrap();
+
+    let operations = || async {
+        sqlx::query_scalar::<_, i64>("SELECT count(*) FROM undo_operations")
+            .fetch_one(&db)
+            .await
+            .unwrap()
+    };
+    let before = operations().await;
+
+    let resent: UpdateTodo = serde_json::from_value(json!({
+        "title": "Pack",
+        "notes": "- [ ] Charger",
+    }))
+    .unwrap();
+    let unchanged = Todo::update(&db, user.id, todo.id, &resent, None)
+        .await
+        .unwrap();
+    assert_eq!(unchanged.version, todo.version);
+    assert_eq!(operations().await, before);
+
+    let renamed: UpdateTodo = serde_json::from_value(json!({ "title": "Pack up" })).unwrap();
+    let changed = Todo::update(&db, user.id, todo.id, &renamed, None)
+        .await
+        .unwrap();
+    assert_eq!(changed.version, todo.version + 1);
+    assert_eq!(operations().await, before + 1);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "needs a Postgres database in DATABASE_URL"]
+async fn test_concurrent_appends_get_distinct_positions(db: PgPool) {
+    let user = register(&db, "alice").await;
+    let list = List::create(
+        &db,
+        user.id,
+        &CreateList {
+            name: "Errands".into(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let appends = (0..20).map(|i| {
+        let db = db.clone();
+        let list_id = (i % 2 == 0).then_some(list.id);
+        tokio::spawn(async move {
+            let dto: CreateTodo =
+                serde_json::from_value(json!({ "title": "Errand", "listId": list_id })).unwrap();
+            Todo::create(&db, user.id, &dto).await.unwrap()
+        })
+    });
+    for append in appends.collect::<Vec<_>>() {
+        append.await.unwrap();
+    }
+
+    let duplicates: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM (SELECT 1 FROM todos GROUP BY list_id, position \
+         HAVING count(*) > 1) d",
+    )
+    .fetch_one(&db)
+    .await
+    .unwrap();
+    assert_eq!(duplicates, 0);
+}
@@ -0,0 +1,94 @@
+use std::{path::Path, sync::Mutex};
+
+use async_trait::async_trait;
+use axum::body::Body;
+use chrono::Duration;
+use serde_json::json;
+use sqlx::PgPool;
+use todos::{
+    error::{Error, Result},
+    models::{
+        todos::{CreateTodo, Todo},
+        trash::Trash,
+    },
+    storage::Storage,
+};
+use uuid::Uuid;
+
+use super::register;
+
+/// A storage that fails to delete anything, remembering what it was asked to delete.
+#[derive(Default)]
+struct Failing {
+    deleted: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl Storage for Failing {
+    async fn put(&self, _: &str, _: &Path, _: &str, _: &str) -> Result<()> {
+        unreachable!("nothing is uploaded")
+    }
+
+    async fn get(&self, _: &str) -> Result<Body> {
+        unreachable!("nothing is downloaded")
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.deleted.lock().unwrap().push(key.to_string());
+
+        Err(Error::InternalServerError.into())
+    }
+}
+
+async fn todo_with_attachment(db: &PgPool, user_id: Uuid, checksum: &str) -> Uuid {
+    let dto: CreateTodo = serde_json::from_value(json!({ "title": "Scan" })).unwrap();
+    let todo = Todo::create(db, user_id, &dto).await.unwrap();
+
+    sqlx::query(
+        "INSERT INTO attachments (todo_id, user_id, filename, content_type, size, checksum) \
+         VALUES ($1, $2, 'scan.pdf', 'application/pdf', 1, $3)",
+    )
+    .bind(todo.id)
+    .bind(user_id)
+    .bind(checksum)
+    .execute(db)
+    .await
+    .unwrap();
+
+    todo.id
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "needs a Postgres database in DATABASE_URL"]
+async fn test_purge_expired_deletes_contents_after_commit(db: PgPool) {
+    let user = register(&db, "alice").await;
+    let (shared, own) = ("a".repeat(64), "b".repeat(64));
+    let kept = todo_with_attachment(&db, user.id, &shared).await;
+    let expired = [
+        todo_with_attachment(&db, user.id, &shared).await,
+        todo_with_attachment(&db, user.id, &own).await,
+        todo_with_attachment(&db, user.id, &own).await,
+    ];
+    sqlx::query("UPDATE todos SET deleted_at = now() - interval '40 days' WHERE id = ANY($1)")
+        .bind(&expired[..])
+        .execute(&db)
+        .await
+        .unwrap();
+
+    let storage = Failing::default();
+    let purged = Trash::purge_expired(&db, &storage, Duration::days(30), 1)
+        .await
+        .unwrap();
+    assert_eq!(purged, 3);
+
+    // Only the contents no todo uses any more, and failing to delete them loses nothing
+    let deleted = storage.deleted.lock().unwrap().clone();
+    assert_eq!(deleted.len(), 1);
+    assert!(deleted[0].ends_with(&own));
+
+    let left: Vec<Uuid> = sqlx::query_scalar("SELECT todo_id FROM attachments")
+        .fetch_all(&db)
+        .await
+        .unwrap();
+    assert_eq!(left, [kept]);
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde_json::{json, Value};
+use todos::models::{
+    todos::Todo,
+    urgency::{score, Coefficients},
+};
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 5, 1, 12, 0, 0).unwrap()
+}
+
+fn todo(changes: Value) -> Todo {
+    let mut todo = json!({
+        "id": "00000000-0000-0000-0000-000000000001",
+        "userId": "00000000-0000-0000-0000-000000000002",
+        "title": "Write report",
+        "notes": null,
+        "notesHtml": null,
+        "dueAt": null,
+        "startAt": null,
+        "notifyOnStart": false,
+        "completedAt": null,
+        "seriesId": null,
+        "listId": null,
+        "position": "a0",
+        "statusId": null,
+        "tags": [],
+        "priority": null,
+        "estimateMinutes": null,
+        "fields": {},
+        "version": 1,
+        "createdAt": now(),
+        "updatedAt": now(),
+        "deletedAt": null,
+        "blocked": false,
+        "checklistChecked": 0,
+        "checklistTotal": 0
+    });
+    for (field, value) in changes.as_object().unwrap() {
+        todo[field] = value.clone();
+    }
+
+    serde_json::from_value(todo).unwrap()
+}
+
+fn factors(todo: &Todo, coefficients: &Coefficients) -> Vec<(String, f64)> {
+    score(todo, coefficients, now())
+        .terms
+        .into_iter()
+        .map(|term| (term.factor, (term.score * 1000.0).round() / 1000.0))
+        .collect()
+}
+
+#[test]
+fn test_score() {
+    let coefficients = Coefficients::default();
+
+    assert_eq!(score(&todo(json!({})), &coefficients, now()).score, 0.0);
+    assert_eq!(
+        factors(
+            &todo(json!({
+                "dueAt": now() + Duration::days(7),
+                "createdAt": now() - Duration::days(73),
+                "priority": "high",
+                "tags": ["work", "next"],
+                "blocked": true
+            })),
+            &coefficients
+        ),
+        vec![
+            ("due".into(), 5.6),
+            ("age".into(), 0.4),
+            ("priority".into(), 6.0),
+            ("tags".into(), 0.9),
+            ("blocked".into(), -5.0),
+        ]
+    );
+
+    // Overdue by a week or more counts fully, and completed todos not at all
+    let overdue = json!({ "dueAt": now() - Duration::days(30) });
+    assert_eq!(
+        factors(&todo(overdue), &coefficients),
+        vec![("due".into(), 12.0)]
+    );
+    assert_eq!(
+        score(
+            &todo(json!({ "dueAt": now(), "completedAt": now() })),
+            &coefficients,
+            now()
+        )
+        .terms,
+        vec![]
+    );
+
+    let coefficients = Coefficients {
+        tags: 0.0,
+        tag: [("next".to_string(), 15.0)].into(),
+        ..Default::default()
+    };
+    assert_eq!(
+        factors(&todo(json!({ "tags": ["next"] })), &coefficients),
+        vec![("tag:next".into(), 15.0)]
+    );
+}
@@ -0,0 +1,73 @@
+use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::{json, Value};
+use todos::models::{
+    todos::Todo,
+    views::{group, GroupBy},
+};
+
+fn todo(changes: Value) -> Todo {
+    let now = Utc.with_ymd_and_hms(2025, 5, 1, 12, 0, 0).unwrap();
+    let mut todo = json!({
+        "id": "00000000-0000-0000-0000-000000000001",
+        "userId": "00000000-0000-0000-0000-000000000002",
+        "title": "Write report",
+        "notes": null,
+        "notesHtml": null,
+        "dueAt": null,
+        "startAt": null,
+        "notifyOnStart": false,
+        "completedAt": null,
+        "seriesId": null,
+        "listId": null,
+        "position": "a0",
+        "statusId": null,
+        "tags": [],
+        "priority": null,
+        "estimateMinutes": null,
+        "fields": {},
+        "version": 1,
+        "createdAt": now,
+        "updatedAt": now,
+        "deletedAt": null,
+        "blocked": false,
+        "checklistChecked": 0,
+        "checklistTotal": 0
+    });
+    for (field, value) in changes.as_object().unwrap() {
+        todo[field] = value.clone();
+    }
+
+    serde_json::from_value(todo).unwrap()
+}
+
+#[test]
+fn test_group_by_due_day_in_timezone() {
+    let todos = vec![
+        // 23:00 on the 5th in Berlin
+        todo(json!({ "title": "Late", "dueAt": "2025-05-05T21:00:00Z" })),
+        todo(json!({ "title": "Early", "dueAt": "2025-05-05T08:00:00Z" })),
+        todo(json!({ "title": "Next day", "dueAt": "2025-05-05T23:30:00Z" })),
+        todo(json!({ "title": "Someday" })),
+    ];
+
+    let groups: Vec<_> = group(todos, Some(GroupBy::Due), Tz::Europe__Berlin)
+        .into_iter()
+        .map(|group| {
+            let titles: Vec<_> = group.todos.iter().map(|todo| todo.title.clone()).collect();
+            (group.key, titles)
+        })
+        .collect();
+
+    assert_eq!(
+        groups,
+        [
+            (
+                Some("2025-05-05".to_string()),
+                vec!["Late".to_string(), "Early".into()]
+            ),
+            (Some("2025-05-06".to_string()), vec!["Next day".to_string()]),
+            (None, vec!["Someday".to_string()]),
+        ]
+    );
+}
@@ -0,0 +1,36 @@
+use todos::storage::{local::LocalStorage, Storage};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_local_storage_round_trip() {
+    let root = std::env::temp_dir().join(format!("todos-storage-{}", Uuid::new_v4()));
+    let source = std::env::temp_dir().join(format!("todos-source-{}", Uuid::new_v4()));
+    std::fs::write(&source, b"hello world").unwrap();
+
+    let storage = LocalStorage::new(&root);
+
+    storage
+        .put("blobs/ab/abc", &source, "text/plain", "abc")
+        .await
+        .unwrap();
+
+    let body = storage.get("blobs/ab/abc").await.unwrap();
+    let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+    assert_eq!(&bytes[..], b"hello world");
+
+    storage.delete("blobs/ab/abc").await.unwrap();
+    // Deleting twice is not an error
+    storage.delete("blobs/ab/abc").await.unwrap();
+    assert!(storage.get("blobs/ab/abc").await.is_err());
+
+    std::fs::remove_dir_all(&root).unwrap();
+    std::fs::remove_file(&source).unwrap();
+}
+
+#[tokio::test]
+async fn test_local_storage_rejects_keys_outside_root() {
+    let storage = LocalStorage::new(std::env::temp_dir().join("todos-storage"));
+
+    assert!(storage.get("../etc/passwd").await.is_err());
+    assert!(storage.delete("blobs//abc").await.is_err());
+}
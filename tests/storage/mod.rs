@@ -0,0 +1,2 @@
+mod local;
+mod s3;
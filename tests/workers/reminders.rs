@@ -0,0 +1,168 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use todos::{
+    config::{notifications::NotificationsConfig, scheduler::SchedulerConfig},
+    error::{Error, Result},
+    models::reminders::DueReminder,
+    notifications::{Channel, Notifiers},
+    workers::reminders::{deliver, Outbox},
+};
+use uuid::Uuid;
+
+/// An outbox whose marks of one reminder fail.
+struct Flaky {
+    due: Vec<DueReminder>,
+    failing: Uuid,
+    sent: Mutex<Vec<Uuid>>,
+}
+
+#[async_trait]
+impl Outbox for Flaky {
+    async fn claim(&self, _: &SchedulerConfig, _: Uuid) -> Result<Vec<DueReminder>> {
+        Ok(self.due.clone())
+    }
+
+    async fn renew(&self, _: Uuid, _: Uuid) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn mark_sent(&self, id: Uuid) -> Result<()> {
+        if id == self.failing {
+            return Err(Error::EntityNotFound.into());
+        }
+        self.sent.lock().unwrap().push(id);
+
+        Ok(())
+    }
+
+    async fn mark_failed(&self, _: Uuid, _: &str, _: DateTime<Utc>) -> Result<()> {
+        unreachable!("the log channel always delivers")
+    }
+}
+
+/// An outbox shared with another scheduler, where each delivery takes `delivery` seconds. Once
+/// the claim of a reminder has run out, the other scheduler takes it over.
+struct Slow {
+    due: Vec<DueReminder>,
+    lease: i64,
+    delivery: i64,
+    clock: Mutex<i64>,
+    claims: Mutex<HashMap<Uuid, (Uuid, i64)>>,
+    sent: Mutex<Vec<Uuid>>,
+    taken: Mutex<Vec<Uuid>>,
+}
+
+#[async_trait]
+impl Outbox for Slow {
+    async fn claim(&self, _: &SchedulerConfig, token: Uuid) -> Result<Vec<DueReminder>> {
+        let now = *self.clock.lock().unwrap();
+        let mut claims = self.claims.lock().unwrap();
+        for reminder in &self.due {
+            claims.insert(reminder.id, (token, now));
+        }
+
+        Ok(self.due.clone())
+    }
+
+    async fn renew(&self, id: Uuid, token: Uuid) -> Result<bool> {
+        let now = *self.clock.lock().unwrap();
+        let mut claims = self.claims.lock().unwrap();
+        let claim = claims.get_mut(&id).unwrap();
+
+        if claim.0 != token {
+            return Ok(false);
+        }
+        if now - claim.1 >= self.lease {
+            *claim = (Uuid::new_v4(), now);
+            self.taken.lock().unwrap().push(id);
+            return Ok(false);
+        }
+        claim.1 = now;
+
+        Ok(true)
+    }
+
+    async fn mark_sent(&self, id: Uuid) -> Result<()> {
+        *self.clock.lock().unwrap() += self.delivery;
+        self.sent.lock().unwrap().push(id);
+
+        Ok(())
+    }
+
+    async fn mark_failed(&self, _: Uuid, _: &str, _: DateTime<Utc>) -> Result<()> {
+        unreachable!("the log channel always delivers")
+    }
+}
+
+fn notifiers() -> Notifiers {
+    Notifiers::new(&NotificationsConfig {
+        email: None,
+        webhook: None,
+    })
+    .unwrap()
+}
+
+fn reminder() -> DueReminder {
+    DueReminder {
+        id: Uuid::new_v4(),
+        channel: Channel::Log,
+        attempts: 1,
+        todo_id: Uuid::new_v4(),
+        title: "Water the plants".into(),
+        due_at: None,
+        user_id: Uuid::new_v4(),
+        username: "alice".into(),
+        email: "alice@example.com".into(),
+    }
+}
+
+#[tokio::test]
+async fn test_failed_mark_keeps_the_rest_of_the_batch() {
+    let due: Vec<DueReminder> = (0..3).map(|_| reminder()).collect();
+    let outbox = Flaky {
+        failing: due[1].id,
+        due: due.clone(),
+        sent: Mutex::new(Vec::new()),
+    };
+    let notifiers = notifiers();
+    let scheduler = SchedulerConfig {
+        interval: 1,
+        batch_size: 10,
+        max_attempts: 5,
+        lease: 60,
+    };
+
+    let processed = deliver(&outbox, &notifiers, &scheduler).await.unwrap();
+
+    // The reminders around the failed mark stay sent; that one waits for its claim to run out
+    assert_eq!(processed, 3);
+    assert_eq!(*outbox.sent.lock().unwrap(), vec![due[0].id, due[2].id]);
+}
+
+#[tokio::test]
+async fn test_delivery_outliving_the_lease_sends_each_reminder_once() {
+    let due: Vec<DueReminder> = (0..3).map(|_| reminder()).collect();
+    let outbox = Slow {
+        due: due.clone(),
+        lease: 300,
+        delivery: 200,
+        clock: Mutex::new(0),
+        claims: Mutex::new(HashMap::new()),
+        sent: Mutex::new(Vec::new()),
+        taken: Mutex::new(Vec::new()),
+    };
+    let scheduler = SchedulerConfig {
+        interval: 1,
+        batch_size: 3,
+        max_attempts: 5,
+        lease: 300,
+    };
+
+    deliver(&outbox, &notifiers(), &scheduler).await.unwrap();
+
+    // The third reminder comes up 400 seconds into the batch, after the other scheduler took it
+    assert_eq!(*outbox.sent.lock().unwrap(), vec![due[0].id, due[1].id]);
+    assert_eq!(*outbox.taken.lock().unwrap(), vec![due[2].id]);
+}